@@ -0,0 +1,12 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        cbindgen::generate(&crate_dir)
+            .expect("failed to generate the C header")
+            .write_to_file(format!("{}/include/ratify.h", crate_dir));
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::{arbitrary::Unstructured, fuzz_target};
+use ratify::{check_clauses, fuzz::Instance, CheckerOptions, Mode};
+
+// The propagators agree on corrupted proofs, which are usually rejected.
+fuzz_target!(|data: &[u8]| {
+    let Ok(instance) = Instance::corrupted(&mut Unstructured::new(data)) else {
+        return;
+    };
+    let verdicts = [Mode::Mutating, Mode::Immutable, Mode::Naive, Mode::HeadTail].map(|mode| {
+        check_clauses(
+            instance.formula.clone(),
+            instance.proof.clone(),
+            CheckerOptions::default().mode(mode),
+        )
+        .unwrap()
+        .verdict
+    });
+    assert!(verdicts.iter().all(|verdict| *verdict == verdicts[0]), "{:?}", verdicts);
+});
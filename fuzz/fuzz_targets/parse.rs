@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ratify::parser::{cnf, drat};
+
+// The parsers have to reject invalid input with an error instead of panicking.
+fuzz_target!(|input: &str| {
+    let _ = cnf::parse(input);
+    let _ = cnf::parse_spanned(input);
+    let _ = drat::parse(input);
+    let _ = drat::parse_spanned(input, None);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ratify::fuzz::roundtrip::{self, Sample};
+
+// Writing a sample in any format and parsing it back gives the same sample. A failing sample is
+// shrunk before it is reported.
+fuzz_target!(|sample: Sample| {
+    if let Err(failure) = roundtrip::check_all(&sample) {
+        let shrunk = roundtrip::shrink(sample, failure.format);
+        match roundtrip::check(&shrunk, failure.format) {
+            Err(failure) => panic!("{}\nshrunk to {:?}", failure, shrunk),
+            Ok(()) => unreachable!(),
+        }
+    }
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ratify::{check_clauses, fuzz::Instance, CheckerOptions, Mode};
+
+// Every generated proof is verified by all propagators.
+fuzz_target!(|instance: Instance| {
+    for mode in [Mode::Mutating, Mode::Immutable, Mode::Naive, Mode::HeadTail] {
+        let report = check_clauses(
+            instance.formula.clone(),
+            instance.proof.clone(),
+            CheckerOptions::default().mode(mode),
+        )
+        .unwrap();
+        assert!(report.verdict.is_verified(), "{:?}: {:?}", mode, report.verdict);
+    }
+});
@@ -0,0 +1,128 @@
+#ifndef RATIFY_H
+#define RATIFY_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The proof has been verified.
+ */
+#define RATIFY_VERIFIED 0
+
+/**
+ * The proof has been checked and rejected.
+ */
+#define RATIFY_REJECTED 1
+
+/**
+ * The input could not be read or parsed, or the options are invalid.
+ */
+#define RATIFY_ERROR 2
+
+/**
+ * The propagator used for checking, like `--mode` of the binary.
+ */
+typedef enum RatifyMode {
+  RatifyMode_Mutating,
+  RatifyMode_Immutable,
+  RatifyMode_Naive,
+  RatifyMode_HeadTail,
+  RatifyMode_Auto,
+} RatifyMode;
+
+/**
+ * A formula and proof which are passed clause by clause before checking them.
+ */
+typedef struct RatifyChecker RatifyChecker;
+
+/**
+ * The options of a check, start from `ratify_default_options`.
+ */
+typedef struct RatifyOptions {
+  enum RatifyMode mode;
+  bool rup_only;
+  bool drup;
+  bool check_all;
+  bool implicit_empty;
+  bool ignore_deletions;
+} RatifyOptions;
+
+/**
+ * The default options, which check with the mutating propagator.
+ */
+struct RatifyOptions ratify_default_options(void);
+
+/**
+ * Check the DRAT proof at `proof_path` for the DIMACS formula at `cnf_path`.
+ *
+ * # Safety
+ * The paths have to be nul terminated strings. `options` may be NULL for the default options.
+ */
+int ratify_check_drat(const char *cnf_path,
+                      const char *proof_path,
+                      const struct RatifyOptions *options);
+
+/**
+ * The message of the last error or rejection on this thread, or NULL if there was none. The
+ * string is valid until the next check on this thread.
+ */
+const char *ratify_last_error(void);
+
+/**
+ * Create a checker for a formula and proof which are passed with the `ratify_checker_*`
+ * functions. It has to be freed with `ratify_checker_free`.
+ *
+ * # Safety
+ * `options` may be NULL for the default options.
+ */
+struct RatifyChecker *ratify_checker_new(const struct RatifyOptions *options);
+
+/**
+ * Add a clause of `len` DIMACS literals without the terminating 0 to the formula.
+ *
+ * # Safety
+ * `checker` has to come from `ratify_checker_new` and `literals` has to point to `len` literals.
+ */
+void ratify_checker_add_clause(struct RatifyChecker *checker, const int32_t *literals, size_t len);
+
+/**
+ * Append the addition of a lemma to the proof.
+ *
+ * # Safety
+ * `checker` has to come from `ratify_checker_new` and `literals` has to point to `len` literals.
+ */
+void ratify_checker_add_lemma(struct RatifyChecker *checker, const int32_t *literals, size_t len);
+
+/**
+ * Append the deletion of a clause to the proof.
+ *
+ * # Safety
+ * `checker` has to come from `ratify_checker_new` and `literals` has to point to `len` literals.
+ */
+void ratify_checker_delete_lemma(struct RatifyChecker *checker,
+                                 const int32_t *literals,
+                                 size_t len);
+
+/**
+ * Check the proof passed so far. More clauses and lemmas may be passed afterwards to check
+ * again.
+ *
+ * # Safety
+ * `checker` has to come from `ratify_checker_new`.
+ */
+int ratify_checker_check(const struct RatifyChecker *checker);
+
+/**
+ * Free a checker. Passing NULL does nothing.
+ *
+ * # Safety
+ * `checker` has to come from `ratify_checker_new` and must not be used afterwards.
+ */
+void ratify_checker_free(struct RatifyChecker *checker);
+
+#endif /* RATIFY_H */
@@ -0,0 +1,133 @@
+//! Checking a proof while it arrives, enabled with the `async` feature. The checker runs on a
+//! blocking thread of the tokio runtime and reports the outcome of every proof step as soon as
+//! it has been checked. Since the proof is not known up front, the clause storage is reserved
+//! according to [`ProofBounds`].
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::{
+    common::RawLemma,
+    parser::{self, format::ParsedStep},
+    stream_lemmas, CheckReport, CheckerOptions, Error, Observer, ProofBounds, ProofStep, Result,
+    StepOutcome,
+};
+
+// The number of parsed lemmas which are buffered before reading the proof waits for the checker.
+const LEMMA_BUFFER: usize = 1024;
+
+/// A check running in the background.
+pub struct AsyncCheck {
+    /// The outcome of every proof step as soon as it has been checked. The receiver may be
+    /// dropped if only the final report is of interest.
+    pub steps: mpsc::UnboundedReceiver<(usize, StepOutcome)>,
+    /// The report once the proof has been read completely or the check failed.
+    pub report: JoinHandle<Result<CheckReport>>,
+}
+
+struct ChannelObserver(mpsc::UnboundedSender<(usize, StepOutcome)>);
+
+impl Observer for ChannelObserver {
+    fn after_step(&mut self, step: usize, _lemma: &ProofStep, outcome: StepOutcome) {
+        // nobody is listening anymore if sending fails
+        let _ = self.0.send((step, outcome));
+    }
+}
+
+/// Check the DRAT proof read from `proof` for the DIMACS formula `cnf`. Has to be called from
+/// within a tokio runtime.
+pub fn check_reader(
+    cnf: &str,
+    proof: impl AsyncRead + Unpin + Send + 'static,
+    options: CheckerOptions,
+    bounds: ProofBounds,
+) -> Result<AsyncCheck> {
+    let (sender, receiver) = mpsc::channel(LEMMA_BUFFER);
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(proof).lines();
+        let mut line = 0;
+        loop {
+            line += 1;
+            let lemma = match lines.next_line().await {
+                Ok(Some(text)) => match parser::drat::lemma(line, &text) {
+                    Some(lemma) => lemma.map(|lemma| ParsedStep::on_line(line, lemma)),
+                    None => continue,
+                },
+                Ok(None) => break,
+                Err(e) => Err(e.into()),
+            };
+            let failed = lemma.is_err();
+            // the checker stops receiving once the proof has been verified or rejected
+            if sender.send(lemma).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+    start(cnf, receiver, options, bounds)
+}
+
+/// Check the proof steps received from `proof` for the DIMACS formula `cnf`. The proof ends once
+/// all senders are dropped. Has to be called from within a tokio runtime.
+pub fn check_channel(
+    cnf: &str,
+    mut proof: mpsc::Receiver<ProofStep>,
+    options: CheckerOptions,
+    bounds: ProofBounds,
+) -> Result<AsyncCheck> {
+    let (sender, receiver) = mpsc::channel(LEMMA_BUFFER);
+    tokio::spawn(async move {
+        let mut step = 0;
+        while let Some(proof_step) = proof.recv().await {
+            step += 1;
+            let lemma = match proof_step {
+                ProofStep::Add(c) => parser::normalize_clause(c).map(RawLemma::Add),
+                ProofStep::Delete(c) => parser::normalize_clause(c).map(RawLemma::Del),
+            }
+            .map(|lemma| ParsedStep::on_line(step, lemma))
+            .ok_or_else(|| Error::Parse {
+                line: step,
+                message: "clause contains the literal 0 or one out of range".to_string(),
+            });
+            let failed = lemma.is_err();
+            if sender.send(lemma).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+    start(cnf, receiver, options, bounds)
+}
+
+fn start(
+    cnf: &str,
+    mut lemmas: mpsc::Receiver<Result<ParsedStep>>,
+    options: CheckerOptions,
+    bounds: ProofBounds,
+) -> Result<AsyncCheck> {
+    options.validate()?;
+    if options.cross_check.is_some() || options.compact_variables {
+        return Err(Error::InvalidOptions(
+            "checking a proof while it arrives cannot be combined with --cross-check or \
+             --compact-variables"
+                .to_string(),
+        ));
+    }
+    let (_, formula) = parser::cnf::parse(cnf)?;
+    let (sender, steps) = mpsc::unbounded_channel();
+    let report = tokio::task::spawn_blocking(move || {
+        let proof = std::iter::from_fn(|| lemmas.blocking_recv());
+        let mut observer = ChannelObserver(sender);
+        // the number of additions stands in for the unknown length of the proof
+        stream_lemmas(
+            options,
+            formula,
+            proof,
+            bounds.additions,
+            bounds,
+            Some(&mut observer),
+        )
+    });
+    Ok(AsyncCheck { steps, report })
+}
@@ -1,25 +1,56 @@
 mod assignment;
 mod literal;
+mod origins;
+mod preprocess;
+mod renaming;
+pub mod stats;
 pub mod storage;
 
-use std::collections::BTreeSet;
-
 pub use assignment::*;
 pub use literal::*;
+pub use origins::*;
+pub use preprocess::*;
+pub use renaming::*;
+
+use crate::prelude::*;
+use core::hash::BuildHasherDefault;
 
 use self::storage::Clause;
 
+/// A hash map with the fast hash function of rustc, which is not resistant against collisions
+/// crafted on purpose.
+pub type FxHashMap<K, V> = hashbrown::HashMap<K, V, BuildHasherDefault<rustc_hash::FxHasher>>;
+
+/// A hash set with the hash function of [`FxHashMap`].
+pub type FxHashSet<T> = hashbrown::HashSet<T, BuildHasherDefault<rustc_hash::FxHasher>>;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Conflict {}
 
-#[derive(Debug, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum RawLemma {
-    Add(BTreeSet<Literal>),
-    Del(BTreeSet<Literal>),
+    Add(Vec<Literal>),
+    Del(Vec<Literal>),
+    /// Adds back a clause which has been deleted, written with the prefix `r` in DRAT proofs.
+    Restore(Vec<Literal>),
+}
+
+impl RawLemma {
+    /// The literals of the clause which is added, deleted or restored.
+    pub fn clause(&self) -> &[Literal] {
+        match self {
+            RawLemma::Add(c) | RawLemma::Del(c) | RawLemma::Restore(c) => c,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Lemma {
     Add(Clause),
     Del(Clause),
+    /// A clause which is known to have been deleted before is added back, see
+    /// [`CheckerOptions::restores`](crate::CheckerOptions::restores).
+    Restore(Clause),
 }
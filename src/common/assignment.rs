@@ -1,8 +1,10 @@
-use std::fmt::Display;
+use crate::prelude::*;
+use core::fmt::Display;
 
 use itertools::Itertools;
 
 use super::{
+    stats::vec_bytes,
     storage::{Clause, ClauseStorage, LiteralSet},
     Conflict, Literal,
 };
@@ -15,6 +17,8 @@ pub struct Rollback {
 pub struct Assignment {
     inner: LiteralSet,
     trace: Vec<Literal>,
+    // the shortest length the trace had since the last call to take_low_water_mark
+    low_water_mark: usize,
 }
 
 impl Assignment {
@@ -25,6 +29,7 @@ impl Assignment {
                 inner: clause_db.literal_array(),
             },
             trace: vec![],
+            low_water_mark: 0,
         }
     }
 
@@ -70,7 +75,15 @@ impl Assignment {
             self.inner.remove(lit);
         }
 
-        self.trace.truncate(rollback_point.len)
+        self.trace.truncate(rollback_point.len);
+        self.low_water_mark = self.low_water_mark.min(rollback_point.len);
+    }
+
+    /// Returns the shortest length the trace had since the last call of this function. Everything
+    /// assigned after this position may have been rolled back in the meantime. Propagators which
+    /// store information depending on the position in the trace use this to detect rollbacks.
+    pub fn take_low_water_mark(&mut self) -> usize {
+        core::mem::replace(&mut self.low_water_mark, self.trace.len())
     }
 
     pub fn is_satisfied(&self, clause: Clause, clause_db: &ClauseStorage) -> bool {
@@ -80,6 +93,15 @@ impl Assignment {
             .any(|&lit| self.is_true(lit))
     }
 
+    pub fn heap_size(&self) -> usize {
+        self.inner.inner.heap_size() + vec_bytes(&self.trace)
+    }
+
+    /// Make room for the literals of all variables up to `max_variable`.
+    pub fn grow(&mut self, max_variable: u32) {
+        self.inner.grow(max_variable);
+    }
+
     pub fn trace_len(&self) -> usize {
         self.trace.len()
     }
@@ -87,10 +109,53 @@ impl Assignment {
     pub fn nth_lit(&self, n: usize) -> Literal {
         self.trace[n]
     }
+
+    /// Check that the trace contains exactly the assigned literals and that no literal is assigned
+    /// together with its negation. Returns the first inconsistency found.
+    pub fn check_consistency(&self) -> Result<(), String> {
+        for (position, &lit) in self.trace.iter().enumerate() {
+            if !self.is_true(lit) {
+                return Err(format!(
+                    "literal {} at trace position {} is not assigned",
+                    lit, position
+                ));
+            }
+            if self.is_true(-lit) {
+                return Err(format!(
+                    "literal {} is assigned together with its negation",
+                    lit
+                ));
+            }
+        }
+        let assigned = self
+            .inner
+            .inner
+            .iter()
+            .filter(|&&assigned| assigned)
+            .count();
+        if assigned != self.trace.len() {
+            return Err(format!(
+                "{} literals are assigned but the trace has {}",
+                assigned,
+                self.trace.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// All literals which are assigned in this assignment but not in the other one.
+    pub fn difference(&self, other: &Assignment) -> Vec<Literal> {
+        self.trace
+            .iter()
+            .filter(|&&lit| !other.is_true(lit))
+            .copied()
+            .sorted()
+            .collect()
+    }
 }
 
 impl Display for Assignment {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "[{}]",
@@ -1,45 +1,111 @@
-use std::{fmt::Display, num::NonZeroI32, ops::Neg};
+use core::{fmt::Display, num::NonZeroU32, ops::Neg};
 
-/// A literal represented by an integer
+/// A literal packed into an unsigned integer. The variable is stored in the upper bits and the
+/// lowest bit is set for negative literals, so a literal and its negation are neighbours and the
+/// code can directly be used as an index.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Literal {
-    // We choose a nonzeroi32 to optimize nullable data structures
-    inner: NonZeroI32,
+    // We choose a nonzerou32 to optimize nullable data structures. Variables start at 1, so the
+    // smallest code is 2.
+    inner: NonZeroU32,
 }
 
 impl Literal {
+    pub fn new(var: u32, negative: bool) -> Self {
+        Literal {
+            inner: unsafe { NonZeroU32::new_unchecked((var << 1) | negative as u32) },
+        }
+    }
+
+    /// The literal as it appears in DIMACS files.
     pub fn raw(&self) -> i32 {
-        i32::from(self.inner)
+        let var = self.var() as i32;
+        if self.is_negative() {
+            -var
+        } else {
+            var
+        }
+    }
+
+    /// The packed representation of this literal, usable as an index.
+    pub fn code(&self) -> usize {
+        self.inner.get() as usize
+    }
+
+    pub fn var(&self) -> u32 {
+        self.inner.get() >> 1
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.inner.get() & 1 == 1
     }
 }
 
 impl Neg for Literal {
     type Output = Self;
 
-    fn neg(mut self) -> Self::Output {
-        self.inner = -self.inner;
-        self
+    fn neg(self) -> Self::Output {
+        Literal {
+            inner: unsafe { NonZeroU32::new_unchecked(self.inner.get() ^ 1) },
+        }
     }
 }
 
 impl Default for Literal {
     fn default() -> Self {
-        Literal {
-            inner: unsafe { NonZeroI32::new_unchecked(1) },
-        }
+        Literal::from(1)
     }
 }
 
+/// The literal of a DIMACS integer. Panics for 0 and for `i32::MIN`, whose variable does not fit
+/// into an i32, the parsers reject both before converting.
 impl From<i32> for Literal {
     fn from(value: i32) -> Self {
-        Literal {
-            inner: unsafe { NonZeroI32::new_unchecked(value) },
-        }
+        assert!(
+            value != 0 && value != i32::MIN,
+            "{} is not a DIMACS literal",
+            value
+        );
+        Literal::new(value.unsigned_abs(), value < 0)
+    }
+}
+
+// Any literal of a variable which still fits into an i32, like the parsers accept.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Literal {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let var = u.int_in_range(1..=i32::MAX as u32)?;
+        Ok(Literal::new(var, u.arbitrary()?))
     }
 }
 
 impl Display for Literal {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.inner)
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.raw())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimacs_round_trip() {
+        for value in [1, -1, i32::MAX, -i32::MAX] {
+            assert_eq!(Literal::from(value).raw(), value);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "0 is not a DIMACS literal")]
+    fn zero() {
+        let _ = Literal::from(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "-2147483648 is not a DIMACS literal")]
+    fn min() {
+        let _ = Literal::from(i32::MIN);
     }
 }
@@ -0,0 +1,82 @@
+use crate::prelude::*;
+
+/// Where the proof steps seen by the checker come from in the input. Preprocessing skips some
+/// lemmas, which renumbers the steps after them, and comments shift the lines of the steps. Both
+/// are rare, so only the skipped steps and the steps whose line does not follow the line of the
+/// step before are kept.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Origins {
+    // whether the lines of the input are known, otherwise there are no lines to report
+    known: bool,
+    // the steps of the input which have been skipped, in increasing order
+    skipped: Vec<usize>,
+    // the steps of the input with their line where the lines jump, in increasing order
+    jumps: Vec<(usize, usize)>,
+    // the number of input steps recorded so far
+    steps: usize,
+}
+
+impl Origins {
+    /// The origins of proof steps which are given in memory, where the line of a step is its
+    /// position counted from 1.
+    pub fn positions() -> Self {
+        Origins {
+            known: true,
+            ..Origins::default()
+        }
+    }
+
+    /// Record the line of the next step of the input, `None` if the format has no lines.
+    pub fn record(&mut self, line: Option<usize>) {
+        let step = self.steps;
+        self.steps += 1;
+        let Some(line) = line else {
+            return;
+        };
+        self.known = true;
+        if self.input_line(step) != Some(line) {
+            self.jumps.push((step, line));
+        }
+    }
+
+    /// Record that preprocessing skipped this step of the input. Steps have to be skipped in
+    /// increasing order.
+    pub fn skip(&mut self, input_step: usize) {
+        self.skipped.push(input_step);
+    }
+
+    /// The steps of the input which have been skipped, in increasing order.
+    pub fn skipped(&self) -> &[usize] {
+        &self.skipped
+    }
+
+    /// The step of the input which became this step of the checker.
+    pub fn input_step(&self, step: usize) -> usize {
+        let mut input_step = step;
+        for &skipped in &self.skipped {
+            if skipped > input_step {
+                break;
+            }
+            input_step += 1;
+        }
+        input_step
+    }
+
+    /// The line of a step of the input, counted from 1.
+    pub fn input_line(&self, input_step: usize) -> Option<usize> {
+        if !self.known {
+            return None;
+        }
+        let jump = self.jumps.partition_point(|&(step, _)| step <= input_step);
+        Some(match jump.checked_sub(1).map(|i| self.jumps[i]) {
+            Some((step, line)) => line + input_step - step,
+            None => input_step + 1,
+        })
+    }
+
+    /// The line of a step of the checker.
+    pub fn line(&self, step: usize) -> Option<usize> {
+        self.input_line(self.input_step(step))
+    }
+}
@@ -0,0 +1,339 @@
+use crate::prelude::*;
+
+use crate::common::{FxHashMap, FxHashSet};
+use crate::{
+    parser::is_tautology, CheckerOptions, DeletionMatch, DeletionPolicy, DuplicatePolicy,
+    PreprocessStats,
+};
+
+use super::{
+    stats::{map_bytes, vec_bytes},
+    storage::{Builder, Clause, ClauseStorage, Interner},
+    Lemma, Literal, RawLemma,
+};
+
+/// Converts raw lemmas to lemmas referencing the clause storage. Clauses are only ever active
+/// once, so additions of clauses which are already active and deletions of clauses which are not
+/// active are dropped. Deleting a clause only takes effect once all its copies are deleted. A
+/// clause which is added again after it has been deleted becomes a new clause, which is checked
+/// and propagated like any other lemma. Deletions of the empty clause are dropped as well, the
+/// proof is verified once the empty clause is active. A restore step becomes `Lemma::Restore` if
+/// its clause is known to have been deleted, see `CheckerOptions::restores`, and an addition
+/// otherwise.
+///
+/// When duplicates are kept, every clause of the formula and every lemma is stored as a clause of
+/// its own, like in checkers which treat the formula as a multiset. A deletion then deletes the
+/// most recently or the first added copy which is still active, see `DeletionMatch`. Otherwise
+/// duplicate additions are skipped or checked, see `DuplicatePolicy`.
+#[derive(Default)]
+pub struct Preprocessor {
+    // how many copies of every clause have been added and not yet deleted
+    seen: FxHashMap<Clause, i64>,
+    ignore_deletions: bool,
+    keep_duplicates: bool,
+    check_duplicates: bool,
+    deletion_match: DeletionMatch,
+    // drop the empty clause from the formula, see `CheckerOptions::check_trivial`
+    drop_empty_clause: bool,
+    stats: PreprocessStats,
+    // the first proof step deleting a clause which is not active, with the clause
+    missing_deletion: Option<(usize, Vec<Literal>)>,
+    // every clause which has been deleted, only kept with `CheckerOptions::restores`
+    deleted: Option<FxHashSet<Vec<Literal>>>,
+}
+
+impl Preprocessor {
+    /// Create a preprocessor for the handling of deletions, duplicate clauses and the empty clause
+    /// selected by the options.
+    pub fn new(options: &CheckerOptions) -> Self {
+        Preprocessor {
+            seen: FxHashMap::default(),
+            ignore_deletions: options.deletions == DeletionPolicy::Ignore,
+            keep_duplicates: options.keep_duplicates,
+            check_duplicates: options.duplicates == DuplicatePolicy::Check,
+            deletion_match: options.deletion_match,
+            drop_empty_clause: options.check_trivial,
+            stats: PreprocessStats::default(),
+            missing_deletion: None,
+            deleted: options.restores.then(FxHashSet::default),
+        }
+    }
+
+    /// The clauses and lemmas seen so far and how many lemmas have been skipped. The distinct
+    /// formula clauses and the largest variable are left to the caller.
+    pub fn stats(&self) -> PreprocessStats {
+        self.stats
+    }
+
+    pub fn heap_size(&self) -> usize {
+        map_bytes::<Clause, i64>(self.seen.capacity())
+            + self.deleted.as_ref().map_or(0, |deleted| {
+                map_bytes::<Vec<Literal>, ()>(deleted.capacity())
+                    + deleted.iter().map(vec_bytes).sum::<usize>()
+            })
+    }
+
+    /// The first proof step which deletes a clause that is not active, and the clause.
+    pub fn missing_deletion(&self) -> Option<(usize, &[Literal])> {
+        self.missing_deletion
+            .as_ref()
+            .map(|(step, clause)| (*step, clause.as_slice()))
+    }
+
+    /// Store a clause of the formula.
+    pub fn formula_clause(&mut self, builder: &mut Builder, clause: &[Literal]) {
+        if clause.is_empty() && self.drop_empty_clause {
+            tracing::warn!("ignoring the empty clause of the formula to check the proof");
+            self.stats.formula_clauses += 1;
+            return;
+        }
+        if is_tautology(clause) {
+            self.stats.tautologies += 1;
+        }
+        let clause = if self.keep_duplicates {
+            builder.add_copy(clause)
+        } else {
+            builder.add_clause(clause)
+        };
+        self.stats.formula_clauses += 1;
+        *self.seen.entry(clause).or_default() += 1;
+    }
+
+    /// Convert the lemma of the given proof step, returns `None` if it has to be skipped. The line
+    /// of the step is only used in the warnings.
+    pub fn lemma(
+        &mut self,
+        step: usize,
+        line: Option<usize>,
+        lemma: RawLemma,
+        interner: &mut Interner,
+        clause_db: &mut ClauseStorage,
+    ) -> Option<Lemma> {
+        match lemma {
+            RawLemma::Restore(c) => {
+                self.stats.restores += 1;
+                let deleted = self
+                    .deleted
+                    .as_ref()
+                    .is_some_and(|deleted| deleted.contains(&c));
+                if !deleted {
+                    tracing::warn!(
+                        "proof step {}{} restores a clause which is not known to be deleted, \
+                         checking it like a lemma",
+                        step,
+                        on_line(line)
+                    );
+                }
+                match self.lemma(step, line, RawLemma::Add(c), interner, clause_db) {
+                    Some(Lemma::Add(clause)) if deleted => Some(Lemma::Restore(clause)),
+                    lemma => lemma,
+                }
+            }
+            RawLemma::Add(c) => {
+                self.stats.additions += 1;
+                if is_tautology(&c) {
+                    self.stats.tautologies += 1;
+                }
+                let clause = if self.keep_duplicates {
+                    interner.add_copy(clause_db, &c)
+                } else {
+                    interner.add_clause(clause_db, &c)
+                };
+                let entry = self.seen.entry(clause).or_default();
+                *entry += 1;
+                if *entry > 1 && self.check_duplicates {
+                    self.stats.checked_duplicates += 1;
+                    Some(Lemma::Add(clause))
+                } else if *entry > 1 {
+                    // The clause has already been added, only count the appearance
+                    tracing::warn!(
+                        "ignoring proof step {}{} addition of duplicate clause",
+                        step,
+                        on_line(line)
+                    );
+                    self.stats.duplicate_additions += 1;
+                    None
+                } else {
+                    Some(Lemma::Add(clause))
+                }
+            }
+            RawLemma::Del(_) if self.ignore_deletions => {
+                self.stats.deletions += 1;
+                None
+            }
+            RawLemma::Del(c) if c.is_empty() => {
+                tracing::warn!(
+                    "ignoring proof step {}{} deletion of the empty clause",
+                    step,
+                    on_line(line)
+                );
+                self.stats.deletions += 1;
+                self.stats.empty_deletions += 1;
+                None
+            }
+            RawLemma::Del(c) => {
+                self.stats.deletions += 1;
+                let found = match self.deletion_match {
+                    DeletionMatch::Newest => interner.find(clause_db, &c),
+                    DeletionMatch::Oldest => interner.copies(clause_db, &c).last(),
+                };
+                let Some(clause) = found else {
+                    tracing::warn!(
+                        "ignoring proof step {}{} deletion of non existing clause",
+                        step,
+                        on_line(line)
+                    );
+                    self.stats.missing_deletions += 1;
+                    self.missing_deletion.get_or_insert((step, c));
+                    return None;
+                };
+                let entry = self.seen.entry(clause).or_default();
+                *entry -= 1;
+                if *entry <= 0 {
+                    // All instances of the clause were removed, actually keep the delete
+                    // instruction then. Adding it again later creates a new clause, so its
+                    // literals can be dropped from the storage.
+                    self.seen.remove(&clause);
+                    if let Some(deleted) = &mut self.deleted {
+                        deleted.insert(c);
+                    }
+                    interner.forget(clause_db, clause);
+                    Some(Lemma::Del(clause))
+                } else {
+                    tracing::warn!(
+                        "ignoring proof step {}{} deletion of duplicate clause",
+                        step,
+                        on_line(line)
+                    );
+                    self.stats.duplicate_deletions += 1;
+                    None
+                }
+            }
+        }
+    }
+}
+
+fn on_line(line: Option<usize>) -> String {
+    line.map(|line| format!(" on line {}", line))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_str, preprocess_str, Verdict};
+
+    // All clauses over three variables.
+    const FORMULA: &str = "p cnf 3 8
+1 2 3 0
+1 2 -3 0
+1 -2 3 0
+1 -2 -3 0
+-1 2 3 0
+-1 2 -3 0
+-1 -2 3 0
+-1 -2 -3 0
+";
+
+    // 1 2 is restored once it and the clauses it subsumes are deleted, which leaves it without
+    // RUP and RAT.
+    const PROOF: &str = "1 2 0
+d 1 2 3 0
+d 1 2 -3 0
+d 1 2 0
+r 1 2 0
+1 0
+2 0
+0
+";
+
+    fn lemmas(cnf: &str, proof: &str, options: &CheckerOptions) -> Vec<Lemma> {
+        preprocess_str(cnf, proof, options).unwrap().proof
+    }
+
+    #[test]
+    fn restore_of_deleted_clause() {
+        let options = CheckerOptions::default().restores(true);
+        let preprocessed = preprocess_str(FORMULA, PROOF, &options).unwrap();
+        assert!(matches!(preprocessed.proof[4], Lemma::Restore(_)));
+        assert_eq!(preprocessed.stats().restores, 1);
+        assert_eq!(preprocessed.stats().additions, 5);
+        let report = check_str(FORMULA, PROOF, options).unwrap();
+        assert_eq!(report.verdict, Verdict::Verified);
+    }
+
+    #[test]
+    fn restore_without_restores() {
+        let options = CheckerOptions::default();
+        assert!(matches!(lemmas(FORMULA, PROOF, &options)[4], Lemma::Add(_)));
+        let report = check_str(FORMULA, PROOF, options).unwrap();
+        assert_eq!(report.verdict, Verdict::Refuted { step: 4 });
+    }
+
+    #[test]
+    fn restore_of_clause_never_deleted() {
+        // the restored clause is checked like a lemma, it has RUP
+        let proof = "r 1 2 0\n1 0\n2 0\n0\n";
+        let options = CheckerOptions::default().restores(true);
+        assert!(matches!(lemmas(FORMULA, proof, &options)[0], Lemma::Add(_)));
+        let report = check_str(FORMULA, proof, options).unwrap();
+        assert_eq!(report.verdict, Verdict::Verified);
+    }
+
+    // The clause 1 2 is in the formula twice.
+    const DUPLICATES: &str = "p cnf 2 3\n1 2 0\n-1 2 0\n1 2 0\n";
+
+    #[test]
+    fn deletions_of_deduplicated_clause() {
+        let proof = "d 1 2 0\nd 1 2 0\nd 1 2 0\n";
+        let preprocessed = preprocess_str(DUPLICATES, proof, &CheckerOptions::default()).unwrap();
+        let stats = preprocessed.stats();
+        assert_eq!(
+            (
+                stats.deletions,
+                stats.duplicate_deletions,
+                stats.missing_deletions
+            ),
+            (3, 1, 1)
+        );
+        assert_eq!(preprocessed.proof.len(), 1);
+        assert!(matches!(preprocessed.proof[0], Lemma::Del(_)));
+    }
+
+    #[test]
+    fn deletions_without_dedup() {
+        let proof = "d 1 2 0\nd 1 2 0\nd 1 2 0\n";
+        let options = CheckerOptions::default().keep_duplicates(true);
+        let preprocessed = preprocess_str(DUPLICATES, proof, &options).unwrap();
+        let stats = preprocessed.stats();
+        assert_eq!(
+            (
+                stats.deletions,
+                stats.duplicate_deletions,
+                stats.missing_deletions
+            ),
+            (3, 0, 1)
+        );
+        // every deletion deletes one of the copies
+        let [Lemma::Del(first), Lemma::Del(second)] = preprocessed.proof[..] else {
+            panic!("the deletions are {:?}", preprocessed.proof);
+        };
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn deletion_match_without_dedup() {
+        let proof = "1 2 0\nd 1 2 0\n";
+        for deletion_match in [DeletionMatch::Newest, DeletionMatch::Oldest] {
+            let options = CheckerOptions::default()
+                .keep_duplicates(true)
+                .deletion_match(deletion_match);
+            let [Lemma::Add(added), Lemma::Del(deleted)] = lemmas(DUPLICATES, proof, &options)[..]
+            else {
+                panic!("the lemma and deletion are not kept");
+            };
+            // the newest copy is the lemma, the oldest one is in the formula
+            assert_eq!(added == deleted, deletion_match == DeletionMatch::Newest);
+        }
+    }
+}
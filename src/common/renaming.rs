@@ -0,0 +1,45 @@
+use crate::common::FxHashMap;
+use crate::prelude::*;
+use itertools::Itertools;
+
+use super::Literal;
+
+/// Renames the variables of a formula and proof to 1..=n in their original order. Structures
+/// indexed by literals then only need to be as large as the number of distinct variables
+/// instead of the largest variable, which matters for formulas with sparse variable ids.
+pub struct Renaming {
+    internal: FxHashMap<u32, u32>,
+    // the original variable of every renamed one, starting at variable 1
+    external: Vec<u32>,
+}
+
+impl Renaming {
+    pub fn new<'a>(clauses: impl Iterator<Item = &'a Vec<Literal>>) -> Self {
+        let external = clauses
+            .flatten()
+            .map(|lit| lit.var())
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+        let internal = external
+            .iter()
+            .enumerate()
+            .map(|(i, &var)| (var, i as u32 + 1))
+            .collect();
+        Renaming { internal, external }
+    }
+
+    /// Rename the variables of a clause. Since the renaming keeps the order of variables the
+    /// order of literals in the clause does not change.
+    pub fn rename(&self, mut clause: Vec<Literal>) -> Vec<Literal> {
+        for lit in &mut clause {
+            *lit = Literal::new(self.internal[&lit.var()], lit.is_negative());
+        }
+        clause
+    }
+
+    /// The table mapping renamed variables back to the original ones.
+    pub fn into_external(self) -> Vec<u32> {
+        self.external
+    }
+}
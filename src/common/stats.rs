@@ -0,0 +1,40 @@
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+/// Bytes allocated by a vector, not counting allocations owned by its elements.
+pub fn vec_bytes<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+/// Approximate bytes allocated by a hash map with the given capacity. The control bytes of the
+/// table are ignored.
+pub fn map_bytes<K, V>(capacity: usize) -> usize {
+    capacity * size_of::<(K, V)>()
+}
+
+/// The peak resident set size of this process in bytes, as reported with --stats. Only available
+/// on Linux.
+#[cfg(feature = "std")]
+pub fn peak_rss() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Stands in for `std::time::Instant` without std, where there is no clock. All durations are
+/// zero.
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Copy)]
+pub struct Instant;
+
+#[cfg(not(feature = "std"))]
+impl Instant {
+    pub fn now() -> Self {
+        Instant
+    }
+
+    pub fn elapsed(&self) -> core::time::Duration {
+        core::time::Duration::ZERO
+    }
+}
@@ -1,49 +1,104 @@
-use std::{
-    collections::BTreeSet,
+use crate::prelude::*;
+use core::{
     fmt::Display,
     ops::{Index, IndexMut},
 };
 
-use fxhash::FxHashMap;
+use crate::common::FxHashMap;
 use itertools::Itertools;
 
-use super::{Assignment, Literal};
+use super::{
+    stats::{map_bytes, vec_bytes},
+    Assignment, Literal,
+};
+
+// The number of literal codes up to the literals of `max_variable`. Fails instead of wrapping
+// around if they do not fit into a usize, which can only happen on 32 bit targets.
+fn literal_codes(max_variable: u32) -> usize {
+    (max_variable as usize)
+        .checked_add(1)
+        .and_then(|vars| vars.checked_mul(2))
+        .expect("the literals of the largest variable do not fit into the address space")
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LiteralArray<T> {
     inner: Vec<T>,
-    max_literal: i32,
 }
 
 impl<T> Index<Literal> for LiteralArray<T> {
     type Output = T;
     fn index(&self, index: Literal) -> &Self::Output {
-        let index = index.raw();
-        if index < 0 {
-            unsafe {
-                self.inner
-                    .get_unchecked((-index + self.max_literal) as usize)
-            }
-        } else {
-            unsafe { self.inner.get_unchecked(index as usize) }
+        debug_assert!(
+            index.code() < self.inner.len(),
+            "literal {} is beyond the variables of the array",
+            index
+        );
+        #[cfg(feature = "checked-indexing")]
+        return &self.inner[index.code()];
+        #[cfg(not(feature = "checked-indexing"))]
+        unsafe {
+            self.inner.get_unchecked(index.code())
         }
     }
 }
 
 impl<T> IndexMut<Literal> for LiteralArray<T> {
     fn index_mut(&mut self, index: Literal) -> &mut Self::Output {
-        let index = index.raw();
-        if index < 0 {
-            unsafe {
-                self.inner
-                    .get_unchecked_mut((-index + self.max_literal) as usize)
-            }
-        } else {
-            unsafe { self.inner.get_unchecked_mut(index as usize) }
+        debug_assert!(
+            index.code() < self.inner.len(),
+            "literal {} is beyond the variables of the array",
+            index
+        );
+        #[cfg(feature = "checked-indexing")]
+        return &mut self.inner[index.code()];
+        #[cfg(not(feature = "checked-indexing"))]
+        unsafe {
+            self.inner.get_unchecked_mut(index.code())
+        }
+    }
+}
+
+impl<T> LiteralArray<T> {
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.inner.iter()
+    }
+
+    pub fn heap_size(&self) -> usize {
+        vec_bytes(&self.inner)
+    }
+}
+
+impl<T: Default + Clone> LiteralArray<T> {
+    /// Make room for the literals of all variables up to `max_variable`.
+    pub fn grow(&mut self, max_variable: u32) {
+        let len = literal_codes(max_variable);
+        if self.inner.len() < len {
+            self.inner.resize(len, T::default());
         }
     }
 }
 
+/// The bucket type for watchlists. With the `smallvec` feature a few entries are stored inline,
+/// which avoids a heap allocation for the many short watchlists.
+#[cfg(feature = "smallvec")]
+pub type WatchList<T> = smallvec::SmallVec<[T; 4]>;
+#[cfg(not(feature = "smallvec"))]
+pub type WatchList<T> = Vec<T>;
+
+/// Bytes allocated on the heap by a watchlist, which is nothing as long as a small vector has not
+/// spilled.
+pub fn watch_list_bytes<T>(list: &WatchList<T>) -> usize {
+    #[cfg(feature = "smallvec")]
+    return if list.spilled() {
+        list.capacity() * core::mem::size_of::<T>()
+    } else {
+        0
+    };
+    #[cfg(not(feature = "smallvec"))]
+    vec_bytes(list)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct LiteralSet {
     pub(super) inner: LiteralArray<bool>,
@@ -65,35 +120,67 @@ impl LiteralSet {
         self.inner[lit] = false;
         already_present
     }
+
+    pub fn grow(&mut self, max_variable: u32) {
+        self.inner.grow(max_variable);
+    }
 }
 
 /// A clause identified by its index in a database
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Clause {
     index: usize,
 }
 
 impl Display for Clause {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "c{}", self.index)
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClauseArray<T> {
     inner: Vec<T>,
 }
 
+impl<T> ClauseArray<T> {
+    pub fn heap_size(&self) -> usize {
+        vec_bytes(&self.inner)
+    }
+}
+
 impl<T> Index<Clause> for ClauseArray<T> {
     type Output = T;
     fn index(&self, c: Clause) -> &Self::Output {
-        unsafe { self.inner.get_unchecked(c.index) }
+        debug_assert!(
+            c.index < self.inner.len(),
+            "clause {} is beyond the clauses of the array",
+            c
+        );
+        #[cfg(feature = "checked-indexing")]
+        return &self.inner[c.index];
+        #[cfg(not(feature = "checked-indexing"))]
+        unsafe {
+            self.inner.get_unchecked(c.index)
+        }
     }
 }
 
 impl<T> IndexMut<Clause> for ClauseArray<T> {
     fn index_mut(&mut self, c: Clause) -> &mut Self::Output {
-        unsafe { self.inner.get_unchecked_mut(c.index) }
+        debug_assert!(
+            c.index < self.inner.len(),
+            "clause {} is beyond the clauses of the array",
+            c
+        );
+        #[cfg(feature = "checked-indexing")]
+        return &mut self.inner[c.index];
+        #[cfg(not(feature = "checked-indexing"))]
+        unsafe {
+            self.inner.get_unchecked_mut(c.index)
+        }
     }
 }
 
@@ -101,11 +188,16 @@ impl<T> IndexMut<Clause> for ClauseArray<T> {
 /// database.
 /// Generate a view from the database and then access the clauses through it.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct View {
     active: ClauseArray<bool>,
 }
 
 impl View {
+    pub fn heap_size(&self) -> usize {
+        self.active.heap_size()
+    }
+
     pub fn del(&mut self, clause: Clause) {
         self.active[clause] = false;
     }
@@ -119,7 +211,18 @@ impl View {
     }
 }
 
+/// The shape of a clause depending on how many literals it has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClauseKind {
+    Empty,
+    Unit(Literal),
+    Binary(Literal, Literal),
+    /// A clause with more than two literals, together with its first two literals.
+    Long(Literal, Literal),
+}
+
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Range {
     start: usize,
     end: usize,
@@ -132,32 +235,72 @@ impl Range {
 }
 
 /// The clause database stores all clauses that exist within the proof and formula.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClauseStorage {
     literals: Vec<Literal>,
     ranges: Vec<Range>,
-    max_literal: i32,
+    // all clauses consisting of a single literal
+    units: Vec<Clause>,
+    // the largest variable occurring in any clause
+    max_variable: u32,
+    // how many clauses arrays are created for, if more clauses are added after creating them
+    reserved_clauses: usize,
+    // the original name of every variable if they have been renamed
+    external_names: Option<Vec<u32>>,
 }
 
 impl ClauseStorage {
     pub fn literal_array<T: Default + Clone>(&self) -> LiteralArray<T> {
         LiteralArray {
-            inner: vec![T::default(); (self.max_literal * 2 + 1) as usize],
-            max_literal: self.max_literal,
+            // both literals of the largest variable are at the end
+            inner: vec![T::default(); literal_codes(self.max_variable)],
         }
     }
 
     pub fn clause_array<T: Default + Clone>(&self) -> ClauseArray<T> {
         ClauseArray {
-            inner: vec![T::default(); self.number_of_clauses()],
+            inner: vec![T::default(); self.number_of_clauses().max(self.reserved_clauses)],
         }
     }
 
+    /// Make literal and clause arrays large enough for clauses which are only added after the
+    /// arrays have been created. Used when the proof is checked while it is read.
+    pub fn reserve(&mut self, clauses: usize, max_variable: u32) {
+        self.reserved_clauses = clauses;
+        self.max_variable = self.max_variable.max(max_variable);
+    }
+
+    /// Whether another clause fits into the storage reserved with `reserve`.
+    pub fn fits(&self) -> bool {
+        self.number_of_clauses() < self.reserved_clauses
+    }
+
+    /// The largest variable literal arrays are created for.
+    pub fn max_variable(&self) -> u32 {
+        self.max_variable
+    }
+
+    /// Create literal arrays for variables up to `max_variable` from now on. Arrays which have
+    /// been created before have to be grown with [`LiteralArray::grow`].
+    pub fn grow(&mut self, max_variable: u32) {
+        self.max_variable = self.max_variable.max(max_variable);
+    }
+
+    pub fn heap_size(&self) -> usize {
+        vec_bytes(&self.literals) + vec_bytes(&self.ranges) + vec_bytes(&self.units)
+    }
+
     // how many clauses are in the database?
     pub fn number_of_clauses(&self) -> usize {
         self.ranges.len()
     }
 
+    // how many literals are in the database, counting each clause separately?
+    pub fn number_of_literals(&self) -> usize {
+        self.literals.len()
+    }
+
     /// Add a new clause to the database containing the specified literals.
     pub fn add_clause(&mut self, literals: impl Iterator<Item = Literal>) -> Clause {
         let index = self.ranges.len();
@@ -165,14 +308,113 @@ impl ClauseStorage {
         self.literals.extend(literals);
         let end = self.literals.len();
         self.ranges.push(Range { start, end });
+        if end - start == 1 {
+            self.units.push(Clause { index });
+        }
         Clause { index }
     }
 
+    /// Remove the literals of all clauses for which `dead` returns true from the storage. The
+    /// literals of the remaining clauses are moved to the front, their indices stay the same.
+    /// Dead clauses become empty and must not be used anymore.
+    pub fn compact(&mut self, dead: impl Fn(Clause) -> bool) {
+        let mut write = 0;
+        // clauses are stored in the order of their indices, so literals only ever move to the
+        // front and never overwrite a clause which has not been moved yet
+        for (index, range) in self.ranges.iter_mut().enumerate() {
+            if dead(Clause { index }) {
+                *range = Range { start: 0, end: 0 };
+            } else {
+                let len = range.end - range.start;
+                self.literals.copy_within(range.start..range.end, write);
+                *range = Range {
+                    start: write,
+                    end: write + len,
+                };
+                write += len;
+            }
+        }
+        self.literals.truncate(write);
+        self.literals.shrink_to_fit();
+    }
+
     /// Get the literals of a clause
     pub fn clause(&self, clause: Clause) -> &[Literal] {
-        // TODO this could be unchecked
         let range = &self.ranges[clause.index];
-        unsafe { self.literals.get_unchecked(range.start..range.end) }
+        debug_assert!(
+            range.start <= range.end && range.end <= self.literals.len(),
+            "clause {} has the literals {}..{} of {}",
+            clause,
+            range.start,
+            range.end,
+            self.literals.len()
+        );
+        #[cfg(feature = "checked-indexing")]
+        return &self.literals[range.start..range.end];
+        #[cfg(not(feature = "checked-indexing"))]
+        unsafe {
+            self.literals.get_unchecked(range.start..range.end)
+        }
+    }
+
+    /// All clauses in the database, whether they are active or not.
+    pub fn all_clauses(&self) -> impl Iterator<Item = Clause> {
+        (0..self.number_of_clauses()).map(|index| Clause { index })
+    }
+
+    /// Check that the literals of every clause are within the storage and do not overlap those
+    /// of another clause, and that the units are the clauses with a single literal.
+    pub fn check_ranges(&self) -> Result<(), String> {
+        let mut end = 0;
+        for (index, range) in self.ranges.iter().enumerate() {
+            if range.is_empty() {
+                continue;
+            }
+            if range.start < end || range.end > self.literals.len() {
+                return Err(format!(
+                    "clause c{} has the literals {}..{} of {}, the previous clause ends at {}",
+                    index,
+                    range.start,
+                    range.end,
+                    self.literals.len(),
+                    end
+                ));
+            }
+            end = range.end;
+        }
+        // the literals of deleted units may have been dropped
+        let unit = |range: &Range| range.is_empty() || range.end - range.start == 1;
+        match self
+            .units
+            .iter()
+            .find(|clause| !self.ranges.get(clause.index).is_some_and(unit))
+        {
+            Some(unit) => Err(format!("clause {} is listed as unit", unit)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the view has an entry for every clause in the database and that no clause
+    /// beyond them is active.
+    pub fn check_view(&self, view: &View) -> Result<(), String> {
+        let entries = &view.active.inner;
+        if entries.len() < self.number_of_clauses() {
+            return Err(format!(
+                "the view has {} clauses but the database has {}",
+                entries.len(),
+                self.number_of_clauses()
+            ));
+        }
+        match entries[self.number_of_clauses()..]
+            .iter()
+            .position(|&active| active)
+        {
+            Some(i) => Err(format!(
+                "clause c{} is active but not in the database",
+                self.number_of_clauses() + i
+            )),
+            None => Ok(()),
+        }
     }
 
     pub fn clauses<'a>(&'a self, view: &'a View) -> impl Iterator<Item = Clause> + 'a {
@@ -186,12 +428,18 @@ impl ClauseStorage {
         })
     }
 
+    /// All active clauses which consist of a single literal.
+    pub fn units<'a>(&'a self, view: &'a View) -> impl Iterator<Item = Clause> + 'a {
+        self.units
+            .iter()
+            .copied()
+            .filter(|&clause| view.is_active(clause))
+    }
+
     pub fn extract_true_unit(&self, clause: Clause) -> Option<Literal> {
-        let range = &self.ranges[clause.index];
-        if range.end - range.start == 1 {
-            Some(self.literals[range.start])
-        } else {
-            None
+        match self.kind(clause) {
+            ClauseKind::Unit(lit) => Some(lit),
+            _ => None,
         }
     }
 
@@ -231,29 +479,64 @@ impl ClauseStorage {
         None
     }
 
+    /// Classify a clause by its number of literals.
+    pub fn kind(&self, clause: Clause) -> ClauseKind {
+        match *self.clause(clause) {
+            [] => ClauseKind::Empty,
+            [a] => ClauseKind::Unit(a),
+            [a, b] => ClauseKind::Binary(a, b),
+            [a, b, ..] => ClauseKind::Long(a, b),
+        }
+    }
+
     /// Gets the first two literals of a clause. These are usually the ones being watched by the
-    /// propagator. If the clause has less than 2 literals this will return literals from the next
-    /// clause which causes undefined behavior in the checker.
+    /// propagator. Panics if the clause has less than 2 literals, use `kind` to check first.
     pub fn first_two_literals(&self, clause: Clause) -> (Literal, Literal) {
-        //assert!(clause.index < self.ranges.len());
-        //let start = self.ranges[clause.index].start;
-        //assert!(start + 1 < self.literals.len());
-        //(self.literals[start], self.literals[start + 1])
-        unsafe {
-            let start = self.ranges.get_unchecked(clause.index).start;
-            (
-                *self.literals.get_unchecked(start),
-                *self.literals.get_unchecked(start + 1),
-            )
+        debug_assert!(
+            clause.index < self.number_of_clauses(),
+            "clause {} is not in the database",
+            clause
+        );
+        match self.kind(clause) {
+            ClauseKind::Binary(a, b) | ClauseKind::Long(a, b) => (a, b),
+            kind => panic!("clause {} has less than two literals: {:?}", clause, kind),
         }
     }
 
+    /// Set the table to translate renamed variables back to their original names in output.
+    pub fn set_external_names(&mut self, names: Vec<u32>) {
+        self.external_names = Some(names);
+    }
+
+    /// The literal as it appeared in the input, undoing any renaming of variables.
+    pub fn external(&self, lit: Literal) -> i32 {
+        match &self.external_names {
+            Some(names) => {
+                let var = names[lit.var() as usize - 1] as i32;
+                if lit.is_negative() {
+                    -var
+                } else {
+                    var
+                }
+            }
+            None => lit.raw(),
+        }
+    }
+
+    /// The literals of a clause as they appeared in the input.
+    pub fn external_clause(&self, clause: Clause) -> Vec<i32> {
+        self.clause(clause)
+            .iter()
+            .map(|&lit| self.external(lit))
+            .collect()
+    }
+
     pub fn print_clause(&self, clause: Clause) -> String {
         format!(
             "[{}]",
             self.clause(clause)
                 .iter()
-                .map(|lit| lit.to_string())
+                .map(|&lit| self.external(lit).to_string())
                 .join(",")
         )
     }
@@ -267,41 +550,163 @@ impl ClauseStorage {
     }
 }
 
+/// Finds clauses in a clause storage by their literals, so that every clause is only stored once.
+/// Clauses are identified by a cheap signature of their literals, the literals themselves are
+/// only compared for clauses with the same signature and read from the storage directly.
+pub struct Interner {
+    // the most recently added clause for every signature
+    signatures: FxHashMap<u64, Clause>,
+    // the previously added clause with the same signature as the clause at this index
+    collisions: Vec<Option<Clause>>,
+}
+
+impl Interner {
+    fn with_capacity(clauses: usize) -> Self {
+        Interner {
+            signatures: FxHashMap::with_capacity_and_hasher(clauses, Default::default()),
+            collisions: Vec::with_capacity(clauses),
+        }
+    }
+
+    // Sum of a mixed hash of every literal. This does not depend on the order of the literals.
+    fn signature(clause: &[Literal]) -> u64 {
+        clause.iter().fold(clause.len() as u64, |sig, lit| {
+            let mut z = (lit.code() as u64).wrapping_mul(0x9e3779b97f4a7c15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            sig.wrapping_add(z ^ (z >> 31))
+        })
+    }
+
+    pub fn heap_size(&self) -> usize {
+        map_bytes::<u64, Clause>(self.signatures.capacity()) + vec_bytes(&self.collisions)
+    }
+
+    /// Look up a clause with exactly these literals. The literals have to be sorted and free of
+    /// duplicates, so that equal clauses are detected.
+    pub fn find(&self, clause_db: &ClauseStorage, clause: &[Literal]) -> Option<Clause> {
+        self.copies(clause_db, clause).next()
+    }
+
+    /// All copies of a clause with exactly these literals which have not been forgotten, the one
+    /// added last first. There are several only if they have been added with `add_copy`.
+    pub fn copies<'a>(
+        &'a self,
+        clause_db: &'a ClauseStorage,
+        clause: &'a [Literal],
+    ) -> impl Iterator<Item = Clause> + 'a {
+        let mut candidate = self.signatures.get(&Self::signature(clause)).copied();
+        core::iter::from_fn(move || {
+            while let Some(c_ref) = candidate {
+                candidate = self.collisions[c_ref.index];
+                if clause_db.clause(c_ref) == clause {
+                    return Some(c_ref);
+                }
+            }
+            None
+        })
+    }
+
+    /// Add a clause to the database unless it exists already.
+    pub fn add_clause(&mut self, clause_db: &mut ClauseStorage, clause: &[Literal]) -> Clause {
+        if let Some(c_ref) = self.find(clause_db, clause) {
+            return c_ref;
+        }
+        self.add_copy(clause_db, clause)
+    }
+
+    /// Add a clause to the database even if it exists already. Until it is forgotten, the copy is
+    /// found instead of the clauses added before it.
+    pub fn add_copy(&mut self, clause_db: &mut ClauseStorage, clause: &[Literal]) -> Clause {
+        let signature = Self::signature(clause);
+        let c_ref = clause_db.add_clause(clause.iter().cloned());
+        self.collisions
+            .push(self.signatures.insert(signature, c_ref));
+        c_ref
+    }
+
+    /// Stop finding the clause, adding the same literals again creates a new clause. This has to
+    /// be called before the literals of the clause are removed from the storage.
+    pub fn forget(&mut self, clause_db: &ClauseStorage, clause: Clause) {
+        let signature = Self::signature(clause_db.clause(clause));
+        let next = self.collisions[clause.index].take();
+        let Some(&head) = self.signatures.get(&signature) else {
+            return;
+        };
+        if head == clause {
+            match next {
+                Some(next) => self.signatures.insert(signature, next),
+                None => self.signatures.remove(&signature),
+            };
+            return;
+        }
+        // unlink the clause from the chain of clauses with the same signature
+        let mut current = head;
+        while let Some(previous) = self.collisions[current.index] {
+            if previous == clause {
+                self.collisions[current.index] = next;
+                return;
+            }
+            current = previous;
+        }
+    }
+}
+
+/// Adds clauses to a clause storage while making sure every clause is only stored once.
 pub struct Builder {
-    clauses: FxHashMap<BTreeSet<Literal>, Clause>,
+    interner: Interner,
     clause_db: ClauseStorage,
 }
 
 impl Builder {
-    pub fn new() -> Self {
+    /// Create a builder with space for the given number of clauses and literals in total.
+    pub fn with_capacity(clauses: usize, literals: usize) -> Self {
         Builder {
-            clauses: FxHashMap::default(),
+            interner: Interner::with_capacity(clauses),
             clause_db: ClauseStorage {
-                literals: vec![],
-                ranges: vec![],
-                max_literal: 0,
+                literals: Vec::with_capacity(literals),
+                ranges: Vec::with_capacity(clauses),
+                units: vec![],
+                max_variable: 0,
+                reserved_clauses: 0,
+                external_names: None,
             },
         }
     }
 
-    pub fn add_clause(&mut self, clause: BTreeSet<Literal>) -> Clause {
-        if let Some(&c_ref) = self.clauses.get(&clause) {
-            c_ref
-        } else {
-            let c_ref = self.clause_db.add_clause(clause.iter().cloned());
-            self.clauses.insert(clause, c_ref);
-            c_ref
-        }
+    /// Add a clause to the database unless it exists already. The literals have to be sorted and
+    /// free of duplicates, so that equal clauses are detected.
+    pub fn add_clause(&mut self, clause: &[Literal]) -> Clause {
+        self.interner.add_clause(&mut self.clause_db, clause)
+    }
+
+    /// Add a clause to the database even if it exists already, see [`Interner::add_copy`].
+    pub fn add_copy(&mut self, clause: &[Literal]) -> Clause {
+        self.interner.add_copy(&mut self.clause_db, clause)
+    }
+
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Access the interner and the clause storage at the same time.
+    pub fn parts_mut(&mut self) -> (&mut Interner, &mut ClauseStorage) {
+        (&mut self.interner, &mut self.clause_db)
+    }
+
+    pub fn finish(self) -> ClauseStorage {
+        self.finish_with_interner().0
     }
 
-    pub fn finish(mut self) -> ClauseStorage {
-        self.clause_db.max_literal = self
+    /// Finish the clause storage but keep the interner around to add more clauses later on. The
+    /// storage may be empty or hold only the empty clause, then its largest variable is 0.
+    pub fn finish_with_interner(mut self) -> (ClauseStorage, Interner) {
+        self.clause_db.max_variable = self
             .clause_db
             .literals
             .iter()
-            .map(|lit| lit.raw().abs())
+            .map(|lit| lit.var())
             .max()
-            .expect("clause storage cannot be empty");
-        self.clause_db
+            .unwrap_or(0);
+        (self.clause_db, self.interner)
     }
 }
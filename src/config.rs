@@ -0,0 +1,127 @@
+//! Defaults for the command line options from a `ratify.toml` file and `RATIFY_*` environment
+//! variables, so that long command lines do not have to be repeated. Every option with a long
+//! name can be set in both, `--max-memory 4096` is `max-memory = 4096` in the file and
+//! `RATIFY_MAX_MEMORY=4096` in the environment. Options on the command line override the
+//! environment, which overrides the file.
+//!
+//! The file is read from the path given with `--config` or `RATIFY_CONFIG`, otherwise from
+//! `ratify.toml` in the current directory if there is one. Its top level applies to every command
+//! which has the option, the tables `[serve]` and `[watch]` only to these subcommands.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use clap::{Arg, Command, CommandFactory};
+
+use crate::Cli;
+
+const DEFAULT_PATH: &str = "ratify.toml";
+const CONFIG_ENV: &str = "RATIFY_CONFIG";
+
+/// The command line interface with the defaults of the config file and the environment.
+pub fn command() -> Result<Command> {
+    let command = Cli::command().arg(
+        Arg::new("config")
+            .long("config")
+            .value_name("PATH")
+            .global(true)
+            .help(format!(
+                "Read defaults for the options from this file instead of {}",
+                DEFAULT_PATH
+            )),
+    );
+    let table = match config_path() {
+        Some(path) => read(Path::new(&path))?,
+        None if Path::new(DEFAULT_PATH).is_file() => read(Path::new(DEFAULT_PATH))?,
+        None => toml::Table::new(),
+    };
+    configure(command, &toml::Table::new(), &table)
+}
+
+// The path given with --config, which has to be known before the arguments are parsed.
+fn config_path() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next();
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(path.to_string());
+        }
+    }
+    std::env::var(CONFIG_ENV).ok()
+}
+
+fn read(path: &Path) -> Result<toml::Table> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    text.parse()
+        .with_context(|| format!("invalid config file {}", path.display()))
+}
+
+// Set the defaults of the command and its subcommands. The options in `own` have to exist, those
+// inherited from the top level are only set if the command has them.
+fn configure(mut command: Command, inherited: &toml::Table, own: &toml::Table) -> Result<Command> {
+    let ids = command
+        .get_arguments()
+        .filter(|arg| arg.get_long().is_some_and(|long| long != "config"))
+        .map(|arg| arg.get_id().clone())
+        .collect::<Vec<_>>();
+    for id in ids {
+        command = command.mut_arg(id, |arg| {
+            let long = arg.get_long().unwrap_or_default().to_uppercase();
+            arg.env(format!("RATIFY_{}", long.replace('-', "_")))
+        });
+    }
+
+    for (key, value, required) in inherited
+        .iter()
+        .map(|(key, value)| (key, value, false))
+        .chain(own.iter().map(|(key, value)| (key, value, true)))
+    {
+        if value.is_table() {
+            if command.find_subcommand(key).is_none() {
+                bail!("unknown table [{}] in the config file", key);
+            }
+            continue;
+        }
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()));
+        let id = match arg {
+            Some(arg) => arg.get_id().clone(),
+            None if required => bail!("unknown option {} in the config file", key),
+            None => continue,
+        };
+        let value = match value {
+            toml::Value::String(s) => s.clone(),
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Float(f) => f.to_string(),
+            toml::Value::Boolean(b) => b.to_string(),
+            _ => bail!(
+                "the option {} in the config file has to be a single value",
+                key
+            ),
+        };
+        command = command.mut_arg(id, |arg| arg.default_value(value));
+    }
+
+    let subcommands = command.get_subcommands().cloned().collect::<Vec<_>>();
+    for subcommand in subcommands {
+        let name = subcommand.get_name().to_string();
+        let mut inherited = inherited.clone();
+        inherited.extend(
+            own.iter()
+                .filter(|(_, value)| !value.is_table())
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        let own = match own.get(&name) {
+            Some(toml::Value::Table(table)) => table.clone(),
+            _ => toml::Table::new(),
+        };
+        let configured = configure(subcommand, &inherited, &own)
+            .with_context(|| format!("in the table [{}]", name))?;
+        command = command.mut_subcommand(name, |_| configured);
+    }
+    Ok(command)
+}
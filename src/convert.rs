@@ -0,0 +1,614 @@
+//! Conversions between proofs in text DRAT, binary DRAT and LRAT. DRAT is converted to LRAT by
+//! trimming it like [`trim_str`](crate::trim_str), which checks the proof and finds the hints of
+//! the needed lemmas. LRAT is converted to DRAT by dropping the hints, which needs the formula to
+//! know the clauses it deletes by their id.
+//!
+//! The ids of LRAT proofs which are read are checked: every lemma needs an id larger than all
+//! before it and every deletion the id of an active clause, otherwise the line of the step is
+//! reported. From LRAT to LRAT this is only done if the formula is given.
+//!
+//! With [`CheckerOptions::validate_lrat`] the LRAT written for a DRAT proof is parsed again and
+//! checked with nothing but its hints, so that a wrong hint fails the conversion instead of
+//! producing a proof other checkers reject.
+
+use crate::prelude::*;
+use core::fmt::{self, Write};
+
+use crate::{
+    common::{FxHashMap, FxHashSet, Literal, Origins, RawLemma},
+    parser::{
+        self, drat,
+        lrat::{self, LratStep},
+    },
+    trim::trim_parsed,
+    CheckReport, CheckerOptions, Error, Instant, ProofStep, Result, TrimStrategy, Verdict,
+};
+
+/// The formats a proof can be converted between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Format {
+    Drat,
+    BinaryDrat,
+    Lrat,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Drat => write!(f, "DRAT"),
+            Format::BinaryDrat => write!(f, "binary DRAT"),
+            Format::Lrat => write!(f, "LRAT"),
+        }
+    }
+}
+
+/// A proof converted with [`convert`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Converted {
+    /// The verdict of the check if the proof had to be checked to convert it, which is the case
+    /// from DRAT to LRAT. The output is empty unless the proof has been verified.
+    pub report: Option<CheckReport>,
+    pub output: Vec<u8>,
+    /// The number of proof steps in the output.
+    pub steps: usize,
+}
+
+/// Convert the proof at the given path between the formats, see [`convert`]. The formula is only
+/// read if it is given.
+#[cfg(feature = "fs")]
+pub fn convert_files(
+    cnf: Option<&str>,
+    proof: &str,
+    from: Format,
+    to: Format,
+    options: CheckerOptions,
+    strategy: TrimStrategy,
+) -> Result<Converted> {
+    let cnf = cnf.map(std::fs::read_to_string).transpose()?;
+    let proof = std::fs::read(proof)?;
+    convert(cnf.as_deref(), &proof, from, to, options, strategy)
+}
+
+/// Convert a proof between the formats. The formula in DIMACS format is needed if either format
+/// is LRAT, from LRAT to LRAT it is optional and only used to check the clause ids. Converting
+/// DRAT to LRAT trims the proof with the strategy, of the options only those listed for
+/// [`trim_str`](crate::trim_str) apply. The maximum clause length applies to every conversion and
+/// stops reading the proof at the first clause which is too long.
+pub fn convert(
+    cnf: Option<&str>,
+    proof: &[u8],
+    from: Format,
+    to: Format,
+    options: CheckerOptions,
+    strategy: TrimStrategy,
+) -> Result<Converted> {
+    let started = Instant::now();
+    let formula = || {
+        let cnf = cnf.ok_or_else(|| {
+            Error::InvalidOptions(format!(
+                "converting from {} to {} needs the formula",
+                from, to
+            ))
+        })?;
+        parser::cnf::parse(cnf)
+    };
+    let converted = |output: Vec<u8>, steps: usize| Converted {
+        report: None,
+        output,
+        steps,
+    };
+    match (from, to) {
+        (Format::Lrat, Format::Lrat) => {
+            let mut clauses = match cnf {
+                Some(_) => Some(LratClauses::new(&formula()?.1)),
+                None => None,
+            };
+            let mut steps = Vec::new();
+            for step in lrat_steps(text(proof)?, &options) {
+                let (line, step) = step?;
+                if let Some(clauses) = &mut clauses {
+                    clauses
+                        .apply(&step)
+                        .map_err(|message| Error::Parse { line, message })?;
+                }
+                steps.push(step);
+            }
+            Ok(converted(write_lrat(&steps).into_bytes(), steps.len()))
+        }
+        (Format::Lrat, to) => {
+            let (_, formula) = formula()?;
+            let steps = strip_hints(&formula, text(proof)?, &options)?;
+            Ok(converted(write(to, &steps), steps.len()))
+        }
+        (from, Format::Lrat) => {
+            options.validate()?;
+            let (header, formula) = formula()?;
+            let (steps, origins) = read_drat(from, proof, &options)?;
+            let lemmas = steps
+                .into_iter()
+                .enumerate()
+                .map(|(i, step)| {
+                    let (clause, add) = match step {
+                        ProofStep::Add(clause) => (clause, true),
+                        ProofStep::Delete(clause) => (clause, false),
+                    };
+                    let clause = parser::normalize_clause(clause).ok_or(Error::Parse {
+                        line: i + 1,
+                        message: "clause contains the literal 0 or one out of range".to_string(),
+                    })?;
+                    Ok(if add {
+                        RawLemma::Add(clause)
+                    } else {
+                        RawLemma::Del(clause)
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let parsing = started.elapsed();
+            let validate = options.validate_lrat.then(|| formula.clone());
+            let trimmed =
+                trim_parsed(header, formula, lemmas, origins, options, strategy, parsing)?;
+            let (output, steps) = if trimmed.report.verdict == Verdict::Verified {
+                let output = write_lrat(&trimmed.lrat);
+                if let Some(formula) = validate {
+                    check_lrat(&formula, &output)?;
+                }
+                (output.into_bytes(), trimmed.lrat.len())
+            } else {
+                (vec![], 0)
+            };
+            Ok(Converted {
+                report: Some(trimmed.report),
+                output,
+                steps,
+            })
+        }
+        (from, to) => {
+            let (steps, _) = read_drat(from, proof, &options)?;
+            Ok(converted(write(to, &steps), steps.len()))
+        }
+    }
+}
+
+// The proof as text, for the formats which are not binary.
+fn text(proof: &[u8]) -> Result<&str> {
+    core::str::from_utf8(proof).map_err(|e| Error::Parse {
+        line: proof[..e.valid_up_to()]
+            .iter()
+            .filter(|&&byte| byte == b'\n')
+            .count()
+            + 1,
+        message: "the proof is not text, it may be in binary DRAT".to_string(),
+    })
+}
+
+// Read a proof in text or binary DRAT, keeping the literals in the order they are written. Binary
+// DRAT has no lines, the steps are located by their position instead. Stops at the first clause
+// which is longer than allowed.
+fn read_drat(
+    format: Format,
+    proof: &[u8],
+    options: &CheckerOptions,
+) -> Result<(Vec<ProofStep>, Origins)> {
+    let max = options.max_clause_length;
+    if format == Format::BinaryDrat {
+        return Ok((drat::parse_binary(proof, max)?, Origins::positions()));
+    }
+    let proof = text(proof)?;
+    let steps = drat::parse_spanned(proof, max)?.steps;
+    let mut origins = Origins::default();
+    let (mut line, mut offset) = (1, 0);
+    for step in &steps {
+        line += proof[offset..step.span.start].matches('\n').count();
+        offset = step.span.start;
+        origins.record(Some(line));
+    }
+    let steps = steps
+        .into_iter()
+        .map(|step| {
+            let clause = step.clause.literals.iter().map(Literal::raw).collect();
+            if step.deletion {
+                ProofStep::Delete(clause)
+            } else {
+                ProofStep::Add(clause)
+            }
+        })
+        .collect();
+    Ok((steps, origins))
+}
+
+// The steps of an LRAT proof with their lines. Stops at the first lemma which is longer than
+// allowed.
+fn lrat_steps<'a>(
+    proof: &'a str,
+    options: &'a CheckerOptions,
+) -> impl Iterator<Item = Result<(usize, LratStep)>> + 'a {
+    proof
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| lrat::step(i + 1, line).map(|step| step.map(|step| (i + 1, step))))
+        .enumerate()
+        .map(move |(index, step)| {
+            let (line, step) = step?;
+            if let LratStep::Add { clause, .. } = &step {
+                options.check_clause_length(index, Some(line), clause.len())?;
+            }
+            Ok((line, step))
+        })
+}
+
+// The lemmas and deletions of an LRAT proof as DRAT, looking up the deleted clauses by their id.
+fn strip_hints(
+    formula: &[Vec<Literal>],
+    proof: &str,
+    options: &CheckerOptions,
+) -> Result<Vec<ProofStep>> {
+    let mut clauses = LratClauses::new(formula);
+    let mut steps = Vec::new();
+    for step in lrat_steps(proof, options) {
+        let (line, step) = step?;
+        let invalid = |message| Error::Parse { line, message };
+        match step {
+            LratStep::Add { id, clause, .. } => {
+                clauses.add(id, clause.clone()).map_err(invalid)?;
+                steps.push(ProofStep::Add(clause));
+            }
+            LratStep::Delete { clauses: ids, .. } => {
+                for id in ids {
+                    let clause = clauses.delete(id).map_err(invalid)?;
+                    steps.push(ProofStep::Delete(clause));
+                }
+            }
+        }
+    }
+    Ok(steps)
+}
+
+// The active clauses of an LRAT proof by their id. The clauses of the formula have the ids 1 to n.
+struct LratClauses {
+    active: FxHashMap<u64, Vec<i32>>,
+    // the largest id so far
+    last: u64,
+}
+
+impl LratClauses {
+    fn new(formula: &[Vec<Literal>]) -> Self {
+        let active = formula
+            .iter()
+            .enumerate()
+            .map(|(i, clause)| (i as u64 + 1, clause.iter().map(Literal::raw).collect()))
+            .collect();
+        LratClauses {
+            active,
+            last: formula.len() as u64,
+        }
+    }
+
+    // Add a clause, its id has to be larger than all before it.
+    fn add(&mut self, id: u64, clause: Vec<i32>) -> core::result::Result<(), String> {
+        if id <= self.last {
+            return Err(if self.active.contains_key(&id) {
+                format!("clause {} is added while it is active", id)
+            } else {
+                format!(
+                    "clause {} is added after clause {}, ids have to increase",
+                    id, self.last
+                )
+            });
+        }
+        self.last = id;
+        self.active.insert(id, clause);
+        Ok(())
+    }
+
+    // Add or delete the clauses of a step.
+    fn apply(&mut self, step: &LratStep) -> core::result::Result<(), String> {
+        match step {
+            LratStep::Add { id, clause, .. } => self.add(*id, clause.clone()),
+            LratStep::Delete { clauses, .. } => {
+                clauses.iter().try_for_each(|&id| self.delete(id).map(drop))
+            }
+        }
+    }
+
+    // Delete an active clause and return its literals.
+    fn delete(&mut self, id: u64) -> core::result::Result<Vec<i32>, String> {
+        self.active.remove(&id).ok_or_else(|| {
+            if id <= self.last {
+                format!("deletion of clause {} which has already been deleted", id)
+            } else {
+                format!("deletion of unknown clause {}", id)
+            }
+        })
+    }
+}
+
+// Check an LRAT proof only with its hints: every hint of a lemma has to become unit or falsified
+// under the negation of the lemma and the literals of the hints before it, and the last one has
+// to be falsified. A lemma with RAT on its first literal has hints for the resolvent with every
+// active clause containing the negated pivot. The proof has to add the empty clause.
+fn check_lrat(formula: &[Vec<Literal>], proof: &str) -> Result<()> {
+    let mut clauses = LratClauses::new(formula);
+    let mut derived_empty = false;
+    for (i, line) in proof.lines().enumerate() {
+        let invalid = |message: String| Error::InvalidLrat {
+            line: i + 1,
+            message,
+        };
+        match lrat::step(i + 1, line).transpose()? {
+            None => {}
+            Some(LratStep::Add { id, clause, hints }) => {
+                check_hints(&clauses.active, &clause, &hints).map_err(invalid)?;
+                derived_empty |= clause.is_empty();
+                clauses.add(id, clause).map_err(invalid)?;
+            }
+            Some(LratStep::Delete { clauses: ids, .. }) => {
+                for id in ids {
+                    clauses.delete(id).map_err(invalid)?;
+                }
+            }
+        }
+    }
+    if !derived_empty {
+        return Err(Error::InvalidLrat {
+            line: proof.lines().count(),
+            message: "the proof does not add the empty clause".to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Check that the hints show that the lemma has RUP or RAT on its first literal.
+fn check_hints(
+    clauses: &FxHashMap<u64, Vec<i32>>,
+    lemma: &[i32],
+    hints: &[i64],
+) -> core::result::Result<(), String> {
+    let mut assigned = lemma.iter().map(|&lit| -lit).collect::<FxHashSet<_>>();
+    let rat = hints
+        .iter()
+        .position(|&hint| hint < 0)
+        .unwrap_or(hints.len());
+    if propagate(clauses, &mut assigned, &hints[..rat])? {
+        return Ok(());
+    }
+    let Some(&pivot) = lemma.first().filter(|_| rat < hints.len()) else {
+        return Err("the hints do not derive a conflict".to_string());
+    };
+    // the hints for every resolvent start with the negated id of the clause
+    let mut resolvents = FxHashMap::default();
+    let mut rest = &hints[rat..];
+    while let Some((&start, tail)) = rest.split_first() {
+        let end = tail.iter().position(|&hint| hint < 0).unwrap_or(tail.len());
+        resolvents.insert(start.unsigned_abs(), &tail[..end]);
+        rest = &tail[end..];
+    }
+    for (&id, clause) in clauses {
+        if !clause.contains(&-pivot) {
+            continue;
+        }
+        let Some(&hints) = resolvents.get(&id) else {
+            return Err(format!(
+                "no hints for the resolvent with clause {} on {}",
+                id, pivot
+            ));
+        };
+        let mut assigned = assigned.clone();
+        let mut tautology = false;
+        for &lit in clause.iter().filter(|&&lit| lit != -pivot) {
+            tautology |= assigned.contains(&lit);
+            assigned.insert(-lit);
+        }
+        if !tautology && !propagate(clauses, &mut assigned, hints)? {
+            return Err(format!(
+                "the hints for the resolvent with clause {} do not derive a conflict",
+                id
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Assign the literal every hint becomes unit on, returns true once a hint is falsified.
+fn propagate(
+    clauses: &FxHashMap<u64, Vec<i32>>,
+    assigned: &mut FxHashSet<i32>,
+    hints: &[i64],
+) -> core::result::Result<bool, String> {
+    for &hint in hints {
+        let clause = clauses
+            .get(&hint.unsigned_abs())
+            .ok_or_else(|| format!("hint {} is not an active clause", hint))?;
+        let mut open = clause.iter().filter(|&&lit| !assigned.contains(&-lit));
+        match (open.next(), open.next()) {
+            (None, _) => return Ok(true),
+            (Some(&lit), None) if !assigned.contains(&lit) => {
+                assigned.insert(lit);
+            }
+            _ => return Err(format!("hint {} is not unit", hint)),
+        }
+    }
+    Ok(false)
+}
+
+// Write DRAT steps in text or binary DRAT.
+fn write(format: Format, steps: &[ProofStep]) -> Vec<u8> {
+    match format {
+        Format::BinaryDrat => write_binary_drat(steps),
+        _ => write_drat(steps).into_bytes(),
+    }
+}
+
+/// The formula in DIMACS format, with a header for the largest variable in it.
+pub fn write_dimacs(formula: &[Vec<i32>]) -> String {
+    let vars = formula
+        .iter()
+        .flatten()
+        .map(|lit| lit.unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    let mut text = format!("p cnf {} {}\n", vars, formula.len());
+    for clause in formula {
+        for lit in clause {
+            let _ = write!(text, "{} ", lit);
+        }
+        text.push_str("0\n");
+    }
+    text
+}
+
+/// The proof in text DRAT, one step per line.
+pub fn write_drat(steps: &[ProofStep]) -> String {
+    let mut text = String::new();
+    for step in steps {
+        let clause = match step {
+            ProofStep::Add(clause) => clause,
+            ProofStep::Delete(clause) => {
+                text.push_str("d ");
+                clause
+            }
+        };
+        for lit in clause {
+            let _ = write!(text, "{} ", lit);
+        }
+        text.push_str("0\n");
+    }
+    text
+}
+
+/// The proof in binary DRAT, see [`drat::parse_binary`] for the encoding.
+pub fn write_binary_drat(steps: &[ProofStep]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for step in steps {
+        let clause = match step {
+            ProofStep::Add(clause) => {
+                bytes.push(b'a');
+                clause
+            }
+            ProofStep::Delete(clause) => {
+                bytes.push(b'd');
+                clause
+            }
+        };
+        for &lit in clause {
+            let mut code = 2 * u64::from(lit.unsigned_abs()) + u64::from(lit < 0);
+            while code >= 0x80 {
+                bytes.push((code & 0x7f) as u8 | 0x80);
+                code >>= 7;
+            }
+            bytes.push(code as u8);
+        }
+        bytes.push(0);
+    }
+    bytes
+}
+
+/// The proof in LRAT, one step per line.
+pub fn write_lrat(steps: &[LratStep]) -> String {
+    let mut text = String::new();
+    for step in steps {
+        match step {
+            LratStep::Add { id, clause, hints } => {
+                let _ = write!(text, "{} ", id);
+                for lit in clause {
+                    let _ = write!(text, "{} ", lit);
+                }
+                text.push_str("0 ");
+                for hint in hints {
+                    let _ = write!(text, "{} ", hint);
+                }
+            }
+            LratStep::Delete { id, clauses } => {
+                let _ = write!(text, "{} d ", id);
+                for clause in clauses {
+                    let _ = write!(text, "{} ", clause);
+                }
+            }
+        }
+        text.push_str("0\n");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMULA: &str = "p cnf 2 2\n1 2 0\n-1 2 0\n";
+
+    // The line and message of the error converting the LRAT proof to LRAT and to DRAT.
+    fn invalid(proof: &str) -> [(usize, String); 2] {
+        [Format::Lrat, Format::Drat].map(|to| {
+            let converted = convert(
+                Some(FORMULA),
+                proof.as_bytes(),
+                Format::Lrat,
+                to,
+                CheckerOptions::default(),
+                TrimStrategy::default(),
+            );
+            match converted {
+                Err(Error::Parse { line, message }) => (line, message),
+                Err(e) => panic!("'{}' fails with {}", proof, e),
+                Ok(_) => panic!("'{}' is accepted", proof),
+            }
+        })
+    }
+
+    #[test]
+    fn reused_ids() {
+        for (proof, line, message) in [
+            (
+                "3 2 0 1 2 0\n3 -2 0 0\n",
+                2,
+                "clause 3 is added while it is active",
+            ),
+            ("2 2 0 1 2 0\n", 1, "clause 2 is added while it is active"),
+            (
+                "5 2 0 1 2 0\n4 -2 0 0\n",
+                2,
+                "clause 4 is added after clause 5, ids have to increase",
+            ),
+            (
+                "3 2 0 1 2 0\n3 d 3 0\n3 2 0 1 2 0\n",
+                3,
+                "clause 3 is added after clause 3, ids have to increase",
+            ),
+        ] {
+            for error in invalid(proof) {
+                assert_eq!(error, (line, message.to_string()), "for '{}'", proof);
+            }
+        }
+    }
+
+    #[test]
+    fn deleted_ids() {
+        for (proof, line, message) in [
+            ("2 d 3 0\n", 1, "deletion of unknown clause 3"),
+            (
+                "2 d 1 0\n2 d 1 0\n",
+                2,
+                "deletion of clause 1 which has already been deleted",
+            ),
+        ] {
+            for error in invalid(proof) {
+                assert_eq!(error, (line, message.to_string()), "for '{}'", proof);
+            }
+        }
+    }
+
+    #[test]
+    fn valid_ids() {
+        let proof = "3 2 0 1 2 0\n3 d 1 2 0\n4 0 3 0\n";
+        let converted = convert(
+            Some(FORMULA),
+            proof.as_bytes(),
+            Format::Lrat,
+            Format::Lrat,
+            CheckerOptions::default(),
+            TrimStrategy::default(),
+        )
+        .unwrap();
+        assert_eq!(converted.steps, 3);
+    }
+}
@@ -0,0 +1,123 @@
+//! Explains why a proof has been rejected, in comment lines which are colored on a terminal.
+
+use std::{fmt::Display, io::IsTerminal};
+
+use itertools::Itertools;
+use ratify::{CheckReport, RejectedLemma, Verdict};
+
+use crate::Flags;
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[33m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Where the comment lines explaining a verdict are printed.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Stdout,
+    Stderr,
+}
+
+impl Target {
+    /// Print a comment line.
+    pub fn comment(self, text: impl Display) {
+        match self {
+            Target::Stdout => println!("c {}", text),
+            Target::Stderr => eprintln!("c {}", text),
+        }
+    }
+
+    fn is_terminal(self) -> bool {
+        match self {
+            Target::Stdout => std::io::stdout().is_terminal(),
+            Target::Stderr => std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    // Colors are only used on a terminal and if NO_COLOR is not set, see https://no-color.org.
+    fn detect(target: Target) -> Self {
+        let color = target.is_terminal()
+            && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+        Style { color }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Print why the proof of the report has been rejected.
+pub fn print_rejection(report: &CheckReport, flags: &Flags) {
+    let target = flags.diagnostics;
+    let style = Style::detect(target);
+    let reason = report.reason.as_deref().unwrap_or_default();
+    target.comment(format_args!("{} {}", style.paint(RED, "error:"), reason));
+    if let Some(lemma) = &report.rejected_lemma {
+        target.comment(format_args!(
+            "  {} {}",
+            style.paint(BOLD, "proof step:"),
+            lemma.step
+        ));
+        if let Some(line) = lemma.line {
+            target.comment(format_args!("  {} {}", style.paint(BOLD, "line:"), line));
+        }
+        let clause = match lemma.clause.is_empty() {
+            true => "the empty clause".to_string(),
+            false => format!("{} 0", lemma.clause.iter().join(" ")),
+        };
+        target.comment(format_args!("  {} {}", style.paint(BOLD, "lemma:"), clause));
+        target.comment(format_args!(
+            "  {} {}",
+            style.paint(BOLD, "active clauses:"),
+            lemma.active_clauses
+        ));
+    }
+    if let Some(hint) = hint(report, flags) {
+        target.comment(format_args!("  {} {}", style.paint(YELLOW, "hint:"), hint));
+    }
+}
+
+fn hint(report: &CheckReport, flags: &Flags) -> Option<String> {
+    match report.verdict {
+        Verdict::Refuted { .. } => match &report.rejected_lemma {
+            Some(RejectedLemma {
+                rat_pivot: Some(pivot),
+                ..
+            }) => Some(format!(
+                "the lemma has RAT on pivot {}, but --rup-only was set",
+                pivot
+            )),
+            Some(RejectedLemma {
+                needs_deleted: true,
+                ..
+            }) if !flags.ignore_deletions => Some(
+                "the lemma has RUP with the clauses the proof deleted before it, try \
+                 --ignore-deletions if they are still needed"
+                    .to_string(),
+            ),
+            Some(_) => Some("check that the proof belongs to this formula".to_string()),
+            // with --drup the first lemma which requires RAT is reported
+            None => Some("the lemma has RAT, drop --drup to accept it".to_string()),
+        },
+        Verdict::NoConflict if !flags.implicit_empty => Some(
+            "the proof does not add the empty clause, --implicit-empty accepts it if it has RUP"
+                .to_string(),
+        ),
+        Verdict::EarlyRefutation => Some(
+            "the formula propagates to a conflict on its own, --implicit-empty accepts this"
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
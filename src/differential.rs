@@ -0,0 +1,79 @@
+//! `--diff-check <cmd>` runs an external checker such as drat-trim on the same formula and proof
+//! once ratify is done and compares the verdicts. The command is split at whitespace and the paths
+//! of the formula and proof are appended to it. The verdict of the external checker is taken from
+//! the `s VERIFIED` or `s NOT VERIFIED` line it prints, its exit code is ignored because checkers
+//! do not agree on it.
+//!
+//! Only verdicts which accept or reject the proof are compared. If ratify stopped early or the
+//! external checker printed no verdict, the comparison is skipped.
+
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use ratify::{CheckReport, Error};
+
+use crate::diagnostic::Target;
+
+/// What the external checker reported.
+pub struct External {
+    /// Whether it verified the proof, `None` if it printed no verdict.
+    pub verified: Option<bool>,
+    pub elapsed: Duration,
+}
+
+/// Run the external checker on the formula and proof.
+pub fn run(command: &str, cnf: &str, proof: &str) -> Result<External> {
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        return Err(Error::InvalidOptions("--diff-check needs a command".to_string()).into());
+    };
+    let started = Instant::now();
+    let output = Command::new(program)
+        .args(words)
+        .args([cnf, proof])
+        .output()
+        .map_err(|e| anyhow!("cannot run the external checker `{}`: {}", command, e))?;
+    let elapsed = started.elapsed();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let verified = stdout.lines().find_map(|line| match line.trim_end() {
+        "s VERIFIED" => Some(true),
+        "s NOT VERIFIED" => Some(false),
+        _ => None,
+    });
+    Ok(External { verified, elapsed })
+}
+
+/// Print both verdicts and how long the checks took. Returns false if the verdicts differ.
+pub fn compare(report: &CheckReport, external: &External, target: Target) -> bool {
+    let timings = report.timings;
+    let elapsed = timings.parsing + timings.preprocessing + timings.checking;
+    let ours = if report.verdict.is_verified() {
+        Some(true)
+    } else if report.verdict.is_rejected() {
+        Some(false)
+    } else {
+        None
+    };
+    let name = |verified: Option<bool>| match verified {
+        Some(true) => "VERIFIED",
+        Some(false) => "NOT VERIFIED",
+        None => "no verdict",
+    };
+    let (agrees, outcome) = match (ours, external.verified) {
+        (Some(ours), Some(theirs)) if ours == theirs => (true, "agree"),
+        (Some(_), Some(_)) => (false, "DISAGREE"),
+        _ => (true, "not compared"),
+    };
+    target.comment(format_args!(
+        "diff-check: ratify {} in {:.3}s, external checker {} in {:.3}s, {}",
+        name(ours),
+        elapsed.as_secs_f64(),
+        name(external.verified),
+        external.elapsed.as_secs_f64(),
+        outcome
+    ));
+    agrees
+}
@@ -0,0 +1,162 @@
+use crate::prelude::*;
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{common::Origins, Verdict};
+
+/// Everything that can go wrong while checking a proof. Besides failures to read the input this
+/// distinguishes the different reasons for rejecting a proof.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    #[cfg(feature = "std")]
+    Io(#[from] std::io::Error),
+    /// A line of the formula or proof could not be parsed. Lines are counted from 1, for clauses
+    /// given in memory this is the position of the clause.
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    /// The options are out of range or cannot be combined.
+    #[error("{0}")]
+    InvalidOptions(String),
+    /// Unit propagation on the formula yields a conflict before the proof is checked. This is
+    /// only an error without --implicit-empty.
+    #[error("the formula propagates to a conflict before the proof is checked")]
+    EarlyConflict,
+    /// The lemma at this proof step is neither RUP nor RAT, or not RUP with --rup-only. See
+    /// [`RejectedLemma`](crate::RejectedLemma) for the fields.
+    #[error(
+        "{}#{step} lemma ([{}]) does not have {}",
+        at(.line),
+        .clause.iter().join(","),
+        if *.rup_only { "RUP" } else { "RUP or RAT" }
+    )]
+    NotRedundant {
+        step: usize,
+        line: Option<usize>,
+        clause: Vec<i32>,
+        rup_only: bool,
+        active_clauses: usize,
+        rat_pivot: Option<i32>,
+        needs_deleted: bool,
+    },
+    /// All lemmas have been checked but none of them derived a conflict.
+    #[error("no conflict detected")]
+    NoConflict,
+    /// Lemmas required RAT with --drup, given by their proof step and pivot.
+    #[error(
+        "proof is not a DRUP proof, {} lemmas require RAT: {}",
+        .steps.len(),
+        .steps.iter().map(|(step, pivot)| format!("#{} (pivot {})", step, pivot)).join(", ")
+    )]
+    NotDrup { steps: Vec<(usize, i32)> },
+    /// A lemma of a proof which is checked while it arrives introduces more clauses than the
+    /// bounds given up front. The step counts the skipped lemmas as well.
+    #[error("{}#{step} lemma exceeds the bounds given for the proof", at(.line))]
+    OutOfBounds { step: usize, line: Option<usize> },
+    /// A proof step has a clause with more literals than allowed by
+    /// [`CheckerOptions::max_clause_length`](crate::CheckerOptions::max_clause_length). The step
+    /// counts the skipped lemmas as well. The parsers stop reading the clause once it is too
+    /// long, so the length may be less than that of the whole clause.
+    #[error(
+        "{}#{step} clause of at least {length} literals is longer than the maximum of {max}",
+        at(.line)
+    )]
+    ClauseTooLong {
+        step: usize,
+        line: Option<usize>,
+        length: usize,
+        max: usize,
+    },
+    /// More proof steps have been skipped while preprocessing than allowed by
+    /// [`CheckerOptions::max_ignored`](crate::CheckerOptions::max_ignored).
+    #[error(
+        "{ignored} proof steps have been ignored while preprocessing, at most {max} are allowed"
+    )]
+    TooManyIgnored { ignored: usize, max: usize },
+    /// A proof step deletes a clause which is not active, which is only an error with
+    /// [`CheckerOptions::strict_deletions`](crate::CheckerOptions::strict_deletions). The step
+    /// counts the skipped lemmas as well.
+    #[error(
+        "{}#{step} deletion of clause ([{}]) which is not active",
+        at(.line),
+        .clause.iter().join(",")
+    )]
+    MissingDeletion {
+        step: usize,
+        line: Option<usize>,
+        clause: Vec<i32>,
+    },
+    /// A checkpoint could not be written or read back.
+    #[error("invalid checkpoint: {0}")]
+    Checkpoint(String),
+    /// A step has been passed to an `OnlineChecker` after it rejected the proof.
+    #[error("#{step} the proof has already been rejected")]
+    AlreadyRejected { step: usize },
+    /// The two propagators of --cross-check disagree at this proof step, which is a bug in one of
+    /// them rather than a problem of the proof.
+    #[error("{}#{step} {message}", at(.line))]
+    Diverged {
+        step: usize,
+        line: Option<usize>,
+        message: String,
+    },
+    /// An invariant of the checker state does not hold after this proof step, which is only
+    /// checked with [`CheckerOptions::paranoid`](crate::CheckerOptions::paranoid). Like
+    /// `Diverged` this is a bug in the checker.
+    #[error("{}#{step} broken invariant: {message}", at(.line))]
+    BrokenInvariant {
+        step: usize,
+        line: Option<usize>,
+        message: String,
+    },
+    /// The LRAT proof written while converting a proof does not check with its own hints, which
+    /// is only checked with
+    /// [`CheckerOptions::validate_lrat`](crate::CheckerOptions::validate_lrat). Like `Diverged`
+    /// this is a bug in the checker. Lines are counted from 1.
+    #[error("line {line} of the LRAT proof: {message}")]
+    InvalidLrat { line: usize, message: String },
+}
+
+impl Error {
+    /// Whether the proof has been checked and rejected, as opposed to the input not being
+    /// readable, the options being invalid or the propagators of --cross-check disagreeing.
+    pub fn is_rejection(&self) -> bool {
+        self.verdict().is_some()
+    }
+
+    /// Add the line in the input to the errors about a proof step, the steps are those of the
+    /// checker.
+    pub(crate) fn locate(mut self, origins: &Origins) -> Self {
+        match &mut self {
+            Error::NotRedundant { step, line, .. }
+            | Error::Diverged { step, line, .. }
+            | Error::BrokenInvariant { step, line, .. } => *line = line.or(origins.line(*step)),
+            Error::OutOfBounds { step, line } | Error::ClauseTooLong { step, line, .. } => {
+                *line = line.or(origins.input_line(*step))
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// The verdict of a proof rejected with this error.
+    pub fn verdict(&self) -> Option<Verdict> {
+        match self {
+            Error::EarlyConflict => Some(Verdict::EarlyRefutation),
+            Error::NotRedundant { step, .. } | Error::AlreadyRejected { step } => {
+                Some(Verdict::Refuted { step: *step })
+            }
+            Error::NoConflict => Some(Verdict::NoConflict),
+            Error::NotDrup { steps } => steps.first().map(|&(step, _)| Verdict::Refuted { step }),
+            _ => None,
+        }
+    }
+}
+
+// The prefix of an error about a proof step with the line in the input, if it is known.
+fn at(line: &Option<usize>) -> String {
+    line.map(|line| format!("line {}: ", line))
+        .unwrap_or_default()
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,262 @@
+//! C interface of the checker, enabled with the `ffi` feature. The header is generated into
+//! `include/ratify.h` by the build script. All functions return one of the `RATIFY_*` codes, the
+//! message of the last error on the calling thread is available through `ratify_last_error`.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr, CString},
+    ptr, slice,
+};
+
+use crate::{
+    check_clauses, check_drat, CheckReport, CheckerOptions, DeletionPolicy, Error, Mode, ProofStep,
+    Verdict,
+};
+
+/// The proof has been verified.
+pub const RATIFY_VERIFIED: c_int = 0;
+/// The proof has been checked and rejected.
+pub const RATIFY_REJECTED: c_int = 1;
+/// The input could not be read or parsed, or the options are invalid.
+pub const RATIFY_ERROR: c_int = 2;
+
+/// The propagator used for checking, like `--mode` of the binary.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub enum RatifyMode {
+    Mutating,
+    Immutable,
+    Naive,
+    HeadTail,
+    Auto,
+}
+
+/// The options of a check, start from `ratify_default_options`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RatifyOptions {
+    pub mode: RatifyMode,
+    pub rup_only: bool,
+    pub drup: bool,
+    pub check_all: bool,
+    pub implicit_empty: bool,
+    pub ignore_deletions: bool,
+}
+
+impl From<RatifyOptions> for CheckerOptions {
+    fn from(options: RatifyOptions) -> Self {
+        CheckerOptions::default()
+            .mode(match options.mode {
+                RatifyMode::Mutating => Mode::Mutating,
+                RatifyMode::Immutable => Mode::Immutable,
+                RatifyMode::Naive => Mode::Naive,
+                RatifyMode::HeadTail => Mode::HeadTail,
+                RatifyMode::Auto => Mode::Auto,
+            })
+            .rup_only(options.rup_only)
+            .drup(options.drup)
+            .check_all(options.check_all)
+            .implicit_empty(options.implicit_empty)
+            .deletions(if options.ignore_deletions {
+                DeletionPolicy::Ignore
+            } else {
+                DeletionPolicy::Apply
+            })
+    }
+}
+
+/// A formula and proof which are passed clause by clause before checking them.
+pub struct RatifyChecker {
+    options: CheckerOptions,
+    formula: Vec<Vec<i32>>,
+    proof: Vec<ProofStep>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // messages never contain interior nul bytes, drop them just in case
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn result_code(result: crate::Result<CheckReport>) -> c_int {
+    match result {
+        Ok(report) => match report.verdict {
+            Verdict::Verified | Verdict::VerifiedProbabilistic => RATIFY_VERIFIED,
+            Verdict::VerifiedPartially => {
+                set_last_error("only a range of the proof steps has been checked".to_string());
+                RATIFY_ERROR
+            }
+            Verdict::Cancelled { .. } => {
+                set_last_error("the check has been cancelled".to_string());
+                RATIFY_ERROR
+            }
+            Verdict::MemoryLimitExceeded { .. } => {
+                set_last_error("the memory limit has been exceeded".to_string());
+                RATIFY_ERROR
+            }
+            Verdict::Refuted { .. } | Verdict::NoConflict | Verdict::EarlyRefutation => {
+                set_last_error(report.reason.unwrap_or_default());
+                RATIFY_REJECTED
+            }
+        },
+        Err(e) => {
+            set_last_error(e.to_string());
+            RATIFY_ERROR
+        }
+    }
+}
+
+unsafe fn options_from(options: *const RatifyOptions) -> CheckerOptions {
+    if options.is_null() {
+        CheckerOptions::default()
+    } else {
+        (*options).into()
+    }
+}
+
+unsafe fn path_from(path: *const c_char) -> crate::Result<String> {
+    if path.is_null() {
+        return Err(Error::InvalidOptions("path is NULL".to_string()));
+    }
+    CStr::from_ptr(path)
+        .to_str()
+        .map(str::to_string)
+        .map_err(|_| Error::InvalidOptions("path is not valid UTF-8".to_string()))
+}
+
+unsafe fn clause_from(literals: *const i32, len: usize) -> Vec<i32> {
+    if len == 0 {
+        vec![]
+    } else {
+        slice::from_raw_parts(literals, len).to_vec()
+    }
+}
+
+/// The default options, which check with the mutating propagator.
+#[no_mangle]
+pub extern "C" fn ratify_default_options() -> RatifyOptions {
+    RatifyOptions {
+        mode: RatifyMode::Mutating,
+        rup_only: false,
+        drup: false,
+        check_all: false,
+        implicit_empty: false,
+        ignore_deletions: false,
+    }
+}
+
+/// Check the DRAT proof at `proof_path` for the DIMACS formula at `cnf_path`.
+///
+/// # Safety
+/// The paths have to be nul terminated strings. `options` may be NULL for the default options.
+#[no_mangle]
+pub unsafe extern "C" fn ratify_check_drat(
+    cnf_path: *const c_char,
+    proof_path: *const c_char,
+    options: *const RatifyOptions,
+) -> c_int {
+    let options = options_from(options);
+    let result = path_from(cnf_path).and_then(|cnf| {
+        let proof = path_from(proof_path)?;
+        check_drat(&cnf, &proof, options)
+    });
+    result_code(result)
+}
+
+/// The message of the last error or rejection on this thread, or NULL if there was none. The
+/// string is valid until the next check on this thread.
+#[no_mangle]
+pub extern "C" fn ratify_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Create a checker for a formula and proof which are passed with the `ratify_checker_*`
+/// functions. It has to be freed with `ratify_checker_free`.
+///
+/// # Safety
+/// `options` may be NULL for the default options.
+#[no_mangle]
+pub unsafe extern "C" fn ratify_checker_new(options: *const RatifyOptions) -> *mut RatifyChecker {
+    Box::into_raw(Box::new(RatifyChecker {
+        options: options_from(options),
+        formula: vec![],
+        proof: vec![],
+    }))
+}
+
+/// Add a clause of `len` DIMACS literals without the terminating 0 to the formula.
+///
+/// # Safety
+/// `checker` has to come from `ratify_checker_new` and `literals` has to point to `len` literals.
+#[no_mangle]
+pub unsafe extern "C" fn ratify_checker_add_clause(
+    checker: *mut RatifyChecker,
+    literals: *const i32,
+    len: usize,
+) {
+    (*checker).formula.push(clause_from(literals, len));
+}
+
+/// Append the addition of a lemma to the proof.
+///
+/// # Safety
+/// `checker` has to come from `ratify_checker_new` and `literals` has to point to `len` literals.
+#[no_mangle]
+pub unsafe extern "C" fn ratify_checker_add_lemma(
+    checker: *mut RatifyChecker,
+    literals: *const i32,
+    len: usize,
+) {
+    (*checker)
+        .proof
+        .push(ProofStep::Add(clause_from(literals, len)));
+}
+
+/// Append the deletion of a clause to the proof.
+///
+/// # Safety
+/// `checker` has to come from `ratify_checker_new` and `literals` has to point to `len` literals.
+#[no_mangle]
+pub unsafe extern "C" fn ratify_checker_delete_lemma(
+    checker: *mut RatifyChecker,
+    literals: *const i32,
+    len: usize,
+) {
+    (*checker)
+        .proof
+        .push(ProofStep::Delete(clause_from(literals, len)));
+}
+
+/// Check the proof passed so far. More clauses and lemmas may be passed afterwards to check
+/// again.
+///
+/// # Safety
+/// `checker` has to come from `ratify_checker_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ratify_checker_check(checker: *const RatifyChecker) -> c_int {
+    let checker = &*checker;
+    result_code(check_clauses(
+        checker.formula.iter().cloned(),
+        checker.proof.iter().cloned(),
+        checker.options.clone(),
+    ))
+}
+
+/// Free a checker. Passing NULL does nothing.
+///
+/// # Safety
+/// `checker` has to come from `ratify_checker_new` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ratify_checker_free(checker: *mut RatifyChecker) {
+    if !checker.is_null() {
+        drop(Box::from_raw(checker));
+    }
+}
@@ -1,176 +1,1233 @@
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
+mod gc;
 mod propagator;
+mod sampler;
 
-use crate::{Flags, Validator};
-use anyhow::{anyhow, Result};
-use indicatif::ProgressBar;
+use crate::prelude::*;
+use crate::{
+    CheckReport, CheckerOptions, Error, Observer, Phase, ProgressSink, ProofStep, Result,
+    StepOutcome, Verdict,
+};
+use itertools::Itertools;
 
 use crate::common::{
-    storage::{Clause, ClauseStorage, View},
-    Assignment, Lemma,
+    stats::vec_bytes,
+    storage::{Clause, ClauseStorage, Interner, View},
+    Assignment, Lemma, Literal, Origins, Preprocessor, RawLemma,
 };
+#[cfg(any(feature = "fs", feature = "async"))]
+use crate::parser::format::ParsedStep;
+use crate::profile::{Part, Profiler};
 
-use propagator::*;
+#[cfg(feature = "checkpoint")]
+pub use checkpoint::Checkpoint;
+use gc::GarbageCollector;
+pub use propagator::*;
+use sampler::Sampler;
 
-pub struct Checker<P> {
-    flags: Flags,
+pub struct Checker {
+    options: CheckerOptions,
     clause_db: ClauseStorage,
     db_view: View,
-    propagator: P,
+    propagator: Box<dyn Propagator>,
 }
 
-pub type NaiveChecker = Checker<NaivePropagator>;
-
-impl Validator for NaiveChecker {
-    fn init(flags: Flags, clause_db: ClauseStorage, db_view: View) -> Self {
-        let propagator = NaivePropagator::init(&clause_db, &db_view);
+impl Checker {
+    /// Set up a check of the clauses active in the view with a propagator created for them.
+    pub fn new(
+        options: CheckerOptions,
+        clause_db: ClauseStorage,
+        db_view: View,
+        propagator: &PropagatorFactory,
+    ) -> Self {
+        let propagator = propagator.init(&clause_db, &db_view);
         Checker {
-            flags,
+            options,
             clause_db,
             db_view,
             propagator,
         }
     }
 
-    fn validate(self, proof: Vec<Lemma>) -> anyhow::Result<()> {
-        validate(self, proof)
+    /// Check the proof, the origins tell errors where the steps are in the input.
+    pub fn validate(
+        self,
+        proof: Vec<Lemma>,
+        origins: Origins,
+        observer: Option<&mut dyn Observer>,
+    ) -> Result<CheckReport> {
+        validate(self, proof, origins, observer)
     }
 }
 
-pub type ConstChecker = Checker<ConstPropagator>;
+/// The outcome of applying a single proof step to the checker state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepResult {
+    /// The step was applied, continue with the next one.
+    Continue,
+    /// A conflict has been derived, the proof is verified.
+    Conflict,
+}
 
-impl Validator for ConstChecker {
-    fn init(flags: Flags, clause_db: ClauseStorage, db_view: View) -> Self {
-        let propagator = ConstPropagator::init(&clause_db, &db_view);
-        Checker {
-            flags,
+/// Which redundancy property a lemma was found to have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Redundancy {
+    Rup,
+    /// The lemma has RAT on the contained pivot literal.
+    Rat(Literal),
+    /// The lemma is neither RUP nor RAT.
+    None,
+}
+
+/// Everything a forward checker modifies while going through the proof.
+struct State {
+    clause_db: ClauseStorage,
+    db_view: View,
+    propagator: Box<dyn Propagator>,
+    assignment: Assignment,
+    profiler: Profiler,
+    // the clauses deleted so far, to tell whether a rejected lemma needs one of them
+    deleted: Vec<Clause>,
+}
+
+impl State {
+    fn new(clause_db: ClauseStorage, db_view: View, propagator: Box<dyn Propagator>) -> Self {
+        let assignment = Assignment::new(&clause_db);
+        State {
             clause_db,
             db_view,
             propagator,
+            assignment,
+            profiler: Profiler::default(),
+            deleted: Vec::new(),
+        }
+    }
+
+    /// Grow the literal arrays if a lemma has variables beyond those reserved so far. They grow by
+    /// at least half, so that a proof introducing one variable after another is not slowed down.
+    fn grow(&mut self, max_variable: u32) {
+        let reserved = self.clause_db.max_variable();
+        if max_variable <= reserved {
+            return;
         }
+        let max_variable =
+            max_variable.max(reserved.saturating_add(reserved / 2).min(i32::MAX as u32));
+        self.clause_db.grow(max_variable);
+        self.assignment.grow(max_variable);
+        self.propagator.grow(max_variable);
     }
 
-    fn validate(self, proof: Vec<Lemma>) -> anyhow::Result<()> {
-        validate(self, proof)
+    /// Assign the units of the formula and propagate them.
+    fn prepropagate(&mut self) -> Result<()> {
+        let started = self.profiler.start();
+        let units = self.propagator.propagate_true_units(
+            &self.clause_db,
+            &self.db_view,
+            &mut self.assignment,
+        );
+        self.profiler.record(started, Part::Units);
+        units.map_err(|_| Error::EarlyConflict)?;
+        self.propagate().map_err(|_| Error::EarlyConflict)
+    }
+
+    fn propagate(&mut self) -> core::result::Result<(), ()> {
+        let started = self.profiler.start();
+        let result = self
+            .propagator
+            .propagate(&mut self.clause_db, &mut self.assignment)
+            .map_err(|_| ());
+        self.profiler.record(started, Part::Propagation);
+        result
+    }
+
+    /// Does unit propagation on the currently active clauses yield a conflict? This is the RUP
+    /// check of the empty clause.
+    fn propagates_to_conflict(&mut self) -> bool {
+        self.propagate().is_err()
+    }
+
+    fn delete(&mut self, clause: Clause) {
+        // check if the clause to be deleted is unit
+        if self.clause_db.is_unit(clause, &self.assignment) {
+            tracing::warn!(
+                "ignoring deletion of unit clause {} {}",
+                clause,
+                self.clause_db.print_clause(clause)
+            );
+        } else {
+            self.propagator.delete_clause(clause, &self.clause_db);
+            self.db_view.del(clause);
+            self.deleted.push(clause);
+        }
+    }
+
+    /// Check whether a lemma is redundant with respect to the currently active clauses. RAT is
+    /// only checked if the lemma does not have RUP and `rup_only` is not set.
+    fn redundancy(&mut self, clause: Clause, rup_only: bool) -> Redundancy {
+        if has_rup(
+            &mut self.clause_db,
+            self.propagator.as_mut(),
+            &mut self.assignment,
+            &mut self.profiler,
+            clause,
+        ) {
+            Redundancy::Rup
+        } else if rup_only {
+            Redundancy::None
+        } else {
+            match has_rat(
+                &mut self.clause_db,
+                &self.db_view,
+                self.propagator.as_mut(),
+                &mut self.assignment,
+                &mut self.profiler,
+                clause,
+            ) {
+                Some(pivot) => Redundancy::Rat(pivot),
+                None => Redundancy::None,
+            }
+        }
+    }
+
+    /// Add a lemma to the clause database without checking it. Adding a clause which is already
+    /// active does not change anything.
+    fn add(&mut self, clause: Clause) -> StepResult {
+        let already_added = self.db_view.is_active(clause);
+        self.db_view.add(clause);
+        if self.clause_db.is_empty(clause) {
+            return StepResult::Conflict;
+        }
+        let started = self.profiler.start();
+        let unit = self.clause_db.extract_true_unit(clause);
+        let conflict = unit.is_some_and(|unit| self.assignment.try_assign(unit).is_err());
+        self.profiler.record(started, Part::Units);
+        if let Some(unit) = unit {
+            tracing::debug!("found unit in proof: {}", unit);
+            if conflict {
+                tracing::warn!("early conflict detected on literal {}", unit);
+                return StepResult::Conflict;
+            }
+        } else if !already_added {
+            // Every clause which becomes active is given to the propagator, even if it is
+            // satisfied, so that the propagator is only ever told to delete clauses it has been
+            // given. A clause which is added again after its deletion is set up from scratch.
+            self.propagator.add_clause(clause, &self.clause_db);
+        }
+
+        // propagate after a clause has been added
+        if self.propagate().is_err() {
+            tracing::warn!("early conflict detected");
+            return StepResult::Conflict;
+        }
+
+        tracing::trace!("OK {}", clause);
+        StepResult::Continue
+    }
+
+    /// Check the invariants of the clause database, the assignment and the propagator after a
+    /// proof step with --paranoid. The accessors of the clause database check their arguments in
+    /// debug builds as well.
+    fn check_invariants(&self, step: usize) -> Result<()> {
+        self.clause_db
+            .check_ranges()
+            .and_then(|()| self.clause_db.check_view(&self.db_view))
+            .and_then(|()| self.assignment.check_consistency())
+            .and_then(|()| {
+                self.propagator
+                    .check_invariants(&self.clause_db, &self.db_view)
+                    .map_err(|message| format!("{}: {}", self.propagator.name(), message))
+            })
+            .map_err(|message| Error::BrokenInvariant {
+                step,
+                line: None,
+                message,
+            })
+    }
+
+    /// The approximate memory used by the checker state, the sum of `memory`.
+    fn heap_size(&self) -> usize {
+        self.memory().iter().map(|(_, bytes)| bytes).sum()
+    }
+
+    /// The approximate memory used by the parts of the checker state for --stats.
+    fn memory(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("clause storage", self.clause_db.heap_size()),
+            ("clause view", self.db_view.heap_size()),
+            ("propagator", self.propagator.heap_size()),
+            ("assignment", self.assignment.heap_size()),
+            ("deleted clauses", vec_bytes(&self.deleted)),
+        ]
+    }
+
+    /// The lemma in DIMACS literals for the observer. A restored clause is added like a lemma.
+    fn proof_step(&self, lemma: Lemma) -> ProofStep {
+        match lemma {
+            Lemma::Add(clause) => ProofStep::Add(self.clause_db.external_clause(clause)),
+            Lemma::Del(clause) => ProofStep::Delete(self.clause_db.external_clause(clause)),
+            Lemma::Restore(clause) => ProofStep::Add(self.clause_db.external_clause(clause)),
+        }
+    }
+
+    // The error for a lemma which is not redundant. With --rup-only, RAT is checked as well to
+    // tell whether the option is the reason.
+    fn not_redundant_error(&mut self, step: usize, clause: Clause, rup_only: bool) -> Error {
+        let rat_pivot = if rup_only {
+            has_rat(
+                &mut self.clause_db,
+                &self.db_view,
+                self.propagator.as_mut(),
+                &mut self.assignment,
+                // explaining the rejection is not part of the profile
+                &mut Profiler::default(),
+                clause,
+            )
+            .map(|pivot| self.clause_db.external(pivot))
+        } else {
+            None
+        };
+        Error::NotRedundant {
+            step,
+            line: None,
+            clause: self.clause_db.external_clause(clause),
+            rup_only,
+            active_clauses: self.clause_db.clauses(&self.db_view).count(),
+            rat_pivot,
+            needs_deleted: self.has_rup_with_deleted(clause),
+        }
+    }
+
+    // Whether the lemma has RUP once the clauses deleted so far are active again. The active
+    // clauses are propagated by the propagator, the deleted ones are gone through until none of
+    // them is unit anymore. Clauses which are active again or whose literals have been dropped by
+    // the garbage collector are left out.
+    fn has_rup_with_deleted(&mut self, lemma: Clause) -> bool {
+        let deleted = self
+            .deleted
+            .iter()
+            .copied()
+            .filter(|&clause| !self.db_view.is_active(clause) && !self.clause_db.is_empty(clause))
+            .collect::<Vec<_>>();
+        if deleted.is_empty() {
+            return false;
+        }
+        let rollback = self.assignment.rollback_point();
+        let mut conflict = self
+            .clause_db
+            .clause(lemma)
+            .iter()
+            .any(|&lit| self.assignment.try_assign(-lit).is_err());
+        while !conflict {
+            if self.propagate().is_err() {
+                conflict = true;
+                break;
+            }
+            let mut assigned = false;
+            for &clause in &deleted {
+                let literals = self.clause_db.clause(clause);
+                if literals.iter().any(|&lit| self.assignment.is_true(lit)) {
+                    continue;
+                }
+                let mut open = literals
+                    .iter()
+                    .filter(|&&lit| !self.assignment.is_true(-lit));
+                match (open.next(), open.next()) {
+                    (None, _) => conflict = true,
+                    (Some(&unit), None) => {
+                        conflict = self.assignment.try_assign(unit).is_err();
+                        assigned = true;
+                    }
+                    _ => {}
+                }
+                if conflict {
+                    break;
+                }
+            }
+            if !assigned {
+                break;
+            }
+        }
+        self.assignment.rollback(rollback);
+        conflict
     }
 }
 
-pub type MutatingChecker = Checker<MutatingPropagator>;
+#[cfg(any(feature = "fs", feature = "async"))]
+impl Checker {
+    /// Check the proof while it is being read instead of converting it up front. Lemmas are added
+    /// to the clause storage as they come in and deleted clauses are removed from it by the
+    /// garbage collector, so apart from some bookkeeping per clause only the active clauses are
+    /// kept in memory. The clause storage has to be reserved for all clauses of the proof.
+    pub fn validate_stream(
+        self,
+        mut interner: Interner,
+        mut preprocessor: Preprocessor,
+        proof: impl Iterator<Item = Result<ParsedStep>>,
+        proof_len: usize,
+        observer: Option<&mut dyn Observer>,
+    ) -> Result<CheckReport> {
+        let (options, state) = start(self);
+        let mut state = match state {
+            Ok(state) => state,
+            Err(report) => return Ok(report),
+        };
+        let progress = options.progress.start(proof_len);
 
-impl Validator for MutatingChecker {
-    fn init(flags: Flags, clause_db: ClauseStorage, db_view: View) -> Self {
-        let propagator = MutatingPropagator::init(&clause_db, &db_view);
-        Checker {
-            flags,
-            clause_db,
-            db_view,
-            propagator,
+        // deleted clauses are forgotten by the preprocessor and never referenced again, so they
+        // can be collected right away
+        let threshold = options.gc.or(Some(STREAM_GC_THRESHOLD));
+        let gc = GarbageCollector::new(threshold, &state.clause_db, &[], 0);
+        let mut run = Run::new(options, gc, observer);
+        let result = run.check_stream(
+            &mut state,
+            &mut interner,
+            &mut preprocessor,
+            proof,
+            progress.as_ref(),
+        );
+        progress.finish();
+        let mut report = run.conclude(result, &state)?;
+        if run.options.stats {
+            let dedup_map = interner.heap_size() + preprocessor.heap_size();
+            report.memory.push(("dedup map", dedup_map));
+        }
+        Ok(report)
+    }
+}
+
+/// A check which is fed one proof step at a time, see `OnlineChecker`.
+pub struct Online {
+    // the final report instead if the formula already propagates to a conflict
+    checking: Result<Box<(Run<'static>, State)>, CheckReport>,
+    interner: Interner,
+    preprocessor: Preprocessor,
+    // the number of proof steps passed in and the number of steps which have not been skipped
+    index: usize,
+    step: usize,
+    conflict: bool,
+    // the step at which the proof has been rejected and the report of the rejection
+    rejected: Option<(usize, CheckReport)>,
+}
+
+impl Checker {
+    /// Set up a check which is fed one proof step at a time. Like when streaming, the clause
+    /// storage has to be reserved for all clauses of the proof.
+    pub fn online(self, interner: Interner, preprocessor: Preprocessor) -> Online {
+        let (options, state) = start(self);
+        let conflict = matches!(&state, Err(report) if !report.verdict.is_rejected());
+        let checking = state.map(|state| {
+            let threshold = options.gc.or(Some(STREAM_GC_THRESHOLD));
+            let gc = GarbageCollector::new(threshold, &state.clause_db, &[], 0);
+            let mut run = Run::new(options, gc, None);
+            // the steps are given in memory, their line is their position
+            run.origins = Origins::positions();
+            Box::new((run, state))
+        });
+        Online {
+            checking,
+            interner,
+            preprocessor,
+            index: 0,
+            step: 0,
+            conflict,
+            rejected: None,
+        }
+    }
+}
+
+impl Online {
+    /// Check and apply the next proof step.
+    pub fn next(&mut self, lemma: RawLemma) -> Result<StepOutcome> {
+        if let Some((step, _)) = self.rejected {
+            return Err(Error::AlreadyRejected { step });
+        }
+        let index = self.index;
+        self.index += 1;
+        let Ok(checking) = &mut self.checking else {
+            return Ok(StepOutcome::Ignored);
+        };
+        let (run, state) = checking.as_mut();
+        if self.conflict && !run.options.check_all {
+            return Ok(StepOutcome::Ignored);
+        }
+        let result = run.raw_step(
+            state,
+            &mut self.interner,
+            &mut self.preprocessor,
+            index,
+            self.step,
+            lemma,
+        );
+        match result {
+            Ok(Some((outcome, result))) => {
+                self.step += 1;
+                self.conflict |= result == StepResult::Conflict;
+                Ok(outcome)
+            }
+            Ok(None) => Ok(StepOutcome::Ignored),
+            Err(e) => {
+                let e = e.locate(&run.origins);
+                // lemmas out of bounds are rejected before changing anything
+                if let Some(report) = CheckReport::rejection(&e) {
+                    self.rejected = Some((index, report));
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Whether a conflict has been derived.
+    pub fn has_conflict(&self) -> bool {
+        self.conflict
+    }
+
+    /// End the proof.
+    pub fn finish(&mut self) -> Result<CheckReport> {
+        let (run, state) = match &mut self.checking {
+            Ok(checking) => checking.as_mut(),
+            Err(report) => return Ok(report.clone()),
+        };
+        if let Some((_, report)) = &self.rejected {
+            return Ok(run.counted(report.clone()));
         }
+        let result = if self.conflict && run.conflict_step.is_none() {
+            run.finish()
+        } else {
+            run.end(state)
+        };
+        run.conclude(result, state)
     }
+}
+
+// The fraction of dead literals at which the clause storage is compacted when streaming the proof
+// and --gc is not given.
+const STREAM_GC_THRESHOLD: f64 = 0.5;
 
-    fn validate(self, proof: Vec<Lemma>) -> anyhow::Result<()> {
-        validate(self, proof)
+// How many proof steps to check between estimating the memory used for --max-memory.
+const MEMORY_CHECK_INTERVAL: usize = 4096;
+
+// Set up the checker state and propagate the units of the formula. If the formula contains the
+// empty clause or already propagates to a conflict the proof is not needed, and the final report
+// is returned instead of the state. The empty clause verifies the proof, a conflict verifies it
+// with --implicit-empty and rejects it otherwise.
+fn start(checker: Checker) -> (CheckerOptions, Result<State, CheckReport>) {
+    let options = checker.options;
+    let mut state = State::new(checker.clause_db, checker.db_view, checker.propagator);
+    state.profiler = Profiler::new(options.profile);
+    if has_empty_clause(&state.clause_db, &state.db_view) {
+        let report = trivially_verified(&options);
+        return (options, Err(report));
+    }
+    if let Err(e) = state.prepropagate() {
+        let report = if options.implicit_empty {
+            comment!(
+                options,
+                "formula propagates to conflict, the proof is not needed"
+            );
+            CheckReport::new(options.verdict())
+        } else {
+            CheckReport {
+                reason: Some(e.to_string()),
+                ..CheckReport::new(Verdict::EarlyRefutation)
+            }
+        };
+        return (options, Err(report));
     }
+    (options, Ok(state))
 }
 
-fn validate<P: Propagator>(checker: Checker<P>, proof: Vec<Lemma>) -> Result<()> {
-    let mut clause_db = checker.clause_db;
-    let mut propagator = checker.propagator;
-    let mut db_view = checker.db_view;
-    let mut assignment = Assignment::new(&clause_db);
-    propagator
-        .propagate_true_units(&clause_db, &db_view, &mut assignment)
-        .map_err(|_| anyhow!("assignment of true units yielded conflict"))?;
-    propagator
-        .propagate(&mut clause_db, &mut assignment)
-        .map_err(|_| anyhow!("prepropagation yielded conflict"))?;
+// Whether the active clauses contain the empty clause. Before the first proof step these are the
+// clauses of the formula.
+fn has_empty_clause(clause_db: &ClauseStorage, db_view: &View) -> bool {
+    clause_db
+        .clauses(db_view)
+        .any(|clause| clause_db.is_empty(clause))
+}
+
+// The report for a formula which contains the empty clause.
+fn trivially_verified(options: &CheckerOptions) -> CheckReport {
+    comment!(
+        options,
+        "the formula contains the empty clause, the proof is not needed"
+    );
+    CheckReport::new(Verdict::Verified)
+}
 
-    let progress = if checker.flags.progress {
-        ProgressBar::new(proof.len() as u64)
-    } else {
-        ProgressBar::hidden()
+fn validate(
+    checker: Checker,
+    proof: Vec<Lemma>,
+    origins: Origins,
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    let (options, state) = start(checker);
+    let mut state = match state {
+        Ok(state) => state,
+        Err(report) => return Ok(report),
     };
+    let progress = options.progress.start(proof.len());
 
-    let mut step = 0;
+    let gc = GarbageCollector::new(options.gc, &state.clause_db, &proof, 0);
+    let mut run = Run::new(options, gc, observer);
+    run.origins = origins;
+    let result = run.check(&mut state, &proof, 0, progress.as_ref());
+    progress.finish();
+    run.conclude(result, &state)
+}
 
-    for lemma in proof {
-        match lemma {
-            Lemma::Del(clause) => {
-                // check if the clause to be deleted is unit
-                if clause_db.is_unit(clause, &assignment) {
-                    tracing::warn!(
-                        "ignoring deletion of unit clause {} {}",
-                        clause,
-                        clause_db.print_clause(clause)
-                    );
-                } else {
-                    propagator.delete_clause(clause, &clause_db);
-                    db_view.del(clause);
+/// Continue a check from a checkpoint, the propagator and assignment are rebuilt from the active
+/// clauses of the checkpoint.
+#[cfg(feature = "checkpoint")]
+pub fn resume(
+    options: CheckerOptions,
+    checkpoint: Checkpoint,
+    propagator: &PropagatorFactory,
+) -> Result<CheckReport> {
+    let Checkpoint {
+        step: first_step,
+        clause_db,
+        db_view,
+        proof,
+        tally,
+        ..
+    } = checkpoint;
+    comment!(options, "resuming the check at step {}", first_step);
+    let checker = Checker::new(options, clause_db, db_view, propagator);
+    let (options, state) = start(checker);
+    let mut state = match state {
+        Ok(state) => state,
+        Err(report) => return Ok(report),
+    };
+    let progress = options.progress.start(first_step + proof.len());
+    progress.tick(first_step as u64);
+
+    let gc = GarbageCollector::new(options.gc, &state.clause_db, &proof, first_step);
+    let mut run = Run::new(options, gc, None);
+    run.restore(tally);
+    let result = run.check(&mut state, &proof, first_step, progress.as_ref());
+    progress.finish();
+    run.conclude(result, &state)
+}
+
+/// Everything besides the checker state that is tracked while going through the proof.
+struct Run<'a> {
+    options: CheckerOptions,
+    observer: Option<&'a mut dyn Observer>,
+    sampler: Sampler,
+    gc: GarbageCollector,
+    // the steps which required RAT together with their pivot, only tracked with --drup
+    rat_steps: Vec<(usize, i32)>,
+    // the first step at which a conflict was derived, only tracked with --check-all
+    conflict_step: Option<usize>,
+    // the verdict if the run has been cancelled or ran out of memory
+    stopped: Option<Verdict>,
+    // where the proof steps are in the input, for the errors
+    origins: Origins,
+    // whether every lemma so far has been checked and has RUP, so that every clause which has
+    // ever been active is implied by the formula and can be restored without a check
+    implied: bool,
+    #[cfg(feature = "checkpoint")]
+    checkpointer: Option<checkpoint::Checkpointer>,
+    steps: usize,
+    rup_checks: usize,
+    rat_checks: usize,
+}
+
+impl<'a> Run<'a> {
+    fn new(
+        options: CheckerOptions,
+        gc: GarbageCollector,
+        observer: Option<&'a mut dyn Observer>,
+    ) -> Self {
+        #[cfg(feature = "checkpoint")]
+        let checkpointer = options
+            .checkpoint
+            .clone()
+            .map(|(path, interval)| checkpoint::Checkpointer::new(path, interval));
+        Run {
+            observer,
+            sampler: Sampler::new(options.sample, options.seed),
+            options,
+            gc,
+            rat_steps: vec![],
+            conflict_step: None,
+            stopped: None,
+            origins: Origins::default(),
+            implied: true,
+            #[cfg(feature = "checkpoint")]
+            checkpointer,
+            steps: 0,
+            rup_checks: 0,
+            rat_checks: 0,
+        }
+    }
+
+    fn report(&self) -> CheckReport {
+        let verdict = self.stopped.unwrap_or_else(|| self.options.verdict());
+        self.counted(CheckReport::new(verdict))
+    }
+
+    // The report with the statistics of the run.
+    fn counted(&self, report: CheckReport) -> CheckReport {
+        CheckReport {
+            steps: self.steps,
+            rup_checks: self.rup_checks,
+            rat_checks: self.rat_checks,
+            ..report
+        }
+    }
+
+    // The report of the run with the verdict of the result and the profile and memory of the
+    // state. A rejection of the proof is not an error.
+    fn conclude(&self, result: Result<()>, state: &State) -> Result<CheckReport> {
+        let report = match result {
+            Ok(()) => self.report(),
+            Err(e) => self.counted(CheckReport::rejected(e.locate(&self.origins))?),
+        };
+        let mut memory = vec![];
+        if self.options.stats {
+            memory = state.memory();
+            memory.push(("garbage collector", self.gc.heap_size()));
+        }
+        Ok(CheckReport {
+            profile: state.profiler.profile(),
+            memory,
+            ..report
+        })
+    }
+
+    // Look at the cancellation token before a proof step, and every few thousand steps at the
+    // memory used, which takes a while to estimate.
+    fn stopped(&mut self, step: usize, memory: impl FnOnce() -> usize) -> bool {
+        let phase = Phase::Checking;
+        if self.options.is_cancelled() {
+            self.stopped = Some(Verdict::Cancelled { phase });
+        } else if self.options.max_memory.is_some()
+            && step.is_multiple_of(MEMORY_CHECK_INTERVAL)
+            && self.options.exceeds_memory(memory() + self.gc.heap_size())
+        {
+            self.stopped = Some(Verdict::MemoryLimitExceeded { phase });
+        }
+        self.stopped.is_some()
+    }
+
+    /// Check all steps of the proof, which starts at `first_step` if the check has been resumed.
+    fn check(
+        &mut self,
+        state: &mut State,
+        proof: &[Lemma],
+        first_step: usize,
+        progress: &dyn ProgressSink,
+    ) -> Result<()> {
+        for (i, &lemma) in proof.iter().enumerate() {
+            let step = first_step + i;
+            if self.stopped(step, || state.heap_size()) {
+                return Ok(());
+            }
+            #[cfg(feature = "checkpoint")]
+            self.checkpoint(state, step, &proof[i..]);
+            if self.step(state, step, lemma)?.1 == StepResult::Conflict {
+                return self.finish();
+            }
+            progress.tick(1);
+        }
+        self.end(state)
+    }
+
+    /// Check the steps of the proof while converting them to lemmas. Skipped lemmas do not count
+    /// as proof steps, so the steps are numbered the same way as without streaming.
+    #[cfg(any(feature = "fs", feature = "async"))]
+    fn check_stream(
+        &mut self,
+        state: &mut State,
+        interner: &mut Interner,
+        preprocessor: &mut Preprocessor,
+        proof: impl Iterator<Item = Result<ParsedStep>>,
+        progress: &dyn ProgressSink,
+    ) -> Result<()> {
+        let mut step = 0;
+        for (i, parsed) in proof.enumerate() {
+            let memory = || state.heap_size() + interner.heap_size() + preprocessor.heap_size();
+            if self.stopped(i, memory) {
+                return Ok(());
+            }
+            let parsed = parsed?;
+            self.origins.record(parsed.line);
+            if let Some((_, result)) =
+                self.raw_step(state, interner, preprocessor, i, step, parsed.lemma)?
+            {
+                if result == StepResult::Conflict {
+                    return self.finish();
                 }
+                step += 1;
             }
-            Lemma::Add(clause) => {
-                if has_rup(&mut clause_db, &mut propagator, &mut assignment, clause) {
-                    let already_added = db_view.is_active(clause);
-                    db_view.add(clause);
-                    if clause_db.is_empty(clause) {
-                        return Ok(());
-                    }
-                    if let Some(unit) = clause_db.extract_true_unit(clause) {
-                        tracing::debug!("found unit in proof: {}", unit);
-                        assignment
-                            .try_assign(unit)
-                            .map_err(|_| anyhow!("early conflict detected on literal {}", unit))?;
-                    } else {
-                        // if we found a non unit clause (more than two literals) add it to the
-                        // propagator. do not add it again if it was already present before,
-                        // this would corrupt the watchlists potentially
-                        if already_added {
-                        } else if assignment.is_satisfied(clause, &clause_db) {
-                            tracing::warn!("clause is already satisfied, not adding to propagator");
-                        } else {
-                            propagator.add_clause(clause, &clause_db);
-                        }
-                    }
+            progress.tick(1);
+        }
+        self.end(state)
+    }
 
-                    // propagate after a clause has been added
-                    if let Err(_) = propagator.propagate(&mut clause_db, &mut assignment) {
-                        tracing::warn!("early conflict detected");
-                        return Ok(());
-                    }
+    /// Convert a lemma of a proof which is read step by step and check it. The index counts all
+    /// lemmas read so far, the step only those which have not been skipped. Returns `None` if
+    /// the preprocessor skips the lemma.
+    fn raw_step(
+        &mut self,
+        state: &mut State,
+        interner: &mut Interner,
+        preprocessor: &mut Preprocessor,
+        index: usize,
+        step: usize,
+        raw_lemma: RawLemma,
+    ) -> Result<Option<(StepOutcome, StepResult)>> {
+        self.options
+            .check_clause_length(index, None, raw_lemma.clause().len())?;
+        if let RawLemma::Add(c) | RawLemma::Restore(c) = &raw_lemma {
+            // new clauses have to fit into the reserved storage, duplicates reuse theirs unless
+            // they are kept
+            if !state.clause_db.fits()
+                && (self.options.keep_duplicates || interner.find(&state.clause_db, c).is_none())
+            {
+                return Err(Error::OutOfBounds {
+                    step: index,
+                    line: None,
+                });
+            }
+            if let Some(max_variable) = c.iter().map(|lit| lit.var()).max() {
+                state.grow(max_variable);
+            }
+        }
+        let line = self.origins.input_line(index);
+        match preprocessor.lemma(index, line, raw_lemma, interner, &mut state.clause_db) {
+            Some(lemma) => self.step(state, step, lemma).map(Some),
+            None => {
+                self.origins.skip(index);
+                self.options.check_ignored(preprocessor.stats().ignored())?;
+                let missing = preprocessor.missing_deletion().map(|(step, clause)| {
+                    let clause = clause.iter().map(|&lit| state.clause_db.external(lit));
+                    (step, clause.collect())
+                });
+                self.options.check_deletions(missing, &self.origins)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Check and apply a single proof step and notify the observer. Returns `Conflict` once the
+    /// proof is verified.
+    fn step(
+        &mut self,
+        state: &mut State,
+        step: usize,
+        lemma: Lemma,
+    ) -> Result<(StepOutcome, StepResult)> {
+        self.steps += 1;
+        let Some(observer) = self.observer.as_deref_mut() else {
+            return self.apply(state, step, lemma);
+        };
+        let observed = state.proof_step(lemma);
+        observer.before_step(step, &observed);
+        let result = self.apply(state, step, lemma);
+        let outcome = match &result {
+            Ok((outcome, _)) => *outcome,
+            Err(_) => StepOutcome::NotRedundant,
+        };
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.after_step(step, &observed, outcome);
+        }
+        result
+    }
 
-                    tracing::trace!("OK {}", clause);
+    // Check and apply a single proof step.
+    fn apply(
+        &mut self,
+        state: &mut State,
+        step: usize,
+        lemma: Lemma,
+    ) -> Result<(StepOutcome, StepResult)> {
+        let options = &self.options;
+        let (outcome, result) = match lemma {
+            Lemma::Del(clause) => {
+                state.delete(clause);
+                self.gc
+                    .deleted(clause, step, &mut state.clause_db, &state.db_view);
+                (StepOutcome::Deleted, StepResult::Continue)
+            }
+            Lemma::Restore(clause) if self.implied => {
+                // every clause which has ever been active is implied by the formula
+                (StepOutcome::Restored, self.add(state, step, clause))
+            }
+            Lemma::Add(clause) | Lemma::Restore(clause) => {
+                let in_range = options.from.is_none_or(|from| step >= from)
+                    && options.to.is_none_or(|to| step <= to);
+                // the empty clause is always checked, even when sampling or restricting the range
+                let check =
+                    state.clause_db.is_empty(clause) || (in_range && self.sampler.check_next());
+                let outcome = if check {
+                    match state.redundancy(clause, options.rup_only && !options.drup) {
+                        Redundancy::Rup => {
+                            self.rup_checks += 1;
+                            StepOutcome::Rup
+                        }
+                        Redundancy::Rat(pivot) => {
+                            self.rat_checks += 1;
+                            self.implied = false;
+                            let pivot = state.clause_db.external(pivot);
+                            if options.drup {
+                                tracing::warn!(
+                                    "#{} lemma {} requires RAT on {}",
+                                    step,
+                                    clause,
+                                    pivot
+                                );
+                                self.rat_steps.push((step, pivot));
+                            }
+                            StepOutcome::Rat { pivot }
+                        }
+                        Redundancy::None => {
+                            return Err(state.not_redundant_error(step, clause, options.rup_only))
+                        }
+                    }
                 } else {
-                    return Err(anyhow!(
-                        "#{} lemma ({}) does not have RUP {}",
-                        step,
-                        clause_db.print_clause(clause),
-                        clause,
-                    ));
+                    // the lemma may need RAT
+                    self.implied = false;
+                    StepOutcome::Unchecked
+                };
+                (outcome, self.add(state, step, clause))
+            }
+        };
+        if self.options.paranoid {
+            state.check_invariants(step)?;
+        }
+        Ok((outcome, result))
+    }
+
+    // Add a lemma to the state. With --check-all a conflict is only noted and the remaining steps
+    // are checked as well.
+    fn add(&mut self, state: &mut State, step: usize, clause: Clause) -> StepResult {
+        match state.add(clause) {
+            StepResult::Conflict if self.options.check_all => {
+                if self.conflict_step.is_none() {
+                    tracing::info!("#{} conflict derived, checking remaining steps", step);
+                    self.conflict_step = Some(step);
                 }
+                StepResult::Continue
+            }
+            result => result,
+        }
+    }
+
+    /// Called after the last proof step, fails if no conflict has been derived.
+    fn end(&self, state: &mut State) -> Result<()> {
+        match self.conflict_step {
+            Some(step) => {
+                comment!(
+                    self.options,
+                    "conflict derived at step {}, all steps checked",
+                    step
+                );
+                self.finish()
+            }
+            None if self.options.implicit_empty && state.propagates_to_conflict() => {
+                comment!(
+                    self.options,
+                    "proof does not contain the empty clause, but it has RUP"
+                );
+                self.finish()
+            }
+            None => Err(Error::NoConflict),
+        }
+    }
+
+    // Report the final result of a successful verification, which fails if lemmas requiring RAT
+    // were found in --drup mode.
+    fn finish(&self) -> Result<()> {
+        self.sampler.report(&self.options);
+        if !self.rat_steps.is_empty() {
+            return Err(Error::NotDrup {
+                steps: self.rat_steps.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Run two propagators in lock step over the same proof and report the first step at which their
+/// verdicts or assignments diverge. Both are checked like a single propagator with all options.
+pub fn cross_check(
+    options: CheckerOptions,
+    clause_db: ClauseStorage,
+    db_view: View,
+    proof: Vec<Lemma>,
+    origins: &Origins,
+    (first_propagator, second_propagator): (&PropagatorFactory, &PropagatorFactory),
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    let first = Checker::new(
+        options.clone(),
+        clause_db.clone(),
+        db_view.clone(),
+        first_propagator,
+    );
+    let second = Checker::new(options, clause_db, db_view, second_propagator);
+    let names = (
+        first.propagator.name().to_string(),
+        second.propagator.name().to_string(),
+    );
+    let ((options, first), (second_options, second)) = (start(first), start(second));
+    let (mut first, mut second) = match (first, second) {
+        (Ok(first), Ok(second)) => (first, second),
+        (Err(report), Err(_)) => return Ok(report),
+        (first, _) => {
+            return Err(Error::Diverged {
+                step: 0,
+                line: None,
+                message: format!(
+                    "the formula propagates to a conflict only with {}",
+                    if first.is_err() { &names.0 } else { &names.1 }
+                ),
+            })
+        }
+    };
+    let progress = options.progress.start(proof.len());
+    let gc = GarbageCollector::new(options.gc, &first.clause_db, &proof, 0);
+    let mut first_run = Run::new(options, gc, observer);
+    first_run.origins = origins.clone();
+    let gc = GarbageCollector::new(second_options.gc, &second.clause_db, &proof, 0);
+    let mut second_run = Run::new(second_options, gc, None);
+    let result = lock_step(
+        (&mut first_run, &mut first),
+        (&mut second_run, &mut second),
+        &proof,
+        &names,
+        progress.as_ref(),
+    );
+    progress.finish();
+    first_run.conclude(result, &first)
+}
+
+// Check every proof step with both runs and fail at the first one at which their outcomes or
+// assignments differ. The first run is the one which is reported.
+fn lock_step(
+    (first_run, first): (&mut Run, &mut State),
+    (second_run, second): (&mut Run, &mut State),
+    proof: &[Lemma],
+    (first_name, second_name): &(String, String),
+    progress: &dyn ProgressSink,
+) -> Result<()> {
+    let describe = |result: &Result<(StepOutcome, StepResult)>| match result {
+        Ok((outcome, result)) => format!("{:?} and returned {:?}", outcome, result),
+        Err(e) => format!("an error: {}", e),
+    };
+    for (step, &lemma) in proof.iter().enumerate() {
+        if first_run.stopped(step, || first.heap_size() + second.heap_size()) {
+            return Ok(());
+        }
+        let first_result = first_run.step(first, step, lemma);
+        let second_result = second_run.step(second, step, lemma);
+        let conflict = match (first_result, second_result) {
+            (Ok(first_result), Ok(second_result)) if first_result == second_result => {
+                first_result.1 == StepResult::Conflict
+            }
+            // both reject the lemma
+            (Err(e), Err(_)) => return Err(e),
+            (first_result, second_result) => {
+                return Err(Error::Diverged {
+                    step,
+                    line: None,
+                    message: format!(
+                        "checks diverge on {:?}: {} found {}, {} found {}",
+                        first.proof_step(lemma),
+                        first_name,
+                        describe(&first_result),
+                        second_name,
+                        describe(&second_result)
+                    ),
+                });
             }
+        };
+
+        if conflict {
+            comment!(
+                first_run.options,
+                "propagators {} and {} agree",
+                first_name,
+                second_name
+            );
+            return first_run.finish();
         }
 
-        step += 1;
-        progress.inc(1);
+        // without a conflict propagation gives the same assignment, but which literals are
+        // assigned at a conflict depends on the order of propagation
+        let only_first = first.assignment.difference(&second.assignment);
+        let only_second = second.assignment.difference(&first.assignment);
+        if !only_first.is_empty() || !only_second.is_empty() {
+            return Err(Error::Diverged {
+                step,
+                line: None,
+                message: format!(
+                    "assignments diverge after {:?}: only {} assigned [{}], only {} assigned [{}]",
+                    first.proof_step(lemma),
+                    first_name,
+                    only_first
+                        .iter()
+                        .map(|&lit| first.clause_db.external(lit))
+                        .join(","),
+                    second_name,
+                    only_second
+                        .iter()
+                        .map(|&lit| first.clause_db.external(lit))
+                        .join(",")
+                ),
+            });
+        }
+        progress.tick(1);
     }
 
-    Err(anyhow!("no conflict detected"))
+    // without a conflict, the proof may still be accepted with --check-all or --implicit-empty
+    let (first_end, second_end) = (first_run.end(first), second_run.end(second));
+    if first_end.is_ok() != second_end.is_ok() {
+        let verdict = |end: &Result<()>| if end.is_ok() { "accepts" } else { "rejects" };
+        return Err(Error::Diverged {
+            step: proof.len().saturating_sub(1),
+            line: None,
+            message: format!(
+                "checks diverge after the last proof step: {} {} the proof, {} {} it",
+                first_name,
+                verdict(&first_end),
+                second_name,
+                verdict(&second_end)
+            ),
+        });
+    }
+    first_end
 }
 
 fn has_rup(
     clause_db: &mut ClauseStorage,
-    propagator: &mut impl Propagator,
+    propagator: &mut dyn Propagator,
     assignment: &mut Assignment,
+    profiler: &mut Profiler,
     lemma: Clause,
 ) -> bool {
+    let started = profiler.start();
     let rollback = assignment.rollback_point();
-    for &lit in clause_db.clause(lemma) {
-        if let Err(_) = assignment.try_assign(-lit) {
-            assignment.rollback(rollback);
-            return true;
-        }
+    let mut conflict = clause_db
+        .clause(lemma)
+        .iter()
+        .any(|&lit| assignment.try_assign(-lit).is_err());
+    if !conflict {
+        let propagation = profiler.start();
+        conflict = propagator.propagate(clause_db, assignment).is_err();
+        profiler.record(propagation, Part::RupPropagation);
+    }
+    assignment.rollback(rollback);
+    profiler.record(started, Part::Rup);
+    conflict
+}
+
+// Check whether the lemma has RAT on any of its literals. For every active clause containing the
+// negated pivot, the resolvent with the lemma has to have RUP. Since the clause database does not
+// preserve the order of literals in the proof, every literal of the lemma is tried as the pivot.
+// Returns the first pivot on which the lemma has RAT.
+//
+// All resolvents share the negated lemma as a prefix of their assignment. It is assigned and
+// propagated once, each resolvent check then only rolls back its own suffix.
+fn has_rat(
+    clause_db: &mut ClauseStorage,
+    db_view: &View,
+    propagator: &mut dyn Propagator,
+    assignment: &mut Assignment,
+    profiler: &mut Profiler,
+    lemma: Clause,
+) -> Option<Literal> {
+    let started = profiler.start();
+    let literals = clause_db.clause(lemma).to_vec();
+    let rollback = assignment.rollback_point();
+    let conflict = literals
+        .iter()
+        .any(|&lit| assignment.try_assign(-lit).is_err())
+        || rat_propagate(clause_db, propagator, assignment, profiler);
+    if conflict {
+        // every resolvent contains the lemma and thus has RUP
+        assignment.rollback(rollback);
+        profiler.record(started, Part::Rat);
+        return literals.first().copied();
     }
 
-    let res = propagator.propagate(clause_db, assignment);
+    let prefix = assignment.rollback_point();
+    let pivot = literals.iter().copied().find(|&pivot| {
+        let candidates = clause_db
+            .clauses(db_view)
+            .filter(|&c| clause_db.clause(c).contains(&-pivot))
+            .collect_vec();
+        candidates.into_iter().all(|candidate| {
+            let rest = clause_db
+                .clause(candidate)
+                .iter()
+                .copied()
+                .filter(|&lit| lit != -pivot)
+                .collect_vec();
+            // the resolvent is either a tautology or falsified by the assignment if assigning
+            // one of its negated literals fails
+            let res = rest
+                .into_iter()
+                .any(|lit| assignment.try_assign(-lit).is_err())
+                || rat_propagate(clause_db, propagator, assignment, profiler);
+            assignment.rollback(prefix);
+            res
+        })
+    });
     assignment.rollback(rollback);
-    res.is_err()
+    profiler.record(started, Part::Rat);
+    pivot
+}
+
+// Propagate during a RAT check, returns whether there is a conflict.
+fn rat_propagate(
+    clause_db: &mut ClauseStorage,
+    propagator: &mut dyn Propagator,
+    assignment: &mut Assignment,
+    profiler: &mut Profiler,
+) -> bool {
+    let started = profiler.start();
+    let conflict = propagator.propagate(clause_db, assignment).is_err();
+    profiler.record(started, Part::RatPropagation);
+    conflict
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{check_str, trim::trim_str, CheckReport, CheckerOptions, TrimStrategy};
+
+    // All clauses over two variables, 1 only has RUP as long as 1 2 is active.
+    const UNSATISFIABLE: &str = "p cnf 2 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n";
+
+    // Satisfied by 1 and 2, so -1 never has RUP.
+    const SATISFIABLE: &str = "p cnf 2 3\n1 2 0\n-1 2 0\n1 -2 0\n";
+
+    // Whether the rejected lemma needs a deleted clause, checked forward and backward.
+    fn needs_deleted(cnf: &str, proof: &str) -> [bool; 2] {
+        let options = CheckerOptions::default();
+        let forward = check_str(cnf, proof, options.clone()).unwrap();
+        let backward = trim_str(cnf, proof, options, TrimStrategy::default())
+            .unwrap()
+            .report;
+        [forward, backward].map(|report: CheckReport| report.rejected_lemma.unwrap().needs_deleted)
+    }
+
+    #[test]
+    fn rejected_lemma_needs_deleted_clause() {
+        assert_eq!(
+            needs_deleted(UNSATISFIABLE, "d 1 2 0\n1 0\n0\n"),
+            [true, true]
+        );
+    }
+
+    #[test]
+    fn rejected_lemma_without_needed_deletion() {
+        assert_eq!(
+            needs_deleted(SATISFIABLE, "d -1 2 0\n-1 0\n0\n"),
+            [false, false]
+        );
+        assert_eq!(needs_deleted(SATISFIABLE, "-1 0\n0\n"), [false, false]);
+    }
 }
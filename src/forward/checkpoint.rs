@@ -0,0 +1,149 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{sampler::Sampler, Run, State};
+use crate::{
+    common::{
+        storage::{ClauseStorage, View},
+        Lemma,
+    },
+    Error, Instant, Result,
+};
+
+// Increased whenever the layout of a checkpoint changes.
+const VERSION: u32 = 2;
+
+/// A snapshot of a check from which it can be resumed. Only the active clauses are stored, the
+/// propagator and assignment are rebuilt from them by propagating the units. The proof steps
+/// which have already been checked are dropped.
+#[derive(Deserialize)]
+pub struct Checkpoint {
+    version: u32,
+    // the first proof step which has not been checked yet
+    pub step: usize,
+    pub clause_db: ClauseStorage,
+    pub db_view: View,
+    pub proof: Vec<Lemma>,
+    pub tally: Tally,
+}
+
+// The same layout as `Checkpoint`, but borrowing the state of a running check.
+#[derive(Serialize)]
+struct CheckpointRef<'a> {
+    version: u32,
+    step: usize,
+    clause_db: &'a ClauseStorage,
+    db_view: &'a View,
+    proof: &'a [Lemma],
+    tally: Tally,
+}
+
+/// What the run has found so far.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tally {
+    sampler: Sampler,
+    rat_steps: Vec<(usize, i32)>,
+    steps: usize,
+    rup_checks: usize,
+    rat_checks: usize,
+    implied: bool,
+}
+
+impl Checkpoint {
+    pub fn read(path: &str) -> Result<Self> {
+        let checkpoint: Checkpoint = bincode::deserialize_from(BufReader::new(File::open(path)?))
+            .map_err(|e| Error::Checkpoint(e.to_string()))?;
+        if checkpoint.version != VERSION {
+            return Err(Error::Checkpoint(format!(
+                "written by an incompatible version, expected version {} but got {}",
+                VERSION, checkpoint.version
+            )));
+        }
+        Ok(checkpoint)
+    }
+}
+
+/// Writes a checkpoint whenever the interval has passed.
+pub struct Checkpointer {
+    path: String,
+    interval: Duration,
+    last: Instant,
+}
+
+impl Checkpointer {
+    pub fn new(path: String, interval: Duration) -> Self {
+        Checkpointer {
+            path,
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    fn is_due(&self) -> bool {
+        self.last.elapsed() >= self.interval
+    }
+
+    // Write to a temporary file first, so that an interrupted write keeps the last checkpoint.
+    fn write(&mut self, checkpoint: &CheckpointRef) -> Result<()> {
+        let partial = format!("{}.partial", self.path);
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        bincode::serialize_into(&mut writer, checkpoint)
+            .map_err(|e| Error::Checkpoint(e.to_string()))?;
+        writer.flush()?;
+        std::fs::rename(&partial, &self.path)?;
+        self.last = Instant::now();
+        Ok(())
+    }
+}
+
+impl Run<'_> {
+    /// Write a checkpoint before the given step if it is due. The remaining proof starts at this
+    /// step.
+    pub(super) fn checkpoint(&mut self, state: &State, step: usize, proof: &[Lemma]) {
+        // the assignment cannot be rebuilt once it is conflicting
+        if self.conflict_step.is_some() {
+            return;
+        }
+        let Some(checkpointer) = self.checkpointer.as_mut() else {
+            return;
+        };
+        if !checkpointer.is_due() {
+            return;
+        }
+        let checkpoint = CheckpointRef {
+            version: VERSION,
+            step,
+            clause_db: &state.clause_db,
+            db_view: &state.db_view,
+            proof,
+            tally: Tally {
+                sampler: self.sampler.clone(),
+                rat_steps: self.rat_steps.clone(),
+                steps: self.steps,
+                rup_checks: self.rup_checks,
+                rat_checks: self.rat_checks,
+                implied: self.implied,
+            },
+        };
+        // a failed checkpoint should not end a long check
+        match checkpointer.write(&checkpoint) {
+            Ok(()) => tracing::info!("wrote checkpoint before step {}", step),
+            Err(e) => tracing::warn!("failed to write checkpoint: {}", e),
+        }
+    }
+
+    /// Continue with the progress of a resumed check.
+    pub(super) fn restore(&mut self, tally: Tally) {
+        self.sampler = tally.sampler;
+        self.rat_steps = tally.rat_steps;
+        self.steps = tally.steps;
+        self.rup_checks = tally.rup_checks;
+        self.rat_checks = tally.rat_checks;
+        self.implied = tally.implied;
+    }
+}
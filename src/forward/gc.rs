@@ -0,0 +1,72 @@
+use crate::common::{
+    storage::{Clause, ClauseArray, ClauseStorage, View},
+    Lemma,
+};
+
+/// Keeps track of how many literals in the clause storage belong to clauses which have been
+/// deleted and are never referenced again by the proof. Once their fraction exceeds the
+/// threshold given with `--gc` the clause storage is compacted.
+pub struct GarbageCollector {
+    threshold: Option<f64>,
+    // the last proof step referencing each clause
+    last_use: ClauseArray<usize>,
+    dead_literals: usize,
+}
+
+impl GarbageCollector {
+    /// The proof starts at `first_step` if the check has been resumed from a checkpoint.
+    pub fn new(
+        threshold: Option<f64>,
+        clause_db: &ClauseStorage,
+        proof: &[Lemma],
+        first_step: usize,
+    ) -> Self {
+        let mut last_use = clause_db.clause_array();
+        if threshold.is_some() {
+            for (step, lemma) in (first_step..).zip(proof) {
+                match lemma {
+                    Lemma::Add(clause) | Lemma::Del(clause) | Lemma::Restore(clause) => {
+                        last_use[*clause] = step
+                    }
+                }
+            }
+        }
+        GarbageCollector {
+            threshold,
+            last_use,
+            dead_literals: 0,
+        }
+    }
+
+    pub fn heap_size(&self) -> usize {
+        self.last_use.heap_size()
+    }
+
+    /// Called after the deletion of a clause at the given proof step. Compacts the clause storage
+    /// if enough literals are dead.
+    pub fn deleted(
+        &mut self,
+        clause: Clause,
+        step: usize,
+        clause_db: &mut ClauseStorage,
+        db_view: &View,
+    ) {
+        let Some(threshold) = self.threshold else {
+            return;
+        };
+        if db_view.is_active(clause) || self.last_use[clause] > step {
+            return;
+        }
+        self.dead_literals += clause_db.clause(clause).len();
+        if self.dead_literals as f64 > threshold * clause_db.number_of_literals() as f64 {
+            let before = clause_db.number_of_literals();
+            clause_db.compact(|c| !db_view.is_active(c) && self.last_use[c] <= step);
+            tracing::debug!(
+                "compacted clause storage from {} to {} literals",
+                before,
+                clause_db.number_of_literals()
+            );
+            self.dead_literals = 0;
+        }
+    }
+}
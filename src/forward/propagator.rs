@@ -1,34 +1,61 @@
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::fmt;
+
 use crate::common::{
     storage::{Clause, ClauseStorage, View},
     Assignment, Conflict,
 };
 
+mod headtail;
 mod immutable;
 mod mutating;
 mod naive;
 
+pub use headtail::*;
 pub use immutable::*;
 pub use mutating::*;
 pub use naive::*;
 
+/// Unit propagation over the active clauses, which the forward checker runs for every RUP and RAT
+/// check. Implement it to check proofs with a custom propagation strategy, see
+/// [`CheckerOptions::propagator`](crate::CheckerOptions::propagator). The checker uses it as a
+/// `Box<dyn Propagator>` created by a [`PropagatorFactory`], so it is selected at runtime.
+///
+/// The checker owns the clause storage and the assignment and passes them to every call. It rolls
+/// the assignment back after each check, a propagator which keeps state per assigned literal can
+/// find out how far with [`Assignment::take_low_water_mark`].
 pub trait Propagator {
-    fn init(clause_db: &ClauseStorage, db_view: &View) -> Self;
+    /// Set up the propagator for the clauses which are active in the view, which are the clauses
+    /// of the formula.
+    fn init(clause_db: &ClauseStorage, db_view: &View) -> Self
+    where
+        Self: Sized;
+
+    /// The name of the propagator in messages, such as those of --cross-check.
+    fn name(&self) -> &str {
+        let name = core::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
 
+    /// Assign all literals which are implied by the active clauses under the current assignment.
+    /// Returns `Err(Conflict)` if an active clause is falsified. The literals of a clause may be
+    /// reordered in the clause storage, for example to keep the watched literals in front.
     fn propagate(
         &mut self,
         clause_db: &mut ClauseStorage,
         assignment: &mut Assignment,
     ) -> Result<(), Conflict>;
 
+    /// Assign the literals of all active unit clauses, which is done before the first
+    /// propagation.
     fn propagate_true_units(
         &self,
         clause_db: &ClauseStorage,
         db_view: &View,
         assignment: &mut Assignment,
     ) -> Result<(), Conflict> {
-        for c in clause_db.clauses(db_view) {
-            // check if there exists is no second literal
-            // this is thus a true unit
+        for c in clause_db.units(db_view) {
             if let Some(unit) = clause_db.extract_true_unit(c) {
                 if let e @ Err(_) = assignment.try_assign(unit) {
                     return e.map(|_| ());
@@ -38,7 +65,60 @@ pub trait Propagator {
         Ok(())
     }
 
+    /// The clause has become active, either as a lemma or when a deletion is rolled back.
     fn add_clause(&mut self, clause: Clause, clause_db: &ClauseStorage);
 
+    /// The clause is no longer active and must not be propagated anymore.
     fn delete_clause(&mut self, clause: Clause, clause_db: &ClauseStorage);
+
+    /// Approximate bytes allocated by the propagator, such as its watchlists.
+    fn heap_size(&self) -> usize {
+        0
+    }
+
+    /// Make room for the literals of all variables up to `max_variable`, which lemmas may
+    /// introduce after `init` when the proof is checked while it is read.
+    fn grow(&mut self, _max_variable: u32) {}
+
+    /// Check the internal data structures against the clause database and the active clauses
+    /// with --paranoid. Returns the first inconsistency found. The assignment may have been rolled
+    /// back since the last propagation.
+    fn check_invariants(&self, _clause_db: &ClauseStorage, _db_view: &View) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+type Init = dyn Fn(&ClauseStorage, &View) -> Box<dyn Propagator> + Send + Sync;
+
+/// Creates the propagator of a check for the clauses which are active in the view.
+#[derive(Clone)]
+pub struct PropagatorFactory {
+    init: Arc<Init>,
+}
+
+impl PropagatorFactory {
+    /// The factory calling `init` for every check.
+    pub fn new(
+        init: impl Fn(&ClauseStorage, &View) -> Box<dyn Propagator> + Send + Sync + 'static,
+    ) -> Self {
+        PropagatorFactory {
+            init: Arc::new(init),
+        }
+    }
+
+    /// The factory using `P::init`.
+    pub fn of<P: Propagator + 'static>() -> Self {
+        PropagatorFactory::new(|clause_db, db_view| Box::new(P::init(clause_db, db_view)))
+    }
+
+    /// Create a propagator for the clauses which are active in the view.
+    pub fn init(&self, clause_db: &ClauseStorage, db_view: &View) -> Box<dyn Propagator> {
+        (self.init)(clause_db, db_view)
+    }
+}
+
+impl fmt::Debug for PropagatorFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PropagatorFactory")
+    }
 }
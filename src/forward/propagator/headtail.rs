@@ -0,0 +1,363 @@
+use crate::prelude::*;
+use core::mem;
+
+use super::Propagator;
+use crate::common::{
+    stats::vec_bytes,
+    storage::{
+        watch_list_bytes, Clause, ClauseArray, ClauseKind, ClauseStorage, LiteralArray, View,
+        WatchList,
+    },
+    Assignment, Conflict, Literal,
+};
+
+#[derive(Debug, Clone, Copy)]
+enum End {
+    Head,
+    Tail,
+}
+
+// A pointer movement which has to be reverted once the literal at trace position `trace_pos` is
+// rolled back.
+#[derive(Debug, Clone, Copy)]
+struct Move {
+    trace_pos: usize,
+    clause: Clause,
+    end: End,
+    old: usize,
+}
+
+/// Propagator based on head/tail lists. Every clause has a head pointer moving right and a tail
+/// pointer moving left over its literals. All literals in front of the head and behind the tail
+/// are falsified. Only the literals pointed at are watched. Once head and tail meet the clause is
+/// either unit or falsified.
+/// In contrast to the two watched literals scheme the pointers have to be restored when the
+/// assignment is rolled back, which is done with an undo log indexed by the trace position.
+/// Binary clauses are kept out of the head/tail lists and stored as implications instead, which
+/// propagate without looking at the clause storage.
+/// Pointers only skip literals whose falsification has already been processed. A literal which is
+/// falsified later in the trace may be rolled back independently of the current one, so the
+/// pointer stops there and moves on once that literal is processed.
+pub struct HeadTailPropagator {
+    // head and tail index into the literals of each clause
+    pointers: ClauseArray<(usize, usize)>,
+    active: ClauseArray<bool>,
+    // clauses whose head or tail literal is the key, entries may be stale
+    watches: LiteralArray<WatchList<Clause>>,
+    // for every literal the binary clauses containing it together with their other literal
+    binaries: LiteralArray<WatchList<(Literal, Clause)>>,
+    // clauses which have been added but whose pointers have not been set up yet
+    pending: Vec<Clause>,
+    undo: Vec<Move>,
+    // the falsified literals of the trace which have been processed, in trace order
+    processed: Vec<Literal>,
+    // whether a literal is contained in processed
+    falsified: LiteralArray<bool>,
+}
+
+impl HeadTailPropagator {
+    // Set up the pointers of a new clause for the current assignment. Skipped literals are
+    // falsified at the top level, so these movements never have to be undone.
+    fn setup(
+        &mut self,
+        clause: Clause,
+        clause_db: &ClauseStorage,
+        assignment: &mut Assignment,
+    ) -> Result<(), Conflict> {
+        match clause_db.kind(clause) {
+            ClauseKind::Empty => return Err(Conflict {}),
+            ClauseKind::Unit(lit) => return assignment.try_assign(lit).map(|_| ()),
+            ClauseKind::Binary(a, b) => {
+                self.binaries[a].push((b, clause));
+                self.binaries[b].push((a, clause));
+                return match (assignment.is_true(-a), assignment.is_true(-b)) {
+                    (true, true) => Err(Conflict {}),
+                    (true, false) => assignment.try_assign(b).map(|_| ()),
+                    (false, true) => assignment.try_assign(a).map(|_| ()),
+                    (false, false) => Ok(()),
+                };
+            }
+            ClauseKind::Long(..) => {}
+        }
+        let literals = clause_db.clause(clause);
+        let non_false = |&i: &usize| !assignment.is_true(-literals[i]);
+        let head = (0..literals.len()).find(non_false).ok_or(Conflict {})?;
+        let tail = (0..literals.len()).rev().find(non_false).unwrap_or(head);
+        self.pointers[clause] = (head, tail);
+        self.watches[literals[head]].push(clause);
+        if head == tail {
+            assignment.try_assign(literals[head])?;
+        } else {
+            self.watches[literals[tail]].push(clause);
+        }
+        Ok(())
+    }
+
+    // Revert all pointer movements caused by literals at or after the trace position.
+    fn undo_until(&mut self, trace_pos: usize, clause_db: &ClauseStorage) {
+        while let Some(&m) = self.undo.last() {
+            if m.trace_pos < trace_pos {
+                break;
+            }
+            self.undo.pop();
+            if !self.active[m.clause] {
+                // the pointers are set up again if the clause is ever added back
+                continue;
+            }
+            let (head, tail) = &mut self.pointers[m.clause];
+            match m.end {
+                End::Head => *head = m.old,
+                End::Tail => *tail = m.old,
+            }
+            self.watches[clause_db.clause(m.clause)[m.old]].push(m.clause);
+        }
+    }
+
+    // Move the pointers of all clauses watching the falsified literal past all literals whose
+    // falsification has been processed. Returns a conflict if all literals of a clause are
+    // falsified.
+    fn falsify(
+        &mut self,
+        falsified: Literal,
+        trace_pos: usize,
+        clause_db: &ClauseStorage,
+        assignment: &mut Assignment,
+    ) -> Result<(), Conflict> {
+        for &(other, _) in &self.binaries[falsified] {
+            assignment.try_assign(other)?;
+        }
+
+        let mut watching = mem::take(&mut self.watches[falsified]);
+        let mut result = Ok(());
+        let mut i = 0;
+        while i < watching.len() {
+            let clause = watching[i];
+            if !self.active[clause] {
+                // deleted clauses are removed lazily, their literals may be gone already
+                watching.swap_remove(i);
+                continue;
+            }
+            let literals = clause_db.clause(clause);
+            let (head, tail) = self.pointers[clause];
+            let end = if literals[head] == falsified {
+                End::Head
+            } else if literals[tail] == falsified {
+                End::Tail
+            } else {
+                // stale entry of a clause that moved on
+                watching.swap_remove(i);
+                continue;
+            };
+
+            let (old, next) = match end {
+                End::Head => (
+                    head,
+                    (head + 1..tail).find(|&j| !self.falsified[literals[j]]),
+                ),
+                End::Tail => (
+                    tail,
+                    (head + 1..tail)
+                        .rev()
+                        .find(|&j| !self.falsified[literals[j]]),
+                ),
+            };
+            match next {
+                Some(j) => {
+                    // found a new literal to point at, leave this watchlist
+                    self.watches[literals[j]].push(clause);
+                    watching.swap_remove(i);
+                    self.set(clause, end, j);
+                    self.undo.push(Move {
+                        trace_pos,
+                        clause,
+                        end,
+                        old,
+                    });
+                }
+                None => {
+                    // head and tail meet, the other pointer is the last literal which may not be
+                    // falsified
+                    let other = match end {
+                        End::Head => tail,
+                        End::Tail => head,
+                    };
+                    if head == tail || assignment.try_assign(literals[other]).is_err() {
+                        result = Err(Conflict {});
+                        break;
+                    }
+                    i += 1;
+                }
+            }
+        }
+        // put back the remaining entries, newly added ones are kept as well
+        watching.append(&mut self.watches[falsified]);
+        self.watches[falsified] = watching;
+        result
+    }
+
+    fn set(&mut self, clause: Clause, end: End, index: usize) {
+        let (head, tail) = &mut self.pointers[clause];
+        match end {
+            End::Head => *head = index,
+            End::Tail => *tail = index,
+        }
+    }
+}
+
+impl Propagator for HeadTailPropagator {
+    fn init(clause_db: &ClauseStorage, db_view: &View) -> Self {
+        let mut active = clause_db.clause_array();
+        let mut pending = vec![];
+        for clause in clause_db.clauses(db_view) {
+            active[clause] = true;
+            // units are assigned separately and the empty clause is never propagated
+            if let ClauseKind::Binary(..) | ClauseKind::Long(..) = clause_db.kind(clause) {
+                pending.push(clause);
+            }
+        }
+        HeadTailPropagator {
+            pointers: clause_db.clause_array(),
+            active,
+            watches: clause_db.literal_array(),
+            binaries: clause_db.literal_array(),
+            pending,
+            undo: vec![],
+            processed: vec![],
+            falsified: clause_db.literal_array(),
+        }
+    }
+
+    fn propagate(
+        &mut self,
+        clause_db: &mut ClauseStorage,
+        assignment: &mut Assignment,
+    ) -> Result<(), Conflict> {
+        let low_water_mark = assignment.take_low_water_mark();
+        if low_water_mark <= self.processed.len() {
+            self.undo_until(low_water_mark, clause_db);
+            for lit in self.processed.drain(low_water_mark..) {
+                self.falsified[lit] = false;
+            }
+        }
+
+        while let Some(&clause) = self.pending.last() {
+            if self.active[clause] {
+                self.setup(clause, clause_db, assignment)?;
+            }
+            self.pending.pop();
+        }
+
+        while self.processed.len() < assignment.trace_len() {
+            let trace_pos = self.processed.len();
+            let falsified = -assignment.nth_lit(trace_pos);
+            self.falsify(falsified, trace_pos, clause_db, assignment)?;
+            self.processed.push(falsified);
+            self.falsified[falsified] = true;
+        }
+        Ok(())
+    }
+
+    fn add_clause(&mut self, clause: Clause, _clause_db: &ClauseStorage) {
+        self.active[clause] = true;
+        self.pending.push(clause);
+    }
+
+    fn delete_clause(&mut self, clause: Clause, clause_db: &ClauseStorage) {
+        // watchlist entries are removed lazily, binary implications right away
+        self.active[clause] = false;
+        if let ClauseKind::Binary(a, b) = clause_db.kind(clause) {
+            self.binaries[a].retain(|(_, c)| *c != clause);
+            self.binaries[b].retain(|(_, c)| *c != clause);
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        self.pointers.heap_size()
+            + self.active.heap_size()
+            + self.watches.heap_size()
+            + self.watches.iter().map(watch_list_bytes).sum::<usize>()
+            + self.binaries.heap_size()
+            + self.binaries.iter().map(watch_list_bytes).sum::<usize>()
+            + vec_bytes(&self.pending)
+            + vec_bytes(&self.undo)
+            + vec_bytes(&self.processed)
+            + self.falsified.heap_size()
+    }
+
+    fn grow(&mut self, max_variable: u32) {
+        self.watches.grow(max_variable);
+        self.binaries.grow(max_variable);
+        self.falsified.grow(max_variable);
+    }
+
+    fn check_invariants(&self, clause_db: &ClauseStorage, db_view: &View) -> Result<(), String> {
+        for clause in clause_db.all_clauses() {
+            let long = matches!(
+                clause_db.kind(clause),
+                ClauseKind::Binary(..) | ClauseKind::Long(..)
+            );
+            match (self.active[clause], db_view.is_active(clause)) {
+                (true, false) => return Err(format!("clause {} is active but deleted", clause)),
+                (false, true) if long => {
+                    return Err(format!("clause {} is active but not propagated", clause))
+                }
+                (false, _) => continue,
+                (true, true) => {}
+            }
+            // pending clauses have not been set up yet
+            if self.pending.contains(&clause) {
+                continue;
+            }
+            match clause_db.kind(clause) {
+                ClauseKind::Binary(a, b) => {
+                    let implies = |lit: Literal, other: Literal| {
+                        self.binaries[lit].contains(&(other, clause))
+                    };
+                    if !implies(a, b) || !implies(b, a) {
+                        return Err(format!(
+                            "binary clause {} is missing an implication",
+                            clause
+                        ));
+                    }
+                }
+                ClauseKind::Long(..) => {
+                    let literals = clause_db.clause(clause);
+                    let (head, tail) = self.pointers[clause];
+                    if head > tail || tail >= literals.len() {
+                        return Err(format!(
+                            "clause {} has head {} and tail {} with {} literals",
+                            clause,
+                            head,
+                            tail,
+                            literals.len()
+                        ));
+                    }
+                    for end in [head, tail] {
+                        if !self.watches[literals[end]].contains(&clause) {
+                            return Err(format!(
+                                "clause {} is not watched by {}",
+                                clause, literals[end]
+                            ));
+                        }
+                    }
+                }
+                ClauseKind::Empty | ClauseKind::Unit(_) => {}
+            }
+        }
+        if let Some(lit) = self.processed.iter().find(|&&lit| !self.falsified[lit]) {
+            return Err(format!(
+                "processed literal {} is not marked as falsified",
+                lit
+            ));
+        }
+        let marked = self.falsified.iter().filter(|&&marked| marked).count();
+        if marked != self.processed.len() {
+            return Err(format!(
+                "{} literals are marked as falsified but {} are processed",
+                marked,
+                self.processed.len()
+            ));
+        }
+        Ok(())
+    }
+}
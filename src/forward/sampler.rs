@@ -0,0 +1,59 @@
+use crate::CheckerOptions;
+
+/// Decides which lemma additions are fully verified when running with `--sample`. Uses a small
+/// splitmix64 generator so that runs with the same seed check the same subset of lemmas.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sampler {
+    probability: Option<f64>,
+    state: u64,
+    checked: usize,
+    skipped: usize,
+}
+
+impl Sampler {
+    pub fn new(probability: Option<f64>, seed: u64) -> Self {
+        Sampler {
+            probability,
+            state: seed,
+            checked: 0,
+            skipped: 0,
+        }
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        // use the upper 53 bits to get a uniformly distributed float in [0, 1)
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Should the next lemma addition be verified?
+    pub fn check_next(&mut self) -> bool {
+        let check = match self.probability {
+            Some(p) => self.next_f64() < p,
+            None => true,
+        };
+        if check {
+            self.checked += 1;
+        } else {
+            self.skipped += 1;
+        }
+        check
+    }
+
+    /// Print a summary of how many lemmas were actually checked if sampling is enabled.
+    pub fn report(&self, options: &CheckerOptions) {
+        if self.probability.is_some() {
+            comment!(
+                options,
+                "sampled {} of {} lemma additions, verdict is only probabilistic",
+                self.checked,
+                self.checked + self.skipped
+            );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Random formulas and proofs for fuzzing and property tests. An [`Instance`] created with
+//! [`Instance::valid`] always has a proof which is verified, [`Instance::corrupted`] changes such
+//! a proof in a few places. Many lemmas of the proofs are not needed, so a corrupted proof may
+//! still be verified, but all propagators have to agree on it. Checking either must never panic.
+//!
+//! The formulas contain every clause over a few core variables, which is unsatisfiable, together
+//! with random clauses over the remaining variables. They never propagate to a conflict on their
+//! own, so the proofs are verified with the default options. The proof resolves the core clauses
+//! variable by variable down to the empty clause, every lemma of it has RUP.
+//!
+//! [`roundtrip`] writes random formulas and proofs in every format and parses them back.
+
+use crate::prelude::*;
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{convert, ProofStep};
+
+pub mod roundtrip;
+
+// Keep the formulas small, there are 2^CORE_VARIABLES core clauses.
+const CORE_VARIABLES: u32 = 6;
+const NOISE_VARIABLES: u32 = 20;
+const NOISE_CLAUSES: usize = 20;
+const NOISE_CLAUSE_LEN: usize = 5;
+const CORRUPTIONS: usize = 3;
+
+/// A formula with a proof, given as DIMACS literals like for [`check_clauses`](crate::check_clauses).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub formula: Vec<Vec<i32>>,
+    pub proof: Vec<ProofStep>,
+}
+
+/// How [`Instance::corrupted`] changes a valid proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Arbitrary)]
+pub enum Corruption {
+    /// Remove the proof step with this index, modulo the length of the proof.
+    DropStep(usize),
+    /// Negate a literal of the lemma added by the step with this index.
+    NegateLiteral(usize),
+    /// Remove the formula clause with this index.
+    DropClause(usize),
+    /// Delete the clauses added by the proof right after adding them.
+    DeleteLemmas,
+}
+
+impl Instance {
+    /// A formula with a proof which is verified.
+    pub fn valid(u: &mut Unstructured<'_>) -> arbitrary::Result<Self> {
+        let core = u.int_in_range(2..=CORE_VARIABLES)?;
+        let vars = core + u.int_in_range(0..=NOISE_VARIABLES)?;
+
+        // the core variables are spread over all variables and used with either polarity
+        let mut order = (1..=vars as i32).collect::<Vec<_>>();
+        shuffle(u, &mut order)?;
+        let (core_vars, noise_vars) = order.split_at_mut(core as usize);
+        for var in core_vars.iter_mut() {
+            *var = literal(u, *var)?;
+        }
+
+        // the random clauses contain at least two distinct variables which are not in the core,
+        // so that the formula does not propagate to a conflict before the proof is checked
+        let mut formula = cube(core_vars, core);
+        if noise_vars.len() >= 2 {
+            for _ in 0..u.int_in_range(0..=NOISE_CLAUSES)? {
+                shuffle(u, noise_vars)?;
+                let len = u.int_in_range(2..=NOISE_CLAUSE_LEN.min(noise_vars.len()))?;
+                let clause = noise_vars[..len]
+                    .iter()
+                    .map(|&var| literal(u, var))
+                    .collect::<arbitrary::Result<_>>()?;
+                formula.push(clause);
+            }
+        }
+        for clause in &mut formula {
+            shuffle(u, clause)?;
+        }
+        shuffle(u, &mut formula)?;
+
+        // derive all clauses over one core variable less from those of the level above, which
+        // may be deleted afterwards
+        let mut proof = Vec::new();
+        for level in (0..core).rev() {
+            for clause in cube(core_vars, level) {
+                proof.push(ProofStep::Add(clause));
+            }
+            if u.arbitrary()? {
+                for clause in cube(core_vars, level + 1) {
+                    proof.push(ProofStep::Delete(clause));
+                }
+            }
+        }
+        Ok(Instance { formula, proof })
+    }
+
+    /// A valid instance with a few corruptions applied.
+    pub fn corrupted(u: &mut Unstructured<'_>) -> arbitrary::Result<Self> {
+        let mut instance = Instance::valid(u)?;
+        for _ in 0..u.int_in_range(1..=CORRUPTIONS)? {
+            instance.corrupt(u.arbitrary()?);
+        }
+        Ok(instance)
+    }
+
+    /// Apply a corruption, which may leave the proof valid.
+    pub fn corrupt(&mut self, corruption: Corruption) {
+        match corruption {
+            Corruption::DropStep(index) => {
+                if !self.proof.is_empty() {
+                    self.proof.remove(index % self.proof.len());
+                }
+            }
+            Corruption::NegateLiteral(index) => {
+                if self.proof.is_empty() {
+                    return;
+                }
+                let len = self.proof.len();
+                if let ProofStep::Add(clause) = &mut self.proof[index % len] {
+                    if let Some(lit) = clause.first_mut() {
+                        *lit = -*lit;
+                    }
+                }
+            }
+            Corruption::DropClause(index) => {
+                if !self.formula.is_empty() {
+                    self.formula.remove(index % self.formula.len());
+                }
+            }
+            Corruption::DeleteLemmas => {
+                self.proof = core::mem::take(&mut self.proof)
+                    .into_iter()
+                    .flat_map(|step| match step {
+                        ProofStep::Add(clause) => {
+                            vec![ProofStep::Add(clause.clone()), ProofStep::Delete(clause)]
+                        }
+                        step => vec![step],
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    /// The formula in DIMACS format.
+    pub fn dimacs(&self) -> String {
+        convert::write_dimacs(&self.formula)
+    }
+
+    /// The proof in textual DRAT format.
+    pub fn drat(&self) -> String {
+        convert::write_drat(&self.proof)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Instance {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Instance::valid(u)
+    }
+}
+
+// All clauses over the first `len` core variables. The clauses over all core variables are
+// unsatisfiable.
+fn cube(core_vars: &[i32], len: u32) -> Vec<Vec<i32>> {
+    (0..1u32 << len)
+        .map(|signs| {
+            core_vars[..len as usize]
+                .iter()
+                .enumerate()
+                .map(|(i, &lit)| if signs >> i & 1 == 1 { -lit } else { lit })
+                .collect()
+        })
+        .collect()
+}
+
+fn literal(u: &mut Unstructured<'_>, var: i32) -> arbitrary::Result<i32> {
+    Ok(if u.arbitrary()? { -var } else { var })
+}
+
+fn shuffle<T>(u: &mut Unstructured<'_>, items: &mut [T]) -> arbitrary::Result<()> {
+    for i in (1..items.len()).rev() {
+        items.swap(i, u.int_in_range(0..=i)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,359 @@
+//! Round trips of random formulas and proofs through the writers and parsers. A [`Sample`] is
+//! written in a [`Format`] and parsed back, which has to give the clauses and steps of the sample
+//! as the parser normalizes them. [`shrink`] reduces a sample whose round trip fails to a small one
+//! which still fails, so that failures found by the fuzzer can be read.
+//!
+//! A new format only needs a variant of [`Format`] and its round trip in [`check`].
+
+use crate::prelude::*;
+use arbitrary::{Arbitrary, Unstructured};
+use core::fmt;
+
+use crate::{
+    common::{Literal, RawLemma},
+    convert,
+    parser::{
+        cnf, drat,
+        lrat::{self, LratStep},
+        normalize_clause,
+    },
+    ProofStep,
+};
+
+const MAX_CLAUSES: usize = 20;
+const MAX_CLAUSE_LEN: usize = 8;
+const MAX_HINTS: usize = 8;
+
+/// A formula with a DRAT and an LRAT proof, given as DIMACS literals. The proofs do not have to
+/// be valid, but no clause contains the literal 0 or `i32::MIN`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub formula: Vec<Vec<i32>>,
+    pub proof: Vec<ProofStep>,
+    pub lrat: Vec<LratStep>,
+}
+
+/// The formats which are written and parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Dimacs,
+    Drat,
+    BinaryDrat,
+    Lrat,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [
+        Format::Dimacs,
+        Format::Drat,
+        Format::BinaryDrat,
+        Format::Lrat,
+    ];
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Dimacs => write!(f, "DIMACS"),
+            Format::Drat => write!(f, "DRAT"),
+            Format::BinaryDrat => write!(f, "binary DRAT"),
+            Format::Lrat => write!(f, "LRAT"),
+        }
+    }
+}
+
+/// A round trip which did not give back the sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub format: Format,
+    /// The written input, binary DRAT as a list of bytes.
+    pub written: String,
+    /// How the parsed input differs from the sample.
+    pub message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "round trip through {} failed, {} in\n{}",
+            self.format, self.message, self.written
+        )
+    }
+}
+
+impl<'a> Arbitrary<'a> for Sample {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let formula = (0..u.int_in_range(0..=MAX_CLAUSES)?)
+            .map(|_| clause(u))
+            .collect::<arbitrary::Result<_>>()?;
+        let proof = (0..u.int_in_range(0..=MAX_CLAUSES)?)
+            .map(|_| {
+                let clause = clause(u)?;
+                Ok(if u.arbitrary()? {
+                    ProofStep::Delete(clause)
+                } else {
+                    ProofStep::Add(clause)
+                })
+            })
+            .collect::<arbitrary::Result<_>>()?;
+
+        // the ids of the lemmas increase like in a proof, but the hints and deleted ids are any
+        let mut id = u64::from(u.arbitrary::<u32>()?);
+        let mut lrat = Vec::new();
+        for _ in 0..u.int_in_range(0..=MAX_CLAUSES)? {
+            if u.arbitrary()? {
+                id = id.saturating_add(u.int_in_range(1..=1000)?);
+                let clause = clause(u)?;
+                let hints = (0..u.int_in_range(0..=MAX_HINTS)?)
+                    .map(|_| {
+                        let hint = u.int_in_range(1..=i64::MAX)?;
+                        Ok(if u.arbitrary()? { -hint } else { hint })
+                    })
+                    .collect::<arbitrary::Result<_>>()?;
+                lrat.push(LratStep::Add { id, clause, hints });
+            } else {
+                let clauses = (0..u.int_in_range(0..=MAX_HINTS)?)
+                    .map(|_| u.int_in_range(1..=u64::MAX))
+                    .collect::<arbitrary::Result<_>>()?;
+                lrat.push(LratStep::Delete { id, clauses });
+            }
+        }
+        Ok(Sample {
+            formula,
+            proof,
+            lrat,
+        })
+    }
+}
+
+// A clause which may contain duplicate literals and tautologies, which the parsers normalize.
+fn clause(u: &mut Unstructured<'_>) -> arbitrary::Result<Vec<i32>> {
+    (0..u.int_in_range(0..=MAX_CLAUSE_LEN)?)
+        .map(|_| Ok(u.arbitrary::<Literal>()?.raw()))
+        .collect()
+}
+
+/// Write the sample in the format and parse it back.
+pub fn check(sample: &Sample, format: Format) -> Result<(), Failure> {
+    let (written, result) = match format {
+        Format::Dimacs => {
+            let text = convert::write_dimacs(&sample.formula);
+            let expected = sample
+                .formula
+                .iter()
+                .map(|clause| normalized(clause))
+                .collect();
+            let result = compare(cnf::parse(&text).map(|(_, clauses)| clauses), expected);
+            (text, result)
+        }
+        Format::Drat => {
+            let text = convert::write_drat(&sample.proof);
+            let expected = sample
+                .proof
+                .iter()
+                .map(|step| match step {
+                    ProofStep::Add(clause) => RawLemma::Add(normalized(clause)),
+                    ProofStep::Delete(clause) => RawLemma::Del(normalized(clause)),
+                })
+                .collect();
+            let result = compare(drat::parse(&text), expected);
+            (text, result)
+        }
+        Format::BinaryDrat => {
+            // binary DRAT keeps the clauses as they are written
+            let bytes = convert::write_binary_drat(&sample.proof);
+            let result = compare(drat::parse_binary(&bytes, None), sample.proof.clone());
+            (format!("{:?}", bytes), result)
+        }
+        Format::Lrat => {
+            let text = convert::write_lrat(&sample.lrat);
+            let result = compare(lrat::parse(&text), sample.lrat.clone());
+            (text, result)
+        }
+    };
+    result.map_err(|message| Failure {
+        format,
+        written,
+        message,
+    })
+}
+
+/// Check the round trips through all formats.
+pub fn check_all(sample: &Sample) -> Result<(), Failure> {
+    Format::ALL
+        .into_iter()
+        .try_for_each(|format| check(sample, format))
+}
+
+fn normalized(clause: &[i32]) -> Vec<Literal> {
+    normalize_clause(clause.to_vec()).expect("a sample contains neither 0 nor i32::MIN")
+}
+
+fn compare<T: PartialEq + fmt::Debug>(
+    parsed: crate::Result<Vec<T>>,
+    expected: Vec<T>,
+) -> Result<(), String> {
+    let parsed = parsed.map_err(|e| format!("it is rejected with '{}'", e))?;
+    if let Some(i) = parsed.iter().zip(&expected).position(|(p, e)| p != e) {
+        return Err(format!(
+            "item {} is parsed as {:?} instead of {:?}",
+            i + 1,
+            parsed[i],
+            expected[i]
+        ));
+    }
+    if parsed.len() != expected.len() {
+        return Err(format!(
+            "{} items are parsed instead of {}",
+            parsed.len(),
+            expected.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Shrink a sample whose round trip through the format fails. Clauses, proof steps, literals and
+/// hints are removed one at a time as long as the round trip still fails, so the result is small
+/// but not necessarily the smallest. A sample which does not fail is returned as it is.
+pub fn shrink(mut sample: Sample, format: Format) -> Sample {
+    loop {
+        let formula =
+            smaller_items(&sample.formula, |clause| smaller_clause(clause)).map(|formula| Sample {
+                formula,
+                ..sample.clone()
+            });
+        let proof = smaller_items(&sample.proof, smaller_step).map(|proof| Sample {
+            proof,
+            ..sample.clone()
+        });
+        let lrat = smaller_items(&sample.lrat, smaller_lrat_step).map(|lrat| Sample {
+            lrat,
+            ..sample.clone()
+        });
+        let smaller = formula
+            .chain(proof)
+            .chain(lrat)
+            .find(|smaller| check(smaller, format).is_err());
+        match smaller {
+            Some(smaller) => sample = smaller,
+            None => return sample,
+        }
+    }
+}
+
+// The items with one of them removed, and then with one of them replaced by a smaller one.
+fn smaller_items<T: Clone>(
+    items: &[T],
+    smaller: fn(&T) -> Vec<T>,
+) -> impl Iterator<Item = Vec<T>> + '_ {
+    let removed = (0..items.len()).map(move |i| {
+        let mut items = items.to_vec();
+        items.remove(i);
+        items
+    });
+    let replaced = (0..items.len()).flat_map(move |i| {
+        smaller(&items[i]).into_iter().map(move |item| {
+            let mut items = items.to_vec();
+            items[i] = item;
+            items
+        })
+    });
+    removed.chain(replaced)
+}
+
+fn smaller_clause(clause: &[i32]) -> Vec<Vec<i32>> {
+    smaller_items(clause, |_| Vec::new()).collect()
+}
+
+fn smaller_step(step: &ProofStep) -> Vec<ProofStep> {
+    match step {
+        ProofStep::Add(clause) => smaller_clause(clause)
+            .into_iter()
+            .map(ProofStep::Add)
+            .collect(),
+        ProofStep::Delete(clause) => smaller_clause(clause)
+            .into_iter()
+            .map(ProofStep::Delete)
+            .collect(),
+    }
+}
+
+fn smaller_lrat_step(step: &LratStep) -> Vec<LratStep> {
+    match step {
+        LratStep::Add { id, clause, hints } => {
+            let clauses = smaller_clause(clause)
+                .into_iter()
+                .map(|clause| LratStep::Add {
+                    id: *id,
+                    clause,
+                    hints: hints.clone(),
+                });
+            let hints = smaller_items(hints, |_| Vec::new()).map(|hints| LratStep::Add {
+                id: *id,
+                clause: clause.clone(),
+                hints,
+            });
+            clauses.chain(hints).collect()
+        }
+        LratStep::Delete { id, clauses } => smaller_items(clauses, |_| Vec::new())
+            .map(|clauses| LratStep::Delete { id: *id, clauses })
+            .collect(),
+    }
+}
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod tests {
+    use super::*;
+
+    // Bytes for the samples, the same in every run.
+    fn bytes(seed: u64) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips() {
+        for seed in 0..256 {
+            let bytes = bytes(seed);
+            let sample = Sample::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            if let Err(failure) = check_all(&sample) {
+                let shrunk = shrink(sample, failure.format);
+                panic!("{}\nshrunk to {:?}", failure, shrunk);
+            }
+        }
+    }
+
+    #[test]
+    fn shrinks_a_failing_sample() {
+        // the hint 0 ends the hints of the first step early, so it cannot be parsed back
+        let sample = Sample {
+            formula: vec![vec![1, 2], vec![-1]],
+            proof: vec![ProofStep::Add(vec![2])],
+            lrat: vec![
+                LratStep::Add {
+                    id: 3,
+                    clause: vec![2, 3],
+                    hints: vec![1, 0, 2],
+                },
+                LratStep::Delete {
+                    id: 3,
+                    clauses: vec![1],
+                },
+            ],
+        };
+        let failure = check_all(&sample).unwrap_err();
+        assert_eq!(failure.format, Format::Lrat);
+        let shrunk = shrink(sample, failure.format);
+        assert!(check(&shrunk, Format::Lrat).is_err());
+        assert!(shrunk.formula.is_empty() && shrunk.proof.is_empty());
+        assert_eq!(shrunk.lrat.len(), 1);
+    }
+}
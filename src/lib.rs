@@ -0,0 +1,1467 @@
+//! A checker for DRAT proofs of unsatisfiability. Use [`check_drat`] to verify a proof for a
+//! formula in DIMACS format, the `ratify` binary is a thin wrapper around it.
+//!
+//! Without the default `std` feature the checker only needs `alloc`. Everything reading files,
+//! measuring time or printing is not available then.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Print a comment line like `c conflict derived at step 5` as part of the output, to stderr if the
+// options ask for it. Without std the line is logged instead.
+macro_rules! comment {
+    ($options:expr, $($arg:tt)*) => {{
+        #[cfg(feature = "std")]
+        if $options.comments_to_stderr {
+            std::eprintln!("c {}", format_args!($($arg)*));
+        } else {
+            std::println!("c {}", format_args!($($arg)*));
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = &$options;
+            tracing::info!($($arg)*);
+        }
+    }};
+}
+
+// The items of the std prelude which come from alloc, for building without std.
+#[allow(unused_imports)]
+mod prelude {
+    pub use alloc::{
+        borrow::ToOwned,
+        boxed::Box,
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+}
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+mod cancel;
+pub mod common;
+pub mod convert;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod forward;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+mod model;
+mod observer;
+mod online;
+mod profile;
+mod progress;
+#[cfg(feature = "python")]
+mod python;
+mod trim;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub mod parser;
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+// std has no clock on wasm32-unknown-unknown, the browser's one is used instead
+#[cfg(not(feature = "std"))]
+use common::stats::Instant;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::time::Instant;
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+use web_time::Instant;
+
+#[allow(unused_imports)]
+use prelude::*;
+
+use common::storage::{Builder, ClauseStorage, View};
+use itertools::Itertools;
+
+use crate::common::{stats, storage, Origins, Preprocessor, Renaming};
+pub use crate::common::{Lemma, Literal, RawLemma};
+
+pub use cancel::CancellationToken;
+pub use error::{Error, Result};
+pub use forward::{Propagator, PropagatorFactory};
+#[cfg(feature = "fs")]
+pub use model::verify_model;
+pub use model::{verify_model_str, ModelReport, UnsatisfiedClause};
+pub use observer::{Observer, StepOutcome};
+pub use online::OnlineChecker;
+use parser::format::ProofParser;
+pub use profile::Profile;
+use progress::Progress;
+pub use progress::ProgressSink;
+#[cfg(feature = "fs")]
+pub use trim::trim_drat;
+pub use trim::{trim_str, TrimStrategy, Trimmed};
+
+/// The outcome of a check. Rejected proofs are reported with a verdict as well, errors are left
+/// for input which cannot be read and other failures of the checker itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// All checked lemmas are redundant and the proof derives a conflict.
+    Verified,
+    /// Like `Verified`, but only a random sample of the lemmas has been checked with --sample.
+    VerifiedProbabilistic,
+    /// The lemmas in the range of [`CheckerOptions::steps`] are redundant and the proof derives a
+    /// conflict, but the lemmas outside of it have been added without being checked and may be
+    /// the ones deriving it.
+    VerifiedPartially,
+    /// The check has been stopped by a [`CancellationToken`] in this phase before it was done.
+    /// The report contains the statistics up to this point.
+    Cancelled { phase: Phase },
+    /// The check has been stopped in this phase because it needs more memory than allowed by
+    /// [`CheckerOptions::max_memory`]. The report contains the statistics up to this point.
+    MemoryLimitExceeded { phase: Phase },
+    /// The lemma at this proof step is not redundant. With --drup this is the first lemma which
+    /// requires RAT.
+    Refuted { step: usize },
+    /// All checked lemmas are redundant, but the proof does not derive a conflict.
+    NoConflict,
+    /// The formula propagates to a conflict before the proof is checked, which is only accepted
+    /// with --implicit-empty.
+    EarlyRefutation,
+}
+
+impl Verdict {
+    /// Whether the proof has been verified, possibly only probabilistically. A check of only a
+    /// range of the proof steps does not verify it.
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Verdict::Verified | Verdict::VerifiedProbabilistic)
+    }
+
+    /// Whether the proof has been rejected.
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            Verdict::Refuted { .. } | Verdict::NoConflict | Verdict::EarlyRefutation
+        )
+    }
+}
+
+/// The result of a successful check, which is also what the binary reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    pub verdict: Verdict,
+    /// The number of proof steps which have been applied, whether they were checked or not.
+    pub steps: usize,
+    /// The number of checked lemmas which have RUP.
+    pub rup_checks: usize,
+    /// The number of checked lemmas which do not have RUP but RAT.
+    pub rat_checks: usize,
+    /// The number of clauses needed to derive the conflict, if an unsatisfiable core has been
+    /// computed.
+    pub core_size: Option<usize>,
+    pub timings: Timings,
+    /// Why the proof has been rejected, if it has.
+    pub reason: Option<String>,
+    /// The lemma which is not redundant, if the proof has been rejected because of one.
+    pub rejected_lemma: Option<RejectedLemma>,
+    /// Where the time spent checking went, if profiling has been enabled.
+    pub profile: Option<Profile>,
+    /// The approximate bytes used by the parts of the checker, recorded with
+    /// [`CheckerOptions::stats`].
+    pub memory: Vec<(&'static str, usize)>,
+}
+
+/// A lemma which is not redundant, together with what is needed to explain why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedLemma {
+    pub step: usize,
+    /// The line of the lemma in the proof, for proofs given in memory its position. `None` if
+    /// the proof format has no lines.
+    pub line: Option<usize>,
+    /// The literals of the lemma in DIMACS format.
+    pub clause: Vec<i32>,
+    /// Whether only RUP has been checked because of --rup-only.
+    pub rup_only: bool,
+    /// The number of clauses which were active when the lemma was checked.
+    pub active_clauses: usize,
+    /// With --rup-only, the pivot on which the lemma has RAT if it has.
+    pub rat_pivot: Option<i32>,
+    /// Whether the lemma has RUP once the clauses the proof deleted before it are active again,
+    /// so that one of the deletions may be wrong. Deleted clauses which the garbage collector has
+    /// already removed from the clause storage are not taken into account.
+    pub needs_deleted: bool,
+}
+
+impl CheckReport {
+    pub(crate) fn new(verdict: Verdict) -> Self {
+        CheckReport {
+            verdict,
+            steps: 0,
+            rup_checks: 0,
+            rat_checks: 0,
+            core_size: None,
+            timings: Timings::default(),
+            reason: None,
+            rejected_lemma: None,
+            profile: None,
+            memory: vec![],
+        }
+    }
+
+    // The report of a check which has been cancelled in this phase, before any proof step has
+    // been checked.
+    pub(crate) fn cancelled(phase: Phase) -> Self {
+        CheckReport::new(Verdict::Cancelled { phase })
+    }
+
+    // The report of a proof rejected with this error, or the error if it does not reject the
+    // proof.
+    pub(crate) fn rejected(error: Error) -> Result<Self> {
+        CheckReport::rejection(&error).ok_or(error)
+    }
+
+    // The report of a proof rejected with this error, `None` if it does not reject the proof.
+    pub(crate) fn rejection(error: &Error) -> Option<Self> {
+        let verdict = error.verdict()?;
+        let rejected_lemma = match error {
+            Error::NotRedundant {
+                step,
+                line,
+                clause,
+                rup_only,
+                active_clauses,
+                rat_pivot,
+                needs_deleted,
+            } => Some(RejectedLemma {
+                step: *step,
+                line: *line,
+                clause: clause.clone(),
+                rup_only: *rup_only,
+                active_clauses: *active_clauses,
+                rat_pivot: *rat_pivot,
+                needs_deleted: *needs_deleted,
+            }),
+            _ => None,
+        };
+        Some(CheckReport {
+            reason: Some(error.to_string()),
+            rejected_lemma,
+            ..CheckReport::new(verdict)
+        })
+    }
+}
+
+/// The parts of a check, in the order they run in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Parsing,
+    Preprocessing,
+    Checking,
+}
+
+impl core::fmt::Display for Phase {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Phase::Parsing => write!(f, "parsing"),
+            Phase::Preprocessing => write!(f, "preprocessing"),
+            Phase::Checking => write!(f, "checking"),
+        }
+    }
+}
+
+/// The time spent in each part of a check. When streaming, parsing the proof is part of checking
+/// and `parsing` only covers the formula.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub parsing: Duration,
+    pub preprocessing: Duration,
+    pub checking: Duration,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Mode {
+    #[default]
+    Mutating,
+    Immutable,
+    Naive,
+    HeadTail,
+    Auto,
+}
+
+impl core::str::FromStr for Mode {
+    type Err = String;
+
+    /// Parse a mode by its name on the command line, ignoring case.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "mutating" => Ok(Mode::Mutating),
+            "immutable" => Ok(Mode::Immutable),
+            "naive" => Ok(Mode::Naive),
+            "head-tail" => Ok(Mode::HeadTail),
+            "auto" => Ok(Mode::Auto),
+            _ => Err(format!("invalid mode {}", name)),
+        }
+    }
+}
+
+/// What to do with the deletion steps of a proof.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeletionPolicy {
+    /// Remove deleted clauses from the set of active clauses.
+    #[default]
+    Apply,
+    /// Skip all deletion steps, so the set of active clauses only grows.
+    Ignore,
+}
+
+/// What to do with proof steps which add a clause that is already active. Duplicates are only
+/// found if they are not kept as separate clauses, see [`CheckerOptions::keep_duplicates`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum DuplicatePolicy {
+    /// Skip the step, it counts towards [`CheckerOptions::max_ignored`].
+    #[default]
+    Skip,
+    /// Check the lemma like any other proof step. The clause stays active once, but has to be
+    /// deleted as often as it has been added.
+    Check,
+}
+
+/// Which copy of a clause a deletion removes if several copies are active, which only happens
+/// with [`CheckerOptions::keep_duplicates`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum DeletionMatch {
+    /// The copy which has been added last.
+    #[default]
+    Newest,
+    /// The copy which has been added first.
+    Oldest,
+}
+
+/// The options of a check. Start from the defaults and change them with the builder methods,
+/// for example `CheckerOptions::default().mode(Mode::HeadTail).rup_only(true)`.
+#[derive(Debug, Clone, Default)]
+pub struct CheckerOptions {
+    pub(crate) rup_only: bool,
+    pub(crate) drup: bool,
+    pub(crate) progress: Progress,
+    pub(crate) check_all: bool,
+    pub(crate) implicit_empty: bool,
+    pub(crate) deletions: DeletionPolicy,
+    pub(crate) mode: Mode,
+    pub(crate) sample: Option<f64>,
+    pub(crate) seed: u64,
+    pub(crate) from: Option<usize>,
+    pub(crate) to: Option<usize>,
+    pub(crate) cross_check: Option<Mode>,
+    pub(crate) gc: Option<f64>,
+    pub(crate) stream: bool,
+    pub(crate) stats: bool,
+    pub(crate) profile: bool,
+    pub(crate) compact_variables: bool,
+    pub(crate) keep_duplicates: bool,
+    pub(crate) check_trivial: bool,
+    pub(crate) paranoid: bool,
+    pub(crate) restores: bool,
+    pub(crate) validate_lrat: bool,
+    pub(crate) duplicates: DuplicatePolicy,
+    pub(crate) deletion_match: DeletionMatch,
+    pub(crate) cancel: Option<CancellationToken>,
+    pub(crate) max_memory: Option<usize>,
+    pub(crate) max_ignored: Option<usize>,
+    pub(crate) max_clause_length: Option<usize>,
+    pub(crate) strict_deletions: bool,
+    pub(crate) proof_format: Option<Arc<dyn ProofParser>>,
+    pub(crate) propagator: Option<PropagatorFactory>,
+    pub(crate) comments_to_stderr: bool,
+    #[cfg(feature = "checkpoint")]
+    pub(crate) checkpoint: Option<(String, Duration)>,
+}
+
+impl CheckerOptions {
+    /// Only check lemmas for RUP, not for RAT.
+    pub fn rup_only(mut self, rup_only: bool) -> Self {
+        self.rup_only = rup_only;
+        self
+    }
+
+    /// Reject the proof if any lemma requires RAT instead of RUP.
+    pub fn drup(mut self, drup: bool) -> Self {
+        self.drup = drup;
+        self
+    }
+
+    /// Show a progress bar on the terminal with the phase of the check, and while checking the
+    /// rate of proof steps and the estimated time left. Options with a progress bar share it when
+    /// they are cloned.
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = if progress {
+            Progress::bar()
+        } else {
+            Progress::Hidden
+        };
+        self
+    }
+
+    /// Report the progress to this sink instead of the terminal.
+    pub fn progress_sink(mut self, sink: impl ProgressSink + 'static) -> Self {
+        self.progress = Progress::Sink(Arc::new(sink));
+        self
+    }
+
+    /// Keep checking the proof steps after the first conflict.
+    pub fn check_all(mut self, check_all: bool) -> Self {
+        self.check_all = check_all;
+        self
+    }
+
+    /// Accept proofs without the empty clause if it has RUP at the end of the proof.
+    pub fn implicit_empty(mut self, implicit_empty: bool) -> Self {
+        self.implicit_empty = implicit_empty;
+        self
+    }
+
+    pub fn deletions(mut self, deletions: DeletionPolicy) -> Self {
+        self.deletions = deletions;
+        self
+    }
+
+    /// The propagator used for checking. [`Mode::Auto`] chooses [`Mode::Naive`] if the number of
+    /// literals in the formula, without the lemmas of the proof, times the number of proof steps
+    /// is at most ten million and [`Mode::Mutating`] otherwise. The other propagators are never
+    /// chosen, and the average clause length only counts through the number of literals.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Only check each lemma addition with the given probability, using the given seed.
+    pub fn sample(mut self, probability: f64, seed: u64) -> Self {
+        self.sample = Some(probability);
+        self.seed = seed;
+        self
+    }
+
+    /// Only check the proof steps in this range, the other ones are applied unchecked. A proof
+    /// which is accepted gets [`Verdict::VerifiedPartially`].
+    pub fn steps(mut self, from: Option<usize>, to: Option<usize>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    /// Run a second propagator in lock step and fail at the first difference. Both are checked
+    /// with all the other options.
+    pub fn cross_check(mut self, mode: Mode) -> Self {
+        self.cross_check = Some(mode);
+        self
+    }
+
+    /// Compact the clause storage once this fraction of its literals is dead.
+    pub fn gc(mut self, fraction: f64) -> Self {
+        self.gc = Some(fraction);
+        self
+    }
+
+    /// Check the proof while reading it instead of loading it first.
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// Record the memory used by the parts of the checker in [`CheckReport::memory`]. The
+    /// timings are always recorded.
+    pub fn stats(mut self, stats: bool) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Record how long unit collection, propagation and the RUP and RAT checks take, see
+    /// [`CheckReport::profile`]. Looking at the clock this often slows down checking a bit.
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Rename the variables to 1..=n before checking.
+    pub fn compact_variables(mut self, compact_variables: bool) -> Self {
+        self.compact_variables = compact_variables;
+        self
+    }
+
+    /// Store duplicate clauses of the formula and proof as separate clauses instead of only once,
+    /// so that every deletion deletes one copy. This matches checkers which treat the formula as
+    /// a multiset. Not supported when trimming.
+    pub fn keep_duplicates(mut self, keep_duplicates: bool) -> Self {
+        self.keep_duplicates = keep_duplicates;
+        self
+    }
+
+    /// A formula which contains the empty clause is unsatisfiable without a proof, so it is
+    /// verified right away by default. With this set, the empty clause is dropped from the formula
+    /// instead and the proof is checked for the remaining clauses.
+    pub fn check_trivial(mut self, check_trivial: bool) -> Self {
+        self.check_trivial = check_trivial;
+        self
+    }
+
+    /// Check the invariants of the clause database, the assignment and the propagator after
+    /// every proof step, and fail with [`Error::BrokenInvariant`] once one does not hold. This is
+    /// slow and meant for debugging the checker, such as a new propagator, against a trusted one.
+    pub fn paranoid(mut self, paranoid: bool) -> Self {
+        self.paranoid = paranoid;
+        self
+    }
+
+    /// Remember the clauses deleted by the proof, so that a restore step adding one of them back
+    /// is applied without a check as long as every lemma before it has RUP. All clauses which
+    /// have ever been active are implied by the formula then. This keeps a copy of every deleted
+    /// clause. Otherwise restored clauses are checked like lemmas.
+    pub fn restores(mut self, restores: bool) -> Self {
+        self.restores = restores;
+        self
+    }
+
+    /// Check the LRAT written by [`convert::convert`] with its hints before returning it, and fail
+    /// with [`Error::InvalidLrat`] if it does not check. Costs about as much as writing the LRAT.
+    pub fn validate_lrat(mut self, validate: bool) -> Self {
+        self.validate_lrat = validate;
+        self
+    }
+
+    /// Whether lemmas adding a clause which is already active are skipped or checked.
+    pub fn duplicates(mut self, duplicates: DuplicatePolicy) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Which copy of a clause a deletion removes with [`CheckerOptions::keep_duplicates`].
+    pub fn deletion_match(mut self, deletion_match: DeletionMatch) -> Self {
+        self.deletion_match = deletion_match;
+        self
+    }
+
+    /// Stop checking once the token is cancelled.
+    pub fn cancel(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Stop once the clause storage, the propagator, the assignment and the deduplication map take
+    /// up more than this many bytes. The memory is estimated like for --stats and looked at after
+    /// preprocessing and every few thousand proof steps.
+    pub fn max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Fail with [`Error::TooManyIgnored`] once more than this many proof steps are skipped while
+    /// preprocessing, because they add clauses which are already active, delete clauses which are
+    /// not or delete the empty clause. With 0 every skipped step is an error instead of a warning.
+    pub fn max_ignored(mut self, steps: usize) -> Self {
+        self.max_ignored = Some(steps);
+        self
+    }
+
+    /// Fail with [`Error::ClauseTooLong`] at the first proof step with more literals than this,
+    /// before the clause is stored. A corrupted proof can contain a clause which never ends, this
+    /// stops it early instead of running out of memory.
+    pub fn max_clause_length(mut self, literals: usize) -> Self {
+        self.max_clause_length = Some(literals);
+        self
+    }
+
+    /// Fail with [`Error::MissingDeletion`] at the first proof step which deletes a clause that is
+    /// not active instead of skipping it. Such deletions usually mean that the solver and the
+    /// checker disagree about which clauses exist. Has no effect if deletions are ignored.
+    pub fn strict_deletions(mut self, strict: bool) -> Self {
+        self.strict_deletions = strict;
+        self
+    }
+
+    /// Check with the propagators of this factory instead of the one selected by the mode, for
+    /// example a [`Propagator`] loaded as a plugin. It is also used by --cross-check, which runs
+    /// it next to the mode given there.
+    pub fn propagator(mut self, factory: PropagatorFactory) -> Self {
+        self.propagator = Some(factory);
+        self
+    }
+
+    /// Read the proof in this format instead of DRAT, see [`parser::format::Registry`].
+    pub fn proof_format(mut self, parser: Arc<dyn ProofParser>) -> Self {
+        self.proof_format = Some(parser);
+        self
+    }
+
+    /// Print the comment lines of the checker, such as the step which derived the conflict, to
+    /// stderr instead of stdout.
+    pub fn comments_to_stderr(mut self, comments_to_stderr: bool) -> Self {
+        self.comments_to_stderr = comments_to_stderr;
+        self
+    }
+
+    /// Write a checkpoint to this path whenever the interval has passed, see [`resume`].
+    #[cfg(feature = "checkpoint")]
+    pub fn checkpoint(mut self, path: &str, interval: Duration) -> Self {
+        self.checkpoint = Some((path.to_string(), interval));
+        self
+    }
+}
+
+/// A proof step given as DIMACS literals, for proofs which are not read from a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ProofStep {
+    Add(Vec<i32>),
+    Delete(Vec<i32>),
+}
+
+/// Check a DRAT proof for the formula in DIMACS format at the given paths.
+#[cfg(feature = "fs")]
+pub fn check_drat(cnf: &str, proof: &str, options: CheckerOptions) -> Result<CheckReport, Error> {
+    check_files(cnf, proof, options, None)
+}
+
+/// Like [`check_drat`], but calls the observer around every proof step.
+#[cfg(feature = "fs")]
+pub fn check_drat_observed(
+    cnf: &str,
+    proof: &str,
+    options: CheckerOptions,
+    observer: &mut dyn Observer,
+) -> Result<CheckReport, Error> {
+    check_files(cnf, proof, options, Some(observer))
+}
+
+#[cfg(feature = "fs")]
+fn check_files(
+    cnf: &str,
+    proof: &str,
+    options: CheckerOptions,
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    options.validate()?;
+    options.progress.phase(Phase::Parsing);
+    let mut phase = Instant::now();
+
+    let cnf = std::fs::read_to_string(cnf)?;
+    if options.stream {
+        if options.cross_check.is_some() || options.compact_variables {
+            return Err(Error::InvalidOptions(
+                "--stream cannot be combined with --cross-check or --compact-variables".to_string(),
+            ));
+        }
+        let (_, formula) = parser::cnf::parse(&cnf)?;
+        let parsing = phase.elapsed();
+        phase = Instant::now();
+        let result = stream(options, formula, proof, observer);
+        let checking = phase.elapsed();
+        let mut report = result?;
+        report.timings.parsing = parsing;
+        report.timings.checking = checking;
+        return Ok(report);
+    }
+    let proof = std::fs::read_to_string(proof)?;
+    check_text(&cnf, &proof, options, observer, phase)
+}
+
+/// Continue a check from a checkpoint written with [`CheckerOptions::checkpoint`]. The formula
+/// and proof are part of the checkpoint, the options should be the same as for the original check.
+#[cfg(feature = "checkpoint")]
+pub fn resume(checkpoint: &str, mut options: CheckerOptions) -> Result<CheckReport, Error> {
+    options.validate()?;
+    if options.stream || options.cross_check.is_some() {
+        return Err(Error::InvalidOptions(
+            "resuming a checkpoint cannot be combined with --stream or --cross-check".to_string(),
+        ));
+    }
+    let phase = Instant::now();
+    let checkpoint = forward::Checkpoint::read(checkpoint)?;
+    options.mode = resolve_mode(
+        options.mode,
+        &checkpoint.clause_db,
+        &checkpoint.db_view,
+        checkpoint.proof.len(),
+    );
+    let propagator = options.propagator_factory();
+    let result = forward::resume(options, checkpoint, &propagator);
+    let checking = phase.elapsed();
+    let mut report = result?;
+    report.timings.checking = checking;
+    Ok(report)
+}
+
+/// Check a DRAT proof for a formula in DIMACS format, both given as text instead of paths.
+/// Streaming is not supported.
+pub fn check_str(cnf: &str, proof: &str, options: CheckerOptions) -> Result<CheckReport, Error> {
+    options.validate()?;
+    if options.stream {
+        return Err(Error::InvalidOptions(
+            "streaming requires the proof to be read from a file".to_string(),
+        ));
+    }
+    options.progress.phase(Phase::Parsing);
+    check_text(cnf, proof, options, None, Instant::now())
+}
+
+// Parse and check the formula and proof, the parsing time is measured from `started`.
+fn check_text(
+    cnf: &str,
+    proof: &str,
+    options: CheckerOptions,
+    observer: Option<&mut dyn Observer>,
+    started: Instant,
+) -> Result<CheckReport> {
+    let (_, formula) = parser::cnf::parse(cnf)?;
+    let (lemmas, origins) = parser::format::lemmas(&options, proof)?;
+    let parsing = started.elapsed();
+    let mut report = if options.is_cancelled() {
+        CheckReport::cancelled(Phase::Parsing)
+    } else {
+        check(formula, lemmas, origins, options, observer)?
+    };
+    report.timings.parsing = parsing;
+    Ok(report)
+}
+
+/// Check a proof for a formula which are both given as clauses of DIMACS literals, without
+/// reading any files. Streaming is not supported.
+pub fn check_clauses(
+    formula: impl IntoIterator<Item = Vec<i32>>,
+    proof: impl IntoIterator<Item = ProofStep>,
+    options: CheckerOptions,
+) -> Result<CheckReport, Error> {
+    check_in_memory(formula, proof, options, None)
+}
+
+/// Like [`check_clauses`], but calls the observer around every proof step.
+pub fn check_clauses_observed(
+    formula: impl IntoIterator<Item = Vec<i32>>,
+    proof: impl IntoIterator<Item = ProofStep>,
+    options: CheckerOptions,
+    observer: &mut dyn Observer,
+) -> Result<CheckReport, Error> {
+    check_in_memory(formula, proof, options, Some(observer))
+}
+
+fn check_in_memory(
+    formula: impl IntoIterator<Item = Vec<i32>>,
+    proof: impl IntoIterator<Item = ProofStep>,
+    options: CheckerOptions,
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    options.validate()?;
+    if options.stream {
+        return Err(Error::InvalidOptions(
+            "streaming requires the proof to be read from a file".to_string(),
+        ));
+    }
+    // the line of an invalid clause is its position in the formula or proof
+    let invalid = |i: usize| Error::Parse {
+        line: i + 1,
+        message: "clause contains the literal 0 or one out of range".to_string(),
+    };
+    let formula = formula
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| parser::normalize_clause(c).ok_or_else(|| invalid(i)))
+        .collect::<Result<Vec<_>>>()?;
+    let lemmas = proof
+        .into_iter()
+        .enumerate()
+        .map(|(i, step)| {
+            match step {
+                ProofStep::Add(c) => parser::normalize_clause(c).map(RawLemma::Add),
+                ProofStep::Delete(c) => parser::normalize_clause(c).map(RawLemma::Del),
+            }
+            .ok_or_else(|| invalid(i))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    check(formula, lemmas, Origins::positions(), options, observer)
+}
+
+impl CheckerOptions {
+    fn validate(&self) -> Result<()> {
+        if let Some(p) = self.sample {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(Error::InvalidOptions(format!(
+                    "sample probability must be between 0 and 1, got {}",
+                    p
+                )));
+            }
+        }
+        if let Some(fraction) = self.gc {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(Error::InvalidOptions(format!(
+                    "gc threshold must be between 0 and 1, got {}",
+                    fraction
+                )));
+            }
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(Error::InvalidOptions(format!(
+                    "invalid step range {}..{}",
+                    from, to
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // The factory of the propagator, the mode has to be resolved before.
+    fn propagator_factory(&self) -> PropagatorFactory {
+        match &self.propagator {
+            Some(factory) => factory.clone(),
+            None => mode_propagator(self.mode),
+        }
+    }
+
+    fn proof_parser(&self) -> &dyn ProofParser {
+        match &self.proof_format {
+            Some(parser) => parser.as_ref(),
+            None => &parser::format::Drat,
+        }
+    }
+
+    fn exceeds_memory(&self, bytes: usize) -> bool {
+        self.max_memory.is_some_and(|max| bytes > max)
+    }
+
+    fn check_ignored(&self, ignored: usize) -> Result<()> {
+        match self.max_ignored {
+            Some(max) if ignored > max => Err(Error::TooManyIgnored { ignored, max }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_clause_length(&self, step: usize, line: Option<usize>, length: usize) -> Result<()> {
+        match self.max_clause_length {
+            Some(max) if length > max => Err(Error::ClauseTooLong {
+                step,
+                line,
+                length,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // The deleted clause is given in DIMACS literals.
+    fn check_deletions(&self, missing: Option<(usize, Vec<i32>)>, origins: &Origins) -> Result<()> {
+        match missing {
+            Some((step, clause)) if self.strict_deletions => Err(Error::MissingDeletion {
+                step,
+                line: origins.input_line(step),
+                clause,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    }
+
+    // the verdict if the check succeeds
+    fn verdict(&self) -> Verdict {
+        if self.from.is_some() || self.to.is_some() {
+            Verdict::VerifiedPartially
+        } else if self.sample.is_some() {
+            Verdict::VerifiedProbabilistic
+        } else {
+            Verdict::Verified
+        }
+    }
+}
+
+/// A formula and proof which have been parsed and deduplicated, see [`preprocess_str`]. With the
+/// `serde` feature it can be stored and checked again with [`check_preprocessed`] without parsing
+/// the input again. Deletions are ignored and variables are renamed during preprocessing already,
+/// so these options have no effect when checking it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Preprocessed {
+    clause_db: ClauseStorage,
+    db_view: View,
+    proof: Vec<Lemma>,
+    origins: Origins,
+    // the first deletion of a clause which is not active, see `CheckerOptions::strict_deletions`
+    missing_deletion: Option<(usize, Vec<i32>)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: PreprocessStats,
+}
+
+impl Preprocessed {
+    /// What preprocessing found, not kept when serialized.
+    pub fn stats(&self) -> PreprocessStats {
+        self.stats
+    }
+
+    /// The proof steps of the input which preprocessing has skipped, counted from 0.
+    pub fn skipped(&self) -> &[usize] {
+        self.origins.skipped()
+    }
+}
+
+/// The size of a formula and proof and the lemmas dropped while deduplicating them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PreprocessStats {
+    pub formula_clauses: usize,
+    /// The formula clauses without duplicates, which are only stored once. All clauses of the
+    /// formula with [`CheckerOptions::keep_duplicates`].
+    pub distinct_formula_clauses: usize,
+    pub additions: usize,
+    pub deletions: usize,
+    /// Additions of clauses which are already active, they are skipped.
+    pub duplicate_additions: usize,
+    /// Additions of clauses which are already active which are checked with
+    /// [`DuplicatePolicy::Check`] instead of being skipped.
+    pub checked_duplicates: usize,
+    /// Deletions of clauses which are not active, they are skipped.
+    pub missing_deletions: usize,
+    /// Deletions of one of several copies of a clause, which stays active.
+    pub duplicate_deletions: usize,
+    /// Deletions of the empty clause, they are skipped. Once the empty clause has been added or
+    /// is part of the formula the proof is verified, deleting it does not change that.
+    pub empty_deletions: usize,
+    /// Clauses of the formula and lemma additions which contain a literal and its negation, see
+    /// [`parser::is_tautology`]. They are checked and stored like any other clause.
+    pub tautologies: usize,
+    /// Restore steps, which are counted as additions as well.
+    pub restores: usize,
+    /// The largest variable of the formula and proof, before --compact-variables renames them.
+    pub max_variable: u32,
+}
+
+impl PreprocessStats {
+    /// The proof steps which have been skipped, see [`CheckerOptions::max_ignored`].
+    pub fn ignored(&self) -> usize {
+        self.duplicate_additions
+            + self.missing_deletions
+            + self.duplicate_deletions
+            + self.empty_deletions
+    }
+}
+
+/// What [`parse_drat`] found in a formula and proof which have been parsed and preprocessed
+/// without checking the proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseReport {
+    pub header: parser::cnf::Header,
+    pub stats: PreprocessStats,
+    /// Problems of the input which do not stop it from being checked, such as a header which does
+    /// not match the formula or a proof without the empty clause.
+    pub issues: Vec<String>,
+    /// The time spent parsing and preprocessing.
+    pub timings: Timings,
+}
+
+/// Parse and deduplicate a proof for a formula in DIMACS format without checking it. How
+/// duplicates and deletions are handled is selected with [`CheckerOptions::deletions`],
+/// [`CheckerOptions::keep_duplicates`], [`CheckerOptions::duplicates`] and
+/// [`CheckerOptions::deletion_match`], what has been changed is in [`Preprocessed::stats`] and
+/// [`Preprocessed::skipped`].
+pub fn preprocess_str(cnf: &str, proof: &str, options: &CheckerOptions) -> Result<Preprocessed> {
+    let (_, formula) = parser::cnf::parse(cnf)?;
+    let (lemmas, origins) = parser::format::lemmas(options, proof)?;
+    Ok(preprocess_lemmas(formula, lemmas, origins, options).0)
+}
+
+/// Parse and preprocess a DRAT proof for a formula in DIMACS format without checking it, to
+/// validate the input or see its size. The options select the proof format and whether deletions
+/// are ignored.
+#[cfg(feature = "fs")]
+pub fn parse_drat(cnf: &str, proof: &str, options: &CheckerOptions) -> Result<ParseReport, Error> {
+    let started = Instant::now();
+    let cnf = std::fs::read_to_string(cnf)?;
+    let proof = std::fs::read_to_string(proof)?;
+    parse_text(&cnf, &proof, options, started)
+}
+
+/// Like [`parse_drat`], with the formula and proof given as text instead of paths.
+pub fn parse_str(cnf: &str, proof: &str, options: &CheckerOptions) -> Result<ParseReport, Error> {
+    parse_text(cnf, proof, options, Instant::now())
+}
+
+/// The verdicts of [`audit_dedup`] with and without deduplication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupAudit {
+    /// The check with equal clauses stored once.
+    pub deduplicated: AuditRun,
+    /// The check with every copy of a clause stored separately, see
+    /// [`CheckerOptions::keep_duplicates`].
+    pub kept: AuditRun,
+}
+
+impl DedupAudit {
+    /// Whether both checks reached the same verdict.
+    pub fn agrees(&self) -> bool {
+        self.deduplicated.report.verdict == self.kept.report.verdict
+    }
+}
+
+/// One of the checks of [`audit_dedup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRun {
+    pub report: CheckReport,
+    pub stats: PreprocessStats,
+    /// The clauses in the clause database. Without deduplication every clause of the formula and
+    /// every addition is stored, with it copies share a single clause.
+    pub clauses: usize,
+    /// The literals of the clauses in the clause database.
+    pub literals: usize,
+}
+
+/// Check a formula and proof given as text twice, once with duplicate clauses deduplicated and
+/// once with them kept as separate clauses, to find where deduplication changes the verdict.
+/// Meant for moderate inputs, both checks are done one after the other.
+pub fn audit_dedup(cnf: &str, proof: &str, options: CheckerOptions) -> Result<DedupAudit> {
+    options.validate()?;
+    if options.stream {
+        return Err(Error::InvalidOptions(
+            "streaming requires the proof to be read from a file".to_string(),
+        ));
+    }
+    let run = |keep| -> Result<AuditRun> {
+        let options = options.clone().keep_duplicates(keep);
+        let preprocessed = preprocess_str(cnf, proof, &options)?;
+        let stats = preprocessed.stats;
+        let clauses = preprocessed.clause_db.number_of_clauses();
+        let literals = preprocessed.clause_db.number_of_literals();
+        Ok(AuditRun {
+            report: check_lemmas(preprocessed, options, None)?,
+            stats,
+            clauses,
+            literals,
+        })
+    };
+    Ok(DedupAudit {
+        deduplicated: run(false)?,
+        kept: run(true)?,
+    })
+}
+
+// Parse and preprocess the formula and proof, the parsing time is measured from `started`.
+fn parse_text(
+    cnf: &str,
+    proof: &str,
+    options: &CheckerOptions,
+    started: Instant,
+) -> Result<ParseReport> {
+    let (header, formula) = parser::cnf::parse(cnf)?;
+    let (lemmas, origins) = parser::format::lemmas(options, proof)?;
+    let parsing = started.elapsed();
+    let mut issues = vec![];
+    if formula.len() != header.clauses {
+        issues.push(format!(
+            "the header declares {} clauses but the formula has {}",
+            header.clauses,
+            formula.len()
+        ));
+    }
+    let formula_variable = formula.iter().flatten().map(|lit| lit.var()).max();
+    if let Some(var) = formula_variable.filter(|&var| var as usize > header.vars) {
+        issues.push(format!(
+            "the header declares {} variables but the formula contains variable {}",
+            header.vars, var
+        ));
+    }
+    if !lemmas
+        .iter()
+        .any(|lemma| matches!(lemma, RawLemma::Add(c) if c.is_empty()))
+    {
+        issues.push("the proof does not add the empty clause".to_string());
+    }
+    let (preprocessed, preprocessing, _) = preprocess_lemmas(formula, lemmas, origins, options);
+    Ok(ParseReport {
+        header,
+        stats: preprocessed.stats,
+        issues,
+        timings: Timings {
+            parsing,
+            preprocessing,
+            checking: Duration::ZERO,
+        },
+    })
+}
+
+/// Check a formula and proof which have been preprocessed before.
+pub fn check_preprocessed(
+    preprocessed: Preprocessed,
+    options: CheckerOptions,
+) -> Result<CheckReport, Error> {
+    options.validate()?;
+    check_lemmas(preprocessed, options, None)
+}
+
+/// Like [`check_preprocessed`], but propagates with `P` instead of the propagator selected by the
+/// mode, see [`CheckerOptions::propagator`]. Together with [`CheckerOptions::cross_check`] a
+/// custom propagator can be compared with the built-in ones.
+pub fn check_preprocessed_with<P: Propagator + 'static>(
+    preprocessed: Preprocessed,
+    options: CheckerOptions,
+) -> Result<CheckReport, Error> {
+    options.validate()?;
+    check_lemmas(
+        preprocessed,
+        options.propagator(PropagatorFactory::of::<P>()),
+        None,
+    )
+}
+
+// Check the parsed formula and proof.
+fn check(
+    formula: Vec<Vec<Literal>>,
+    lemmas: Vec<RawLemma>,
+    origins: Origins,
+    options: CheckerOptions,
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    options.progress.phase(Phase::Preprocessing);
+    let (preprocessed, preprocessing, memory) =
+        preprocess_lemmas(formula, lemmas, origins, &options);
+    let used = memory.iter().map(|(_, bytes)| bytes).sum::<usize>()
+        + preprocessed.clause_db.heap_size()
+        + preprocessed.db_view.heap_size();
+    let stats = options.stats;
+    let mut report = if options.is_cancelled() {
+        CheckReport::cancelled(Phase::Preprocessing)
+    } else if options.exceeds_memory(used) {
+        CheckReport::new(Verdict::MemoryLimitExceeded {
+            phase: Phase::Preprocessing,
+        })
+    } else {
+        check_lemmas(preprocessed, options, observer)?
+    };
+    report.timings.preprocessing = preprocessing;
+    if stats {
+        report.memory.splice(0..0, memory);
+    }
+    Ok(report)
+}
+
+// Rename the variables if requested and convert the proof to lemmas of the clause storage, noting
+// the skipped lemmas in the origins. Returns the time this took and the bytes used by the proof
+// and the deduplication map, which is dropped at the end.
+fn preprocess_lemmas(
+    mut formula: Vec<Vec<Literal>>,
+    mut lemmas: Vec<RawLemma>,
+    mut origins: Origins,
+    options: &CheckerOptions,
+) -> (Preprocessed, Duration, Vec<(&'static str, usize)>) {
+    let phase = Instant::now();
+    let max_variable = formula
+        .iter()
+        .chain(lemmas.iter().map(|lemma| match lemma {
+            RawLemma::Add(c) | RawLemma::Del(c) | RawLemma::Restore(c) => c,
+        }))
+        .flatten()
+        .map(|lit| lit.var())
+        .max()
+        .unwrap_or(0);
+    let mut external_names = None;
+    if options.compact_variables {
+        let renaming =
+            Renaming::new(formula.iter().chain(lemmas.iter().map(|lemma| match lemma {
+                RawLemma::Add(c) | RawLemma::Del(c) | RawLemma::Restore(c) => c,
+            })));
+        formula = formula.into_iter().map(|c| renaming.rename(c)).collect();
+        lemmas = lemmas
+            .into_iter()
+            .map(|lemma| match lemma {
+                RawLemma::Add(c) => RawLemma::Add(renaming.rename(c)),
+                RawLemma::Del(c) => RawLemma::Del(renaming.rename(c)),
+                RawLemma::Restore(c) => RawLemma::Restore(renaming.rename(c)),
+            })
+            .collect();
+        external_names = Some(renaming.into_external());
+    }
+
+    // reserve space for all clauses of the formula and proof up front
+    let literals = formula.iter().map(|c| c.len()).sum::<usize>()
+        + lemmas
+            .iter()
+            .map(|lemma| match lemma {
+                RawLemma::Add(c) | RawLemma::Del(c) | RawLemma::Restore(c) => c.len(),
+            })
+            .sum::<usize>();
+    let mut db_builder = storage::Builder::with_capacity(formula.len() + lemmas.len(), literals);
+
+    let preprocessor = Preprocessor::new(options);
+    let (proof, preprocessor, formula_clauses) =
+        preprocess(formula, lemmas, &mut origins, &mut db_builder, preprocessor);
+    let stats = PreprocessStats {
+        distinct_formula_clauses: formula_clauses,
+        max_variable,
+        ..preprocessor.stats()
+    };
+    let preprocessing = phase.elapsed();
+    let memory = vec![
+        (
+            "dedup map",
+            db_builder.interner().heap_size() + preprocessor.heap_size(),
+        ),
+        ("proof", stats::vec_bytes(&proof)),
+    ];
+    let missing_deletion = preprocessor
+        .missing_deletion()
+        .map(|(step, clause)| (step, clause.to_vec()));
+    drop(preprocessor);
+    let mut clause_db = db_builder.finish();
+    if let Some(names) = external_names {
+        clause_db.set_external_names(names);
+    }
+    let missing_deletion = missing_deletion.map(|(step, clause)| {
+        let clause = clause.into_iter().map(|lit| clause_db.external(lit));
+        (step, clause.collect())
+    });
+
+    // mark the formula clauses as active
+    let db_view = clause_db.partial_view(formula_clauses);
+    (
+        Preprocessed {
+            clause_db,
+            db_view,
+            proof,
+            origins,
+            missing_deletion,
+            stats,
+        },
+        preprocessing,
+        memory,
+    )
+}
+
+// Check the preprocessed formula and proof. Returns the report with the checking time.
+fn check_lemmas(
+    preprocessed: Preprocessed,
+    mut options: CheckerOptions,
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    let Preprocessed {
+        clause_db,
+        db_view,
+        proof,
+        origins,
+        missing_deletion,
+        stats,
+    } = preprocessed;
+    options.check_ignored(stats.ignored())?;
+    options.check_deletions(missing_deletion, &origins)?;
+    let phase = Instant::now();
+    options.mode = resolve_mode(options.mode, &clause_db, &db_view, proof.len());
+    options.cross_check = options
+        .cross_check
+        .map(|mode| resolve_mode(mode, &clause_db, &db_view, proof.len()));
+
+    let propagator = options.propagator_factory();
+    let result = match options.cross_check {
+        Some(other) => forward::cross_check(
+            options,
+            clause_db,
+            db_view,
+            proof,
+            &origins,
+            (&propagator, &mode_propagator(other)),
+            observer,
+        ),
+        None => forward::Checker::new(options, clause_db, db_view, &propagator)
+            .validate(proof, origins, observer),
+    };
+    let checking = phase.elapsed();
+    let mut report = result?;
+    report.timings.checking = checking;
+    Ok(report)
+}
+
+// Check the proof while reading it. The file is read twice, once to find the bounds of the proof.
+#[cfg(feature = "fs")]
+fn stream(
+    options: CheckerOptions,
+    formula: Vec<Vec<Literal>>,
+    path: &str,
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    let (proof_len, bounds) = scan_proof(options.proof_parser(), path)?;
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let proof = options.proof_parser().stream(Box::new(file));
+    stream_lemmas(options, formula, proof, proof_len, bounds, observer)
+}
+
+/// Upper bounds of a proof which is checked while it is read, used to reserve the clause storage
+/// up front. Lemmas adding more clauses are rejected with [`Error::OutOfBounds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofBounds {
+    /// The number of lemma additions, including restore steps.
+    pub additions: usize,
+    /// The largest variable in the proof which space is reserved for. Lemmas with larger
+    /// variables are still checked, but grow the arrays indexed by literals while checking.
+    pub max_variable: u32,
+}
+
+// Check the lemmas while they are read. The formula is stored up front, the storage is reserved
+// for the clauses of the proof which are only added during the check. The proof length is only
+// used for the progress and to pick the mode.
+#[cfg(any(feature = "fs", feature = "async"))]
+pub(crate) fn stream_lemmas(
+    mut options: CheckerOptions,
+    formula: Vec<Vec<Literal>>,
+    proof: impl Iterator<Item = Result<parser::format::ParsedStep>>,
+    proof_len: usize,
+    bounds: ProofBounds,
+    observer: Option<&mut dyn Observer>,
+) -> Result<CheckReport> {
+    let (clause_db, db_view, interner, preprocessor) =
+        reserve_proof(&mut options, formula, proof_len, bounds);
+    let propagator = options.propagator_factory();
+    forward::Checker::new(options, clause_db, db_view, &propagator).validate_stream(
+        interner,
+        preprocessor,
+        proof,
+        proof_len,
+        observer,
+    )
+}
+
+// Set up a check which is fed one proof step at a time, see `OnlineChecker`.
+pub(crate) fn online_check(
+    mut options: CheckerOptions,
+    formula: Vec<Vec<Literal>>,
+    bounds: ProofBounds,
+) -> forward::Online {
+    let (clause_db, db_view, interner, preprocessor) =
+        reserve_proof(&mut options, formula, bounds.additions, bounds);
+    let propagator = options.propagator_factory();
+    forward::Checker::new(options, clause_db, db_view, &propagator).online(interner, preprocessor)
+}
+
+// Store the formula and reserve the storage for the clauses of a proof which is not known up
+// front. Resolves the mode of the options.
+fn reserve_proof(
+    options: &mut CheckerOptions,
+    formula: Vec<Vec<Literal>>,
+    proof_len: usize,
+    bounds: ProofBounds,
+) -> (ClauseStorage, View, storage::Interner, Preprocessor) {
+    let literals = formula.iter().map(|c| c.len()).sum::<usize>();
+    let mut db_builder =
+        storage::Builder::with_capacity(formula.len() + bounds.additions, literals);
+    let mut preprocessor = Preprocessor::new(options);
+    for c in formula {
+        preprocessor.formula_clause(&mut db_builder, &c);
+    }
+    let (mut clause_db, interner) = db_builder.finish_with_interner();
+    let formula_clauses = clause_db.number_of_clauses();
+    clause_db.reserve(formula_clauses + bounds.additions, bounds.max_variable);
+    let db_view = clause_db.partial_view(formula_clauses);
+    options.mode = resolve_mode(options.mode, &clause_db, &db_view, proof_len);
+    (clause_db, db_view, interner, preprocessor)
+}
+
+// Read through the proof without keeping it. Returns the number of proof steps and the bounds of
+// the proof.
+#[cfg(feature = "fs")]
+fn scan_proof(parser: &dyn ProofParser, path: &str) -> Result<(usize, ProofBounds)> {
+    let mut steps = 0;
+    let mut additions = 0;
+    let mut max_variable = 0;
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    for step in parser.stream(Box::new(file)) {
+        let clause = match step?.lemma {
+            RawLemma::Add(c) | RawLemma::Restore(c) => {
+                additions += 1;
+                c
+            }
+            RawLemma::Del(c) => c,
+        };
+        steps += 1;
+        max_variable = clause
+            .iter()
+            .map(|lit| lit.var())
+            .fold(max_variable, u32::max);
+    }
+    Ok((
+        steps,
+        ProofBounds {
+            additions,
+            max_variable,
+        },
+    ))
+}
+
+// The naive propagator goes through all clauses for every propagation, so it is only used if the
+// number of formula literals times the number of proof steps is small. Otherwise the mutating
+// propagator is used. Immutable and head/tail are only used when asked for. The average clause
+// length is not used on its own, the number of literals is already the number of clauses times
+// the average length, which is what a naive propagation costs.
+const NAIVE_WORK_LIMIT: usize = 10_000_000;
+
+// The formula is given by the clauses active in the view, for a checkpoint those which were
+// active when it was written.
+fn resolve_mode(mode: Mode, clause_db: &ClauseStorage, db_view: &View, proof_len: usize) -> Mode {
+    if mode != Mode::Auto {
+        return mode;
+    }
+    let literals = clause_db
+        .clauses(db_view)
+        .map(|clause| clause_db.clause(clause).len())
+        .sum::<usize>();
+    let work = literals.saturating_mul(proof_len.max(1));
+    let mode = if work <= NAIVE_WORK_LIMIT {
+        Mode::Naive
+    } else {
+        Mode::Mutating
+    };
+    tracing::info!(
+        "selected {:?} mode for {} formula literals and {} proof steps",
+        mode,
+        literals,
+        proof_len
+    );
+    mode
+}
+
+// The factory of the built-in propagator of a mode other than auto.
+fn mode_propagator(mode: Mode) -> PropagatorFactory {
+    match mode {
+        Mode::Mutating => PropagatorFactory::of::<forward::MutatingPropagator>(),
+        Mode::Immutable => PropagatorFactory::of::<forward::ConstPropagator>(),
+        Mode::Naive => PropagatorFactory::of::<forward::NaivePropagator>(),
+        Mode::HeadTail => PropagatorFactory::of::<forward::HeadTailPropagator>(),
+        Mode::Auto => unreachable!("auto mode is resolved before checking"),
+    }
+}
+
+// Adds all the clauses from the original formula and the proof to the builder. The lemmas of the
+// proof are converted to lemmas containing clause references by the preprocessor and returned
+// together with it and the number of distinct formula clauses, which come first in the storage.
+fn preprocess(
+    formula: Vec<Vec<Literal>>,
+    proof: Vec<RawLemma>,
+    origins: &mut Origins,
+    builder: &mut Builder,
+    mut preprocessor: Preprocessor,
+) -> (Vec<Lemma>, Preprocessor, usize) {
+    for c in formula {
+        preprocessor.formula_clause(builder, &c);
+    }
+    // duplicate clauses of the formula are only stored once unless they are kept
+    let formula_clauses = builder.parts_mut().1.number_of_clauses();
+
+    let lemmas = proof
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, raw_lemma)| {
+            let (interner, clause_db) = builder.parts_mut();
+            let lemma =
+                preprocessor.lemma(i, origins.input_line(i), raw_lemma, interner, clause_db);
+            if lemma.is_none() {
+                origins.skip(i);
+            }
+            lemma
+        })
+        .collect_vec();
+    (lemmas, preprocessor, formula_clauses)
+}
@@ -1,143 +1,858 @@
-mod common;
-mod forward;
-
-mod parser;
-
-use std::collections::BTreeSet;
+use std::{path::PathBuf, process::ExitCode, thread, time::Duration};
 
 use anyhow::Result;
-use clap::Parser;
-use common::storage::{Builder, ClauseStorage, View};
-use fxhash::FxHashMap;
+use clap::{FromArgMatches, Parser};
 use itertools::Itertools;
+use ratify::{
+    audit_dedup, check_drat,
+    convert::{self, Format},
+    parse_drat,
+    parser::format::Registry,
+    trim_drat, CancellationToken, CheckReport, CheckerOptions, DedupAudit, DeletionMatch,
+    DeletionPolicy, DuplicatePolicy, Error, Mode, ModelReport, ParseReport, Phase, ProofStep,
+    TrimStrategy, Verdict,
+};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
-use crate::common::{
-    storage::{self, Clause},
-    Lemma, Literal, RawLemma,
-};
+use crate::{diagnostic::Target, output::Artifact};
 
-#[derive(clap::ValueEnum, Clone, Debug)]
-enum Mode {
-    Mutating,
-    Immutable,
-    Naive,
-}
+mod config;
+mod diagnostic;
+mod differential;
+mod output;
+mod selftest;
+mod serve;
+mod status;
+#[cfg(feature = "tui")]
+mod tui;
+mod watch;
 
-#[derive(Parser, Debug)]
-pub struct Flags {
+#[derive(clap::Args, Debug, Clone)]
+struct Flags {
     #[arg(short, long)]
     /// Only check lemmas for the RUP property instead of RAT if the RUP check fails.
     rup_only: bool,
+    #[arg(long)]
+    /// Reject the proof if any lemma requires RAT instead of RUP and list these lemmas together
+    /// with their pivot.
+    drup: bool,
     #[arg(short, long)]
     /// Show the progress bar during verification to indicate how many proof steps have been
     /// processed.
     progress: bool,
     #[arg(long)]
+    /// Keep verifying the remaining proof steps after a conflict has been derived instead of
+    /// stopping at the first one.
+    check_all: bool,
+    #[arg(long)]
+    /// Accept proofs which do not contain the empty clause if unit propagation on the final
+    /// clause set yields a conflict, i.e. the empty clause is implied by RUP.
+    implicit_empty: bool,
+    #[arg(long)]
     /// Skip all deletion steps in a proof.
     ignore_deletions: bool,
     #[arg(short, long, value_enum, default_value_t = Mode::Mutating)]
-    /// The type of propagator that should be used. Options are Mutating, Immutable and Naive.
-    /// Mutating will modify the underlying clause storage for efficiency while the immutable
-    /// version keeps it in tact and has a more complex structure. Naive does not make use of
-    /// watchlists and is thus very slow.
+    /// The type of propagator that should be used. Options are Mutating, Immutable, Naive and
+    /// HeadTail. Mutating will modify the underlying clause storage for efficiency while the
+    /// immutable version keeps it in tact and has a more complex structure. Naive does not make
+    /// use of watchlists and is thus very slow. HeadTail uses classical head/tail lists instead of
+    /// two watched literals. Auto chooses Naive if the number of literals in the formula, without
+    /// the lemmas, times the number of proof steps is at most ten million and Mutating otherwise.
     mode: Mode,
+    #[arg(long, value_name = "P")]
+    /// Only fully verify a random subset of lemma additions, each one being checked with
+    /// probability P. All additions and deletions are still applied to the clause database. The
+    /// resulting verdict is only probabilistic.
+    sample: Option<f64>,
+    #[arg(long, default_value_t = 0)]
+    /// Seed for the random number generator used by --sample.
+    seed: u64,
+    #[arg(long, value_name = "STEP")]
+    /// Only verify proof steps starting at this step. Earlier steps are applied to the clause
+    /// database without being checked, so an accepted proof is only PARTIALLY VERIFIED and exits
+    /// with code 8.
+    from: Option<usize>,
+    #[arg(long, value_name = "STEP")]
+    /// Only verify proof steps up to and including this step. Later steps are applied to the
+    /// clause database without being checked, like with --from.
+    to: Option<usize>,
+    #[arg(long, value_enum, value_name = "MODE")]
+    /// Run the propagator selected by --mode and this one in lock step and report the first step
+    /// at which their assignments or verdicts diverge.
+    cross_check: Option<Mode>,
+    #[arg(long, value_name = "FRACTION")]
+    /// Compact the clause storage once this fraction of its literals belongs to clauses which
+    /// have been deleted and are not used by the proof anymore.
+    gc: Option<f64>,
+    #[arg(long)]
+    /// Check the proof while it is being read instead of loading it into memory first. The proof
+    /// file is read twice, once to size the clause storage and once to check it. Deleted clauses
+    /// are dropped from memory, see --gc.
+    stream: bool,
+    #[arg(long)]
+    /// Print the time spent parsing and checking, the approximate memory used by the clause
+    /// storage, propagator, assignment and deduplication and the peak resident set size to
+    /// stderr, or to the file given with --stats-out or --out-dir.
+    stats: bool,
+    #[arg(long)]
+    /// Rename the variables to 1..=n before checking, so that memory scales with the number of
+    /// distinct variables instead of the largest variable id.
+    compact_variables: bool,
+    #[arg(long)]
+    /// Keep duplicate clauses of the formula and proof as separate clauses, like checkers which
+    /// treat the formula as a multiset, so that every deletion deletes one copy. By default a
+    /// clause is only stored once and deleted once all its copies are deleted. Not supported by
+    /// trim.
+    no_dedup: bool,
+    #[arg(long)]
+    /// Check the proof even if the formula contains the empty clause, as if the formula did not
+    /// contain it. By default such a formula is verified right away, since it is unsatisfiable
+    /// without a proof.
+    check_trivial: bool,
+    #[arg(long)]
+    /// Check the invariants of the clause database, assignment and propagator after every proof
+    /// step and fail with exit code 6 once one does not hold. This is slow and meant for debugging
+    /// ratify itself.
+    paranoid: bool,
+    #[arg(long)]
+    /// Keep a copy of every clause deleted by the proof, so that restore steps `r <clause> 0`
+    /// adding one of them back are applied without a check until the first lemma which needs
+    /// RAT. Otherwise restored clauses are checked like lemmas.
+    restores: bool,
+    #[arg(long, value_enum, default_value_t = DuplicatePolicy::Skip)]
+    /// Whether lemmas adding a clause which is already active are skipped or checked again. A
+    /// checked duplicate has to be deleted as often as it is added, like a skipped one.
+    duplicates: DuplicatePolicy,
+    #[arg(long, value_enum, default_value_t = DeletionMatch::Newest)]
+    /// Which copy of a clause a deletion removes with --no-dedup, the one added last or the one
+    /// added first.
+    deletion_match: DeletionMatch,
+    #[arg(long, value_name = "MB")]
+    /// Stop with exit code 7 once the clause storage, propagator, assignment and deduplication
+    /// map take up more than this many megabytes, instead of being killed when the system runs
+    /// out of memory. The memory is estimated like for --stats.
+    max_memory: Option<usize>,
+    #[arg(long, conflicts_with = "max_ignored")]
+    /// Reject the proof with exit code 1 if any proof step is ignored while preprocessing because
+    /// it adds a clause which is already active, deletes one which is not or deletes the empty
+    /// clause.
+    deny_warnings: bool,
+    #[arg(long, value_name = "N")]
+    /// Reject the proof with exit code 1 if more than N proof steps are ignored while
+    /// preprocessing, see --deny-warnings.
+    max_ignored: Option<usize>,
+    #[arg(long, value_name = "N")]
+    /// Stop with exit code 3 at the first proof step whose clause has more than N literals, such
+    /// as a clause of a corrupted proof which never ends.
+    max_clause_length: Option<usize>,
+    #[arg(long, conflicts_with = "ignore_deletions")]
+    /// Reject the proof with exit code 1 at the first proof step which deletes a clause that is
+    /// not active, instead of ignoring it. This usually means that the solver and ratify disagree
+    /// about which clauses exist.
+    strict_deletions: bool,
+    #[arg(long, value_enum, default_value_t = Target::Stdout)]
+    /// Where to print the comment lines explaining the verdict, such as why a proof has been
+    /// rejected. With stderr, stdout only has the `s` line.
+    diagnostics: Target,
+    #[arg(long, value_name = "FORMAT", default_value = "drat")]
+    /// The format of the proof. Only DRAT is built in, other formats are registered by programs
+    /// using ratify as a library.
+    format: String,
+    #[cfg(feature = "checkpoint")]
+    #[arg(long, value_name = "PATH")]
+    /// Periodically write the state of the check to this file, so that it can be continued with
+    /// --resume if it is interrupted. Not supported with --stream and --cross-check.
+    checkpoint: Option<String>,
+    #[cfg(feature = "checkpoint")]
+    #[arg(long, value_name = "SECONDS", default_value_t = 600)]
+    /// How often to write a checkpoint.
+    checkpoint_interval: u64,
+    #[cfg(feature = "checkpoint")]
+    #[arg(long, value_name = "PATH", conflicts_with = "stream")]
+    /// Continue a check from a checkpoint instead of reading a formula and proof. Use the same
+    /// options as for the interrupted check.
+    resume: Option<String>,
+}
+
+impl Flags {
+    fn options(&self) -> Result<CheckerOptions> {
+        let mut options = CheckerOptions::default()
+            .rup_only(self.rup_only)
+            .drup(self.drup)
+            .progress(self.progress)
+            .check_all(self.check_all)
+            .implicit_empty(self.implicit_empty)
+            .deletions(if self.ignore_deletions {
+                DeletionPolicy::Ignore
+            } else {
+                DeletionPolicy::Apply
+            })
+            .mode(self.mode)
+            .steps(self.from, self.to)
+            .stream(self.stream)
+            .stats(self.stats)
+            .compact_variables(self.compact_variables)
+            .keep_duplicates(self.no_dedup)
+            .check_trivial(self.check_trivial)
+            .paranoid(self.paranoid)
+            .restores(self.restores)
+            .duplicates(self.duplicates)
+            .deletion_match(self.deletion_match)
+            .strict_deletions(self.strict_deletions)
+            .comments_to_stderr(self.diagnostics == Target::Stderr)
+            .proof_format(Registry::default().get(&self.format)?);
+        if let Some(p) = self.sample {
+            options = options.sample(p, self.seed);
+        }
+        if let Some(mode) = self.cross_check {
+            options = options.cross_check(mode);
+        }
+        if let Some(fraction) = self.gc {
+            options = options.gc(fraction);
+        }
+        if let Some(megabytes) = self.max_memory {
+            options = options.max_memory(megabytes.saturating_mul(1 << 20));
+        }
+        if self.deny_warnings {
+            options = options.max_ignored(0);
+        } else if let Some(steps) = self.max_ignored {
+            options = options.max_ignored(steps);
+        }
+        if let Some(literals) = self.max_clause_length {
+            options = options.max_clause_length(literals);
+        }
+        #[cfg(feature = "checkpoint")]
+        if let Some(path) = &self.checkpoint {
+            options = options.checkpoint(
+                path,
+                std::time::Duration::from_secs(self.checkpoint_interval),
+            );
+        }
+        Ok(options)
+    }
+}
+
+/// The exit codes of the binary, so that scripts can tell the verdicts apart from each other and
+/// from checks which failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Exit {
+    /// The proof has been verified, possibly only probabilistically with --sample.
+    Verified = 0,
+    /// A lemma is not redundant, the formula propagates to a conflict without --implicit-empty or
+    /// more proof steps are ignored than allowed by --max-ignored.
+    NotVerified = 1,
+    /// All lemmas are redundant but the proof does not derive a conflict.
+    NoConflict = 2,
+    /// The formula, proof or checkpoint cannot be read or parsed.
+    InputError = 3,
+    /// The check has been stopped by --timeout before it was done.
+    Timeout = 4,
+    /// The arguments are invalid or cannot be combined.
+    UsageError = 5,
+    /// The checker failed, for example because the propagators of --cross-check diverged or a
+    /// case of `ratify selftest` did not get its expected verdict.
+    InternalError = 6,
+    /// The check needs more memory than allowed by --max-memory.
+    MemoryLimit = 7,
+    /// Only the proof steps in the range of --from and --to have been checked, and they are
+    /// redundant.
+    PartiallyVerified = 8,
+    /// The check has been stopped by SIGINT, like other programs killed by it.
+    Interrupted = 130,
+}
+
+const EXIT_CODES: &str = "Exit codes:
+  0  VERIFIED
+  1  NOT VERIFIED, a lemma is not redundant, the formula propagates to a conflict, more proof
+     steps are ignored than allowed by --deny-warnings or --max-ignored or a clause which is not
+     active is deleted with --strict-deletions
+  2  NOT VERIFIED, the proof does not derive a conflict
+  3  the formula, proof or checkpoint cannot be read or parsed, or a clause of the proof is
+     longer than --max-clause-length
+  4  TIMEOUT, the check has been stopped by --timeout
+  5  invalid arguments
+  6  internal error, such as diverging propagators with --cross-check, a broken invariant with
+     --paranoid, verdicts which differ with --audit-dedup or --diff-check, LRAT which does not
+     check with --validate-lrat or a failed selftest
+  7  MEMORY LIMIT EXCEEDED, the check needs more memory than allowed by --max-memory
+  8  PARTIALLY VERIFIED, only the proof steps in the range of --from and --to have been checked
+  130  INTERRUPTED, the check has been stopped by SIGINT
+
+On Unix, SIGUSR1 prints the phase and proof steps checked so far without stopping the check.
+SIGINT prints them as well and stops the check, a second SIGINT exits right away.";
+
+impl Exit {
+    fn of_verdict(verdict: Verdict) -> Self {
+        match verdict {
+            Verdict::Verified | Verdict::VerifiedProbabilistic => Exit::Verified,
+            Verdict::Refuted { .. } | Verdict::EarlyRefutation => Exit::NotVerified,
+            Verdict::NoConflict => Exit::NoConflict,
+            Verdict::VerifiedPartially => Exit::PartiallyVerified,
+            Verdict::Cancelled { .. } => Exit::Timeout,
+            Verdict::MemoryLimitExceeded { .. } => Exit::MemoryLimit,
+        }
+    }
+
+    fn of_error(error: &anyhow::Error) -> Self {
+        if error.is::<std::io::Error>() {
+            return Exit::InputError;
+        }
+        let Some(error) = error.downcast_ref::<Error>() else {
+            return Exit::InternalError;
+        };
+        if let Some(verdict) = error.verdict() {
+            return Exit::of_verdict(verdict);
+        }
+        match error {
+            Error::Io(_)
+            | Error::Parse { .. }
+            | Error::Checkpoint(_)
+            | Error::ClauseTooLong { .. } => Exit::InputError,
+            Error::InvalidOptions(_) => Exit::UsageError,
+            Error::TooManyIgnored { .. } | Error::MissingDeletion { .. } => Exit::NotVerified,
+            _ => Exit::InternalError,
+        }
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
+/// Verify a DRAT proof of unsatisfiability for a formula in DIMACS format.
+#[derive(Parser, Debug)]
+#[command(
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    after_help = EXIT_CODES
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    flags: Flags,
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    /// Do not log anything, not even errors. Overrides RUST_LOG.
+    quiet: bool,
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    /// Log warnings, and with -vv info, -vvv debug and -vvvv trace messages. Overrides RUST_LOG.
+    /// Without either, only errors are logged.
+    verbose: u8,
+    #[arg(long, value_name = "SECONDS")]
+    /// Stop the check after this many seconds, print how far it got and exit with code 4.
+    timeout: Option<u64>,
+    #[arg(long)]
+    /// Write the time spent parsing, preprocessing, collecting units, propagating and checking
+    /// RUP and RAT as folded stacks, which flamegraph tools such as inferno turn into a flame
+    /// graph. Needs --profile-out or --out-dir.
+    profile: bool,
+    #[arg(long, value_name = "PATH")]
+    /// Write the phase, the proof steps checked so far, the percentage, the ETA in seconds and the
+    /// peak memory to this file as `<name> <value>` lines, every --status-interval seconds and
+    /// once more with `done 1` when the check has ended.
+    status_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 10,
+        requires = "status_file"
+    )]
+    /// How often to write the --status-file.
+    status_interval: u64,
+    #[command(flatten)]
+    outputs: output::Outputs,
+    #[arg(long, conflicts_with_all = ["sample", "from", "to", "parse_only"])]
+    /// Follow the conventions of the certified track of the SAT Competition: stdout only has
+    /// `c` lines with the statistics as `<name> <value>` and a single `s VERIFIED` or `s NOT
+    /// VERIFIED` line. Implies --diagnostics stderr.
+    competition: bool,
+    #[arg(long, conflicts_with_all = ["stream", "resume"])]
+    /// Only parse and preprocess the formula and proof, print their size, the lemmas skipped
+    /// while deduplicating them and problems of the input, but do not check the proof.
+    parse_only: bool,
+    #[arg(long, conflicts_with_all = ["stream", "resume", "parse_only", "no_dedup", "competition"])]
+    /// Check the proof twice, with and without deduplicating equal clauses, and fail with an
+    /// internal error if the verdicts differ. Prints how many clauses and literals
+    /// deduplication shares. Meant for moderate inputs.
+    audit_dedup: bool,
+    #[arg(
+        long,
+        value_name = "CMD",
+        conflicts_with_all = ["sample", "from", "to", "parse_only", "audit_dedup", "competition"]
+    )]
+    /// Also check the proof with an external checker such as `drat-trim`, which is run with the
+    /// paths of the formula and proof appended to the command, and compare its verdict printed as
+    /// `s VERIFIED` or `s NOT VERIFIED` with the one of ratify. Fails with an internal error if
+    /// they differ.
+    diff_check: Option<String>,
+    #[cfg_attr(feature = "checkpoint", arg(required_unless_present = "resume"))]
+    #[cfg_attr(not(feature = "checkpoint"), arg(required = true))]
+    cnf: Option<String>,
+    #[cfg_attr(feature = "checkpoint", arg(required_unless_present = "resume"))]
+    #[cfg_attr(not(feature = "checkpoint"), arg(required = true))]
+    proof: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct TrimArgs {
+    #[command(flatten)]
+    flags: Flags,
+    #[arg(long, value_enum, default_value_t = TrimStrategy::Aggressive)]
+    /// Aggressive propagates with the clauses which are already needed before any others, which
+    /// keeps the core and proof small. Fast propagates with the clauses in the order they are
+    /// watched. The options of the forward check, such as --mode, do not apply.
+    strategy: TrimStrategy,
+    #[arg(long, value_name = "PATH")]
+    /// Write the clauses of the formula which are needed to this file in DIMACS format.
+    core_out: PathBuf,
+    #[arg(long, value_name = "PATH")]
+    /// Write the lemmas which are needed and the deletions of needed clauses to this file in DRAT
+    /// format.
+    proof_out: PathBuf,
     cnf: String,
     proof: String,
 }
 
-fn main() -> Result<()> {
+#[derive(clap::Args, Debug)]
+struct ConvertArgs {
+    #[command(flatten)]
+    flags: Flags,
+    #[arg(long, value_enum, default_value_t = Format::Drat)]
+    /// The format of the proof which is converted.
+    input_format: Format,
+    #[arg(long, value_enum)]
+    /// The format to convert the proof to. Converting DRAT to LRAT checks the proof backwards like
+    /// `ratify trim` and only keeps the lemmas it needs.
+    output_format: Format,
+    #[arg(long, value_enum, default_value_t = TrimStrategy::Aggressive)]
+    /// How to trim the proof when converting DRAT to LRAT, see `ratify trim`.
+    strategy: TrimStrategy,
+    #[arg(long)]
+    /// Check the LRAT written when converting DRAT to LRAT with its hints before writing it, and
+    /// fail with an internal error if it does not check.
+    validate_lrat: bool,
+    #[arg(long, value_name = "PATH")]
+    /// The formula in DIMACS format, which is needed if either format is LRAT.
+    cnf: Option<String>,
+    proof: String,
+    output: PathBuf,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Check proofs which running solvers stream over a Unix domain socket or TCP connection
+    /// instead of writing them to a file.
+    Serve(serve::Args),
+    /// Check the proofs which solvers write into a directory as they arrive, and write the
+    /// verdict for each of them next to its formula.
+    Watch(watch::Args),
+    /// Check a few small formulas and proofs with known verdicts in every mode to see that this
+    /// build works, and check them as a batch with one and with several threads, which has to
+    /// give the same results.
+    Selftest,
+    /// Check a proof backwards and write the clauses of the formula and the lemmas it needs to
+    /// derive the conflict, which are an unsatisfiable core and a shorter proof for it. Lemmas
+    /// which are not needed are not checked.
+    Trim(TrimArgs),
+    /// Convert a proof between text DRAT, binary DRAT and LRAT.
+    Convert(ConvertArgs),
+    /// Check that the model in the `v` lines of a solver's output satisfies the formula, and list
+    /// the clauses which it does not satisfy.
+    VerifyModel {
+        cnf: String,
+        /// The output of the solver, the lines which do not start with `v` or `s` are ignored.
+        output: String,
+    },
+}
+
+fn main() -> ExitCode {
+    let command = match config::command() {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            return Exit::UsageError.into();
+        }
+    };
+    let cli = match command
+        .try_get_matches()
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+    {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            // --help and --version are not errors
+            return if e.use_stderr() {
+                Exit::UsageError.into()
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
     tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(log_filter(cli.quiet, cli.verbose))
         .init();
-    let flags = Flags::parse();
+    match run(cli) {
+        Ok(exit) => exit.into(),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            Exit::of_error(&e).into()
+        }
+    }
+}
 
-    let (_, formula) = parser::cnf::parse(&std::fs::read_to_string(&flags.cnf)?)?;
-    let lemmas = parser::drat::parse(&std::fs::read_to_string(&flags.proof)?)?;
+// The log levels selected by -q and -v, or RUST_LOG if neither is given.
+fn log_filter(quiet: bool, verbose: u8) -> EnvFilter {
+    let level = match (quiet, verbose) {
+        (true, _) => "off",
+        (false, 0) => return EnvFilter::from_default_env(),
+        (false, 1) => "warn",
+        (false, 2) => "info",
+        (false, 3) => "debug",
+        (false, _) => "trace",
+    };
+    EnvFilter::new(level)
+}
 
-    let mut db_builder = storage::Builder::new();
-    let formula_clauses = formula.len();
+fn run(mut cli: Cli) -> Result<Exit> {
+    if cli.competition {
+        cli.flags.diagnostics = Target::Stderr;
+    }
+    let range = (cli.flags.from, cli.flags.to);
+    let token = CancellationToken::new();
+    if let Some(seconds) = cli.timeout {
+        start_timer(Duration::from_secs(seconds), token.clone());
+    }
+    let outputs = &cli.outputs;
+    let stats = cli.flags.stats || outputs.requested(Artifact::Stats);
+    let profile = cli.profile || outputs.requested(Artifact::Profile);
+    if profile {
+        outputs.require(Artifact::Profile)?;
+    }
+    let status = status::Status::new(cli.flags.progress);
+    let options = cli
+        .flags
+        .options()?
+        .progress_sink(status.clone())
+        .cancel(token.clone())
+        .stats(stats)
+        .profile(profile);
+    let _heartbeat = match &cli.status_file {
+        Some(path) if cli.command.is_none() && !cli.parse_only => Some(status.heartbeat(
+            path.clone(),
+            Duration::from_secs(cli.status_interval.max(1)),
+        )?),
+        _ => None,
+    };
+    let mut agrees = true;
+    let report = match (cli.command, cli.cnf, cli.proof) {
+        (Some(Command::Serve(args)), _, _) => return serve::run(args).map(|_| Exit::Verified),
+        (Some(Command::Watch(args)), _, _) => return watch::run(args).map(|_| Exit::Verified),
+        (Some(Command::Selftest), _, _) => return Ok(selftest::run()),
+        (Some(Command::Trim(args)), _, _) => return trim(args),
+        (Some(Command::Convert(args)), _, _) => return convert(args),
+        (Some(Command::VerifyModel { cnf, output }), _, _) => {
+            return Ok(print_model(&ratify::verify_model(&cnf, &output)?));
+        }
+        (None, Some(cnf), Some(proof)) if cli.parse_only => {
+            print_parsed(&parse_drat(&cnf, &proof, &options)?);
+            return Ok(Exit::Verified);
+        }
+        (None, Some(cnf), Some(proof)) if cli.audit_dedup => {
+            let cnf = std::fs::read_to_string(cnf)?;
+            let proof = std::fs::read_to_string(proof)?;
+            return Ok(print_audit(&audit_dedup(&cnf, &proof, options)?));
+        }
+        (None, Some(cnf), Some(proof)) => {
+            status.handle_signals(token);
+            let report = check_drat(&cnf, &proof, options)?;
+            if let Some(command) = &cli.diff_check {
+                let external = differential::run(command, &cnf, &proof)?;
+                agrees = differential::compare(&report, &external, cli.flags.diagnostics);
+            }
+            report
+        }
+        #[cfg(feature = "checkpoint")]
+        (None, _, _) if cli.flags.resume.is_some() => {
+            let checkpoint = cli.flags.resume.as_deref().unwrap_or_default();
+            status.handle_signals(token);
+            ratify::resume(checkpoint, options)?
+        }
+        _ => unreachable!("the paths are required without a subcommand"),
+    };
 
-    let proof = preprocess(formula, lemmas, &mut db_builder);
-    let clause_db = db_builder.finish();
+    if profile {
+        // the formula may propagate to a conflict before anything is recorded
+        let folded = report.profile.unwrap_or_default().folded(&report.timings);
+        outputs.write(Artifact::Profile, &folded)?;
+    }
+    if stats {
+        let text = output::stats(&report);
+        if !outputs.write(Artifact::Stats, &text)? {
+            eprint!("{}", text);
+        }
+    }
+    let target = cli.flags.diagnostics;
+    match range {
+        (None, None) => {}
+        (from, to) => target.comment(format_args!(
+            "only checked proof steps {}..{}",
+            from.map(|s| s.to_string()).unwrap_or_default(),
+            to.map(|s| s.to_string()).unwrap_or_default()
+        )),
+    }
+    // the check may have been done before it noticed SIGINT, then its verdict is printed
+    let interrupted = status.interrupted() && matches!(report.verdict, Verdict::Cancelled { .. });
+    if cli.competition {
+        print_competition(&report, &cli.flags);
+        return Ok(if interrupted {
+            Exit::Interrupted
+        } else {
+            Exit::of_verdict(report.verdict)
+        });
+    }
+    if let (true, Verdict::Cancelled { phase }) = (interrupted, report.verdict) {
+        print_interrupted(&report, phase, target);
+        return Ok(Exit::Interrupted);
+    }
+    print_verdict(&report, &cli.flags);
+    if !agrees {
+        return Ok(Exit::InternalError);
+    }
+    Ok(Exit::of_verdict(report.verdict))
+}
 
-    // mark the formula clauses as active
-    let db_view = clause_db.partial_view(formula_clauses);
+// Cancel the check once the timeout has passed. The timer does not keep the process alive.
+fn start_timer(timeout: Duration, token: CancellationToken) {
+    thread::spawn(move || {
+        thread::sleep(timeout);
+        token.cancel();
+    });
+}
 
-    match flags.mode {
-        Mode::Mutating => {
-            forward::MutatingChecker::init(flags, clause_db, db_view).validate(proof)?
-        }
-        Mode::Immutable => {
-            forward::ConstChecker::init(flags, clause_db, db_view).validate(proof)?
+fn print_parsed(report: &ParseReport) {
+    let stats = &report.stats;
+    println!(
+        "c formula: {} clauses, {} distinct, {} variables declared",
+        stats.formula_clauses, stats.distinct_formula_clauses, report.header.vars
+    );
+    println!(
+        "c proof: {} additions, {} deletions, largest variable {}",
+        stats.additions, stats.deletions, stats.max_variable
+    );
+    println!(
+        "c skipped: {} additions of active clauses, {} deletions of inactive clauses, {} \
+         deletions of duplicate clauses, {} deletions of the empty clause",
+        stats.duplicate_additions,
+        stats.missing_deletions,
+        stats.duplicate_deletions,
+        stats.empty_deletions
+    );
+    if stats.checked_duplicates > 0 {
+        println!(
+            "c checked: {} additions of active clauses",
+            stats.checked_duplicates
+        );
+    }
+    println!(
+        "c tautologies: {} clauses of the formula and lemmas",
+        stats.tautologies
+    );
+    println!("c restores: {} of the additions", stats.restores);
+    for issue in &report.issues {
+        println!("c warning: {}", issue);
+    }
+    println!(
+        "c parsing took {:.3}s, preprocessing {:.3}s",
+        report.timings.parsing.as_secs_f64(),
+        report.timings.preprocessing.as_secs_f64()
+    );
+    println!("s PARSED");
+}
+
+fn print_audit(audit: &DedupAudit) -> Exit {
+    for (name, run) in [("deduplicated", &audit.deduplicated), ("kept", &audit.kept)] {
+        println!(
+            "c {}: {:?} after {} proof steps, {} clauses and {} literals stored",
+            name, run.report.verdict, run.report.steps, run.clauses, run.literals
+        );
+    }
+    let (shared, kept) = (&audit.deduplicated, &audit.kept);
+    println!(
+        "c sharing: {} clauses and {} literals, {} duplicate additions, {} duplicate deletions",
+        kept.clauses.saturating_sub(shared.clauses),
+        kept.literals.saturating_sub(shared.literals),
+        shared.stats.duplicate_additions,
+        shared.stats.duplicate_deletions
+    );
+    if !audit.agrees() {
+        println!("c the verdicts with and without deduplication differ");
+        println!("s AUDIT FAILED");
+        return Exit::InternalError;
+    }
+    let verdict = shared.report.verdict;
+    match verdict {
+        Verdict::Verified | Verdict::VerifiedProbabilistic => println!("s VERIFIED"),
+        Verdict::VerifiedPartially => println!("s PARTIALLY VERIFIED"),
+        Verdict::Cancelled { .. } => println!("s TIMEOUT"),
+        Verdict::MemoryLimitExceeded { .. } => println!("s MEMORY LIMIT EXCEEDED"),
+        _ => println!("s NOT VERIFIED"),
+    }
+    Exit::of_verdict(verdict)
+}
+
+fn trim(args: TrimArgs) -> Result<Exit> {
+    let options = args.flags.options()?;
+    let trimmed = trim_drat(&args.cnf, &args.proof, options, args.strategy)?;
+    let report = &trimmed.report;
+    if report.verdict == Verdict::Verified {
+        let mut core = format!("p cnf {} {}\n", trimmed.header.vars, trimmed.core.len());
+        for clause in &trimmed.core {
+            core.extend(clause.iter().map(|lit| format!("{} ", lit)));
+            core.push_str("0\n");
         }
-        Mode::Naive => forward::NaiveChecker::init(flags, clause_db, db_view).validate(proof)?,
+        std::fs::write(&args.core_out, core)?;
+        std::fs::write(&args.proof_out, convert::write_drat(&trimmed.proof))?;
+        let lemmas = trimmed
+            .proof
+            .iter()
+            .filter(|step| matches!(step, ProofStep::Add(_)))
+            .count();
+        println!(
+            "c core of {} clauses, trimmed proof of {} lemmas and {} deletions",
+            trimmed.core.len(),
+            lemmas,
+            trimmed.proof.len() - lemmas
+        );
+        println!(
+            "c {} lemmas with RUP, {} lemmas with RAT",
+            report.rup_checks, report.rat_checks
+        );
     }
+    print_verdict(report, &args.flags);
+    Ok(Exit::of_verdict(report.verdict))
+}
 
-    println!("s VERIFIED");
-    Ok(())
+fn convert(args: ConvertArgs) -> Result<Exit> {
+    let options = args.flags.options()?.validate_lrat(args.validate_lrat);
+    let converted = convert::convert_files(
+        args.cnf.as_deref(),
+        &args.proof,
+        args.input_format,
+        args.output_format,
+        options,
+        args.strategy,
+    )?;
+    let verified = converted
+        .report
+        .as_ref()
+        .is_none_or(|report| report.verdict == Verdict::Verified);
+    if verified {
+        std::fs::write(&args.output, &converted.output)?;
+        println!(
+            "c converted {} to {}, wrote {} proof steps",
+            args.input_format, args.output_format, converted.steps
+        );
+    }
+    match &converted.report {
+        Some(report) => {
+            print_verdict(report, &args.flags);
+            Ok(Exit::of_verdict(report.verdict))
+        }
+        None => Ok(Exit::Verified),
+    }
 }
 
-trait Validator {
-    fn init(flags: Flags, clause_db: ClauseStorage, db_view: View) -> Self;
-    fn validate(self, proof: Vec<Lemma>) -> anyhow::Result<()>;
+fn print_model(report: &ModelReport) -> Exit {
+    if let Some(status) = report.status.as_deref().filter(|&s| s != "SATISFIABLE") {
+        println!("c warning: the solver answered {}", status);
+    }
+    for clause in &report.unsatisfied {
+        println!(
+            "c clause {} on line {} is not satisfied: {} 0",
+            clause.index,
+            clause.line,
+            clause.clause.iter().join(" ")
+        );
+    }
+    println!(
+        "c {} of {} clauses satisfied by {} assigned variables",
+        report.clauses - report.unsatisfied.len(),
+        report.clauses,
+        report.assigned
+    );
+    if report.is_verified() {
+        println!("s VERIFIED");
+        Exit::Verified
+    } else {
+        println!("s NOT VERIFIED");
+        Exit::NotVerified
+    }
 }
 
-// Adds all the clauses from the original formula and the proof to the builder. The lemmas of the
-// proof are converted to lemmas containing clause references and returned.
-fn preprocess(
-    formula: Vec<BTreeSet<Literal>>,
-    proof: Vec<RawLemma>,
-    builder: &mut Builder,
-) -> Vec<Lemma> {
-    let mut seen: FxHashMap<Clause, i32> = FxHashMap::default();
+fn print_interrupted(report: &CheckReport, phase: Phase, target: Target) {
+    target.comment(format_args!(
+        "interrupted during {}, {} proof steps applied, {} lemmas with RUP, {} lemmas with RAT",
+        phase, report.steps, report.rup_checks, report.rat_checks
+    ));
+    println!("s INTERRUPTED");
+}
 
-    for c in formula {
-        let clause = builder.add_clause(c);
-        *seen.entry(clause).or_default() += 1;
+fn print_verdict(report: &CheckReport, flags: &Flags) {
+    let target = flags.diagnostics;
+    match report.verdict {
+        Verdict::Verified => println!("s VERIFIED"),
+        Verdict::VerifiedProbabilistic => println!("s VERIFIED (probabilistic)"),
+        Verdict::VerifiedPartially => println!("s PARTIALLY VERIFIED"),
+        // besides SIGINT, the binary only cancels checks on --timeout
+        Verdict::Cancelled { phase } => {
+            target.comment(format_args!(
+                "timeout during {}, {} proof steps applied, {} lemmas verified",
+                phase,
+                report.steps,
+                report.rup_checks + report.rat_checks
+            ));
+            println!("s TIMEOUT");
+        }
+        Verdict::MemoryLimitExceeded { phase } => {
+            target.comment(format_args!(
+                "memory limit exceeded during {}, {} proof steps applied",
+                phase, report.steps
+            ));
+            println!("s MEMORY LIMIT EXCEEDED");
+        }
+        Verdict::Refuted { .. } | Verdict::NoConflict | Verdict::EarlyRefutation => {
+            diagnostic::print_rejection(report, flags);
+            println!("s NOT VERIFIED");
+        }
     }
+}
 
-    proof
-        .into_iter()
-        .enumerate()
-        .filter_map(|(i, raw_lemma)| match raw_lemma {
-            RawLemma::Add(c) => {
-                let clause = builder.add_clause(c);
-                let entry = seen.entry(clause).or_default();
-                if *entry > 0 {
-                    tracing::warn!("ignoring proof step {} addition of duplicate clause", i);
-                    // The clause has already been added, increment the appearances, but do not add
-                    // a duplicate
-                    *entry += 1;
-                    None
-                } else {
-                    // The clause has not been added yet, keep the proof step
-                    *entry += 1;
-                    Some(Lemma::Add(clause))
-                }
-            }
-            RawLemma::Del(c) => {
-                let clause = builder.add_clause(c);
-                let entry = seen.entry(clause).or_default();
-                // TODO maybe theres something we can do here to check if the clause has never
-                // been added before and then we revert adding this clause to the database
-                if *entry < 1 {
-                    // The clause has not been added before it is deleted, ignore this step
-                    tracing::warn!("ignoring proof step {} deletion of non existing clause", i);
-                    None
-                } else {
-                    *entry -= 1;
-                    if *entry == 0 {
-                        // All instances of the clause were removed, actually keep the delete
-                        // instruction then
-                        Some(Lemma::Del(clause))
-                    } else {
-                        tracing::warn!("ignoring proof step {} deletion of duplicate clause", i);
-                        None
-                    }
-                }
-            }
-        })
-        .collect_vec()
+// The statistics and the verdict for --competition, which only knows verified proofs and all
+// others. Why a proof has been rejected goes to stderr.
+fn print_competition(report: &CheckReport, flags: &Flags) {
+    print!("{}", output::competition_stats(report));
+    let target = flags.diagnostics;
+    match report.verdict {
+        Verdict::Verified => {
+            println!("s VERIFIED");
+            return;
+        }
+        Verdict::VerifiedProbabilistic => {
+            target.comment("the proof has only been verified probabilistically")
+        }
+        Verdict::VerifiedPartially => {
+            target.comment("only a range of the proof steps has been checked")
+        }
+        Verdict::Cancelled { phase } => target.comment(format_args!("timeout during {}", phase)),
+        Verdict::MemoryLimitExceeded { phase } => {
+            target.comment(format_args!("memory limit exceeded during {}", phase))
+        }
+        Verdict::Refuted { .. } | Verdict::NoConflict | Verdict::EarlyRefutation => {
+            diagnostic::print_rejection(report, flags)
+        }
+    }
+    println!("s NOT VERIFIED");
 }
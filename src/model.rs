@@ -0,0 +1,81 @@
+//! Checking the model a solver gives for a satisfiable formula, the counterpart to checking proofs
+//! of unsatisfiability.
+
+use crate::prelude::*;
+
+use crate::{
+    common::{FxHashMap, Literal},
+    parser, Result,
+};
+
+/// A clause of the formula which the model does not satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsatisfiedClause {
+    /// The position of the clause in the formula, counted from 0.
+    pub index: usize,
+    /// The line of the clause, counted from 1.
+    pub line: usize,
+    /// The literals in the order they are written in.
+    pub clause: Vec<i32>,
+}
+
+/// The result of [`verify_model`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelReport {
+    /// The answer of the solver after `s`, if there is one.
+    pub status: Option<String>,
+    /// The clauses which are not satisfied, in the order of the formula. Clauses of which no
+    /// literal is assigned count as unsatisfied.
+    pub unsatisfied: Vec<UnsatisfiedClause>,
+    pub clauses: usize,
+    /// The variables the model assigns.
+    pub assigned: usize,
+}
+
+impl ModelReport {
+    /// Whether the model satisfies every clause of the formula.
+    pub fn is_verified(&self) -> bool {
+        self.unsatisfied.is_empty()
+    }
+}
+
+/// Check the model in the solver output at `output` against the formula in DIMACS format at `cnf`.
+#[cfg(feature = "fs")]
+pub fn verify_model(cnf: &str, output: &str) -> Result<ModelReport> {
+    let cnf = std::fs::read_to_string(cnf)?;
+    let output = std::fs::read_to_string(output)?;
+    verify_model_str(&cnf, &output)
+}
+
+/// Like [`verify_model`], but with the formula and solver output given as text.
+pub fn verify_model_str(cnf: &str, output: &str) -> Result<ModelReport> {
+    let formula = parser::cnf::parse_spanned(cnf)?;
+    let model = parser::model::parse(output)?;
+    let values = model
+        .literals
+        .iter()
+        .map(|&lit| (lit.var(), lit))
+        .collect::<FxHashMap<u32, Literal>>();
+    let unsatisfied = formula
+        .clauses
+        .iter()
+        .enumerate()
+        .filter(|(_, clause)| {
+            !clause
+                .literals
+                .iter()
+                .any(|lit| values.get(&lit.var()) == Some(lit))
+        })
+        .map(|(index, clause)| UnsatisfiedClause {
+            index,
+            line: clause.line,
+            clause: clause.literals.iter().map(|lit| lit.raw()).collect(),
+        })
+        .collect();
+    Ok(ModelReport {
+        status: model.status,
+        unsatisfied,
+        clauses: formula.clauses.len(),
+        assigned: values.len(),
+    })
+}
@@ -0,0 +1,33 @@
+use crate::ProofStep;
+
+/// What happened to a single proof step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The clause has been deleted.
+    Deleted,
+    /// The lemma has been added without checking it, see `sample` and `steps`.
+    Unchecked,
+    /// The lemma has RUP.
+    Rup,
+    /// The lemma does not have RUP but RAT on the pivot.
+    Rat { pivot: i32 },
+    /// A clause which has been deleted before has been added back without checking it, see
+    /// [`CheckerOptions::restores`](crate::CheckerOptions::restores).
+    Restored,
+    /// The lemma is not redundant, the check fails after this step.
+    NotRedundant,
+    /// The step has been skipped, like a duplicate lemma or a step after the proof has been
+    /// verified. Only reported by `OnlineChecker`.
+    Ignored,
+}
+
+/// Hooks which are called around every proof step while checking, for tooling built on top of
+/// the checker. Steps are numbered like in error messages and lemmas are given in DIMACS
+/// literals, without duplicates and not necessarily in the order of the proof.
+pub trait Observer {
+    /// Called before a proof step is checked and applied.
+    fn before_step(&mut self, _step: usize, _lemma: &ProofStep) {}
+
+    /// Called after a proof step has been checked and applied.
+    fn after_step(&mut self, _step: usize, _lemma: &ProofStep, _outcome: StepOutcome) {}
+}
@@ -0,0 +1,112 @@
+//! Checking a proof which is passed one step at a time, for instance by a solver while it runs.
+//! Every step is checked as soon as it is passed in, so the first invalid step is reported
+//! immediately. Since the proof is not known up front, the clause storage is reserved according
+//! to [`ProofBounds`].
+
+use crate::prelude::*;
+use crate::{
+    common::RawLemma, forward::Online, online_check, parser, CheckReport, CheckerOptions, Error,
+    Literal, ProofBounds, Result, StepOutcome,
+};
+
+/// A checker which is fed the proof step by step.
+pub struct OnlineChecker {
+    inner: Online,
+    steps: usize,
+}
+
+impl OnlineChecker {
+    /// Set up a checker for the formula given in DIMACS literals. Fails if the options are
+    /// invalid, `cross_check` and `compact_variables` are not supported.
+    pub fn new(
+        formula: impl IntoIterator<Item = Vec<i32>>,
+        options: CheckerOptions,
+        bounds: ProofBounds,
+    ) -> Result<Self> {
+        let formula = formula
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| normalize(i + 1, c))
+            .collect::<Result<_>>()?;
+        OnlineChecker::start(formula, options, bounds)
+    }
+
+    /// Like `new`, but for a formula in DIMACS format.
+    pub fn from_cnf(cnf: &str, options: CheckerOptions, bounds: ProofBounds) -> Result<Self> {
+        let (_, formula) = parser::cnf::parse(cnf)?;
+        OnlineChecker::start(formula, options, bounds)
+    }
+
+    fn start(
+        formula: Vec<Vec<Literal>>,
+        options: CheckerOptions,
+        bounds: ProofBounds,
+    ) -> Result<Self> {
+        options.validate()?;
+        if bounds.max_variable > i32::MAX as u32
+            || formula.len().checked_add(bounds.additions).is_none()
+        {
+            return Err(Error::InvalidOptions(
+                "the bounds of the proof are too large, variables have to fit into an i32"
+                    .to_string(),
+            ));
+        }
+        if options.cross_check.is_some() || options.compact_variables {
+            return Err(Error::InvalidOptions(
+                "checking a proof step by step cannot be combined with --cross-check or \
+                 --compact-variables"
+                    .to_string(),
+            ));
+        }
+        Ok(OnlineChecker {
+            inner: online_check(options, formula, bounds),
+            steps: 0,
+        })
+    }
+
+    /// Check the lemma and add it. Once a step is rejected, all further steps fail with
+    /// [`Error::AlreadyRejected`].
+    pub fn add(&mut self, lemma: &[i32]) -> Result<StepOutcome> {
+        let lemma = normalize(self.steps + 1, lemma.to_vec())?;
+        self.step(RawLemma::Add(lemma))
+    }
+
+    /// Delete the clause.
+    pub fn delete(&mut self, clause: &[i32]) -> Result<StepOutcome> {
+        let clause = normalize(self.steps + 1, clause.to_vec())?;
+        self.step(RawLemma::Del(clause))
+    }
+
+    /// Add back a clause which has been deleted, see
+    /// [`CheckerOptions::restores`](crate::CheckerOptions::restores).
+    pub fn restore(&mut self, clause: &[i32]) -> Result<StepOutcome> {
+        let clause = normalize(self.steps + 1, clause.to_vec())?;
+        self.step(RawLemma::Restore(clause))
+    }
+
+    /// Apply an already parsed proof step, see `parser::drat::lemma`.
+    pub fn step(&mut self, lemma: RawLemma) -> Result<StepOutcome> {
+        self.steps += 1;
+        self.inner.next(lemma)
+    }
+
+    /// Whether the steps passed so far derive a conflict. Further steps are ignored unless
+    /// `check_all` is set.
+    pub fn is_verified(&self) -> bool {
+        self.inner.has_conflict()
+    }
+
+    /// End the proof. The verdict is `NoConflict` if no conflict has been derived and the empty
+    /// clause is not implied, see `implicit_empty`, and the one of the rejected step if a step has
+    /// been rejected.
+    pub fn finish(mut self) -> Result<CheckReport> {
+        self.inner.finish()
+    }
+}
+
+fn normalize(line: usize, clause: Vec<i32>) -> Result<Vec<Literal>> {
+    parser::normalize_clause(clause).ok_or_else(|| Error::Parse {
+        line,
+        message: "clause contains the literal 0 or one out of range".to_string(),
+    })
+}
@@ -0,0 +1,141 @@
+//! Where the artifacts of a check go. Stdout only has the verdict and the comment lines explaining
+//! it, so that scripts can rely on the `s` line. Every artifact is written to the path given with
+//! its `--<artifact>-out` option, otherwise to a file named after it in `--out-dir`.
+
+use std::{fmt::Write, fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use ratify::{CheckReport, Error};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct Outputs {
+    #[arg(long, value_name = "DIR")]
+    /// Write the artifacts which have no path of their own into this directory, as stats.txt and
+    /// profile.folded. The directory is created if it does not exist.
+    out_dir: Option<PathBuf>,
+    #[arg(long, value_name = "PATH")]
+    /// Write the output of --stats to this file instead of stderr. Implies --stats.
+    stats_out: Option<PathBuf>,
+    #[arg(long, value_name = "PATH")]
+    /// Write the output of --profile to this file. Implies --profile.
+    profile_out: Option<PathBuf>,
+}
+
+/// Something a check produces besides the verdict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    Stats,
+    Profile,
+}
+
+impl Artifact {
+    fn file_name(self) -> &'static str {
+        match self {
+            Artifact::Stats => "stats.txt",
+            Artifact::Profile => "profile.folded",
+        }
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            Artifact::Stats => "--stats",
+            Artifact::Profile => "--profile",
+        }
+    }
+}
+
+impl Outputs {
+    /// Whether the artifact has been requested by giving its path.
+    pub fn requested(&self, artifact: Artifact) -> bool {
+        match artifact {
+            Artifact::Stats => self.stats_out.is_some(),
+            Artifact::Profile => self.profile_out.is_some(),
+        }
+    }
+
+    /// Where the artifact goes, `None` if neither its path nor --out-dir is given.
+    pub fn path(&self, artifact: Artifact) -> Option<PathBuf> {
+        let path = match artifact {
+            Artifact::Stats => &self.stats_out,
+            Artifact::Profile => &self.profile_out,
+        };
+        path.clone().or_else(|| {
+            self.out_dir
+                .as_ref()
+                .map(|dir| dir.join(artifact.file_name()))
+        })
+    }
+
+    /// Fail before checking if an artifact which has no other place to go has no path.
+    pub fn require(&self, artifact: Artifact) -> Result<(), Error> {
+        match self.path(artifact) {
+            Some(_) => Ok(()),
+            None => Err(Error::InvalidOptions(format!(
+                "{} needs --{}-out or --out-dir",
+                artifact.flag(),
+                artifact.flag().trim_start_matches('-')
+            ))),
+        }
+    }
+
+    /// Write the artifact to its path. Returns false if it has none.
+    pub fn write(&self, artifact: Artifact, contents: &str) -> Result<bool> {
+        let Some(path) = self.path(artifact) else {
+            return Ok(false);
+        };
+        if let Some(dir) = &self.out_dir {
+            fs::create_dir_all(dir).with_context(|| format!("cannot create {}", dir.display()))?;
+        }
+        fs::write(&path, contents).with_context(|| format!("cannot write {}", path.display()))?;
+        Ok(true)
+    }
+}
+
+/// The timings and memory of the report, in the comment lines printed by --stats.
+pub fn stats(report: &CheckReport) -> String {
+    let mut text = String::new();
+    let timings = &report.timings;
+    for (name, time) in [
+        ("parsing", timings.parsing),
+        ("preprocessing", timings.preprocessing),
+        ("checking", timings.checking),
+    ] {
+        let _ = writeln!(text, "c time {:<20} {:>10.3} s", name, time.as_secs_f64());
+    }
+    let peak = ratify::common::stats::peak_rss().map(|bytes| ("peak resident", bytes));
+    for (name, bytes) in report.memory.iter().copied().chain(peak) {
+        let _ = writeln!(
+            text,
+            "c memory {:<18} {:>10.1} MiB",
+            name,
+            bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+    let _ = writeln!(
+        text,
+        "c {} proof steps, {} lemmas with RUP, {} lemmas with RAT",
+        report.steps, report.rup_checks, report.rat_checks
+    );
+    text
+}
+
+/// The statistics of the report for --competition, as `c <name> <value>` lines with the times in
+/// seconds.
+pub fn competition_stats(report: &CheckReport) -> String {
+    let mut text = String::new();
+    let timings = &report.timings;
+    let _ = writeln!(text, "c steps {}", report.steps);
+    let _ = writeln!(text, "c rup_lemmas {}", report.rup_checks);
+    let _ = writeln!(text, "c rat_lemmas {}", report.rat_checks);
+    for (name, time) in [
+        ("parsing_time", timings.parsing),
+        ("preprocessing_time", timings.preprocessing),
+        ("checking_time", timings.checking),
+    ] {
+        let _ = writeln!(text, "c {} {:.3}", name, time.as_secs_f64());
+    }
+    if let Some(bytes) = ratify::common::stats::peak_rss() {
+        let _ = writeln!(text, "c peak_rss_bytes {}", bytes);
+    }
+    text
+}
@@ -1,12 +1,21 @@
+//! Parsers for formulas in DIMACS format and proofs in textual DRAT format. The checker uses
+//! [`cnf::parse`] and [`drat::parse`], which return the clauses already normalized. For tools
+//! working on the files themselves, [`cnf::parse_spanned`] and [`drat::parse_spanned`] return the
+//! clauses as written together with their position in the input, see [`span`]. [`model`] reads
+//! the model from the output of a SAT solver. [`drat::parse_binary`] and [`lrat::parse`] read
+//! proofs in binary DRAT and LRAT for converting them, see [`convert`](crate::convert).
+
 pub mod cnf;
 pub mod drat;
+pub mod format;
+pub mod lrat;
+pub mod model;
+pub mod span;
 
-use std::collections::BTreeSet;
-
-use anyhow::bail;
+use crate::prelude::*;
 use nom::{
     bytes::complete::tag,
-    character::complete::{digit1, multispace0, multispace1},
+    character::complete::{digit1, multispace1},
     combinator::{map_res, opt, recognize},
     multi::separated_list1,
     sequence::pair,
@@ -19,13 +28,132 @@ fn parse_i32(input: &str) -> IResult<&str, i32> {
     map_res(recognize(pair(opt(tag("-")), digit1)), str::parse).parse(input)
 }
 
-fn parse_clause(input: &str) -> IResult<&str, BTreeSet<Literal>> {
+// Parses a line with a clause terminated by 0 and normalizes it, see `normalize_clause`. Returns
+// the clause with the number of duplicate literals which have been removed from it, or which
+// token makes the clause invalid.
+fn parse_clause(input: &str) -> Result<(Vec<Literal>, usize), String> {
+    let mut ids = Vec::new();
+    let mut tokens = input.split_ascii_whitespace();
+    while let Some(token) = tokens.next() {
+        let digits = token.strip_prefix('-').unwrap_or(token);
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(format!("'{}' is not a literal", token));
+        }
+        let id = match token.parse::<i32>() {
+            // the negation of i32::MIN has no variable which fits into an i32
+            Ok(id) if id != i32::MIN => id,
+            _ => return Err(format!("literal '{}' is out of range", token)),
+        };
+        if id == 0 {
+            return match tokens.next() {
+                None => normalize(ids).ok_or_else(|| "the clause contains 0".to_string()),
+                Some(token) => Err(format!("'{}' follows the terminating 0", token)),
+            };
+        }
+        ids.push(id);
+    }
+    Err("the terminating 0 is missing".to_string())
+}
+
+// Parses the integers of a clause up to the terminating 0, which is not returned. The input has
+// to start at the first literal.
+fn parse_raw_clause(input: &str) -> IResult<&str, Vec<i32>> {
     map_res(
-        pair(multispace0, separated_list1(multispace1, parse_i32)),
-        |(_, ids)| match ids.split_last() {
-            Some((0, rest)) => Ok(rest.iter().map(|&i| Literal::from(i)).collect()),
-            _ => bail!("invalid clause '{}'", input),
+        separated_list1(multispace1, parse_i32),
+        |mut ids| match ids.pop() {
+            Some(0) => Ok(ids),
+            _ => Err(()),
         },
     )
     .parse(input)
 }
+
+// Parses a clause as it is written, with the span from its first literal to the terminating 0. It
+// accepts the same clauses as `parse_clause` and fails with the same reasons.
+fn parse_spanned_clause(line: usize, offset: usize, input: &str) -> Result<span::Clause, String> {
+    parse_clause(input)?;
+    let from = input.trim_start();
+    let (remaining, ids) = parse_raw_clause(from)
+        .map_err(|_| "the literals are not separated by spaces or tabs".to_string())?;
+    Ok(span::Clause {
+        literals: ids.into_iter().map(Literal::from).collect(),
+        span: span::span_of(offset, input, from, remaining),
+        line,
+    })
+}
+
+/// Convert DIMACS literals without the terminating 0 to a clause. Every clause the checker sees is
+/// normalized here: the literals are sorted and duplicate literals are removed, so that equal
+/// clauses are detected. Tautologies, which contain a literal and its negation, are kept as they
+/// are and can be recognized with [`is_tautology`]. Returns `None` if the clause contains the
+/// literal 0 or `i32::MIN`, whose variable does not fit into an i32.
+pub fn normalize_clause(ids: Vec<i32>) -> Option<Vec<Literal>> {
+    normalize(ids).map(|(clause, _)| clause)
+}
+
+/// Whether a clause normalized by [`normalize_clause`] contains a literal and its negation. Such a
+/// clause is satisfied by every assignment, as a lemma it has RUP and in the formula it never
+/// propagates. The literals of a variable are next to each other in a sorted clause.
+pub fn is_tautology(clause: &[Literal]) -> bool {
+    clause.windows(2).any(|pair| pair[0] == -pair[1])
+}
+
+// Normalize the clause and count the duplicate literals which have been removed.
+fn normalize(ids: Vec<i32>) -> Option<(Vec<Literal>, usize)> {
+    if ids.iter().any(|&id| id == 0 || id == i32::MIN) {
+        return None;
+    }
+    // reuse the buffer of the parsed integers for the literals
+    let mut clause: Vec<Literal> = ids.into_iter().map(Literal::from).collect();
+    let len = clause.len();
+    clause.sort_unstable();
+    clause.dedup();
+    let duplicates = len - clause.len();
+    Some((clause, duplicates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clause() {
+        let (clause, duplicates) = parse_clause("  2 -1 2 0 ").unwrap();
+        assert_eq!(clause, vec![Literal::from(-1), Literal::from(2)]);
+        assert_eq!(duplicates, 1);
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        for (input, reason) in [
+            ("1 x 0", "'x' is not a literal"),
+            ("1 - 0", "'-' is not a literal"),
+            ("1 +2 0", "'+2' is not a literal"),
+            ("1 0 2 0", "'2' follows the terminating 0"),
+            ("1 2", "the terminating 0 is missing"),
+            ("", "the terminating 0 is missing"),
+            ("1 99999999999 0", "literal '99999999999' is out of range"),
+        ] {
+            assert_eq!(parse_clause(input).unwrap_err(), reason, "for '{}'", input);
+        }
+    }
+
+    #[test]
+    fn spanned_clause() {
+        let clause = parse_spanned_clause(3, 10, "  1 -2 1 0  ").unwrap();
+        assert_eq!(
+            clause.literals,
+            vec![Literal::from(1), Literal::from(-2), Literal::from(1)]
+        );
+        assert_eq!(clause.span, span::Span { start: 12, end: 20 });
+        assert_eq!(clause.line, 3);
+    }
+
+    #[test]
+    fn spanned_clause_rejects_like_parse_clause() {
+        for input in ["1 2 0 x", "1 0 2 0", "-2147483648 0", "1 2", "1 x 0", "0 0"] {
+            let expected = parse_clause(input).unwrap_err();
+            assert_eq!(parse_spanned_clause(1, 0, input).unwrap_err(), expected);
+        }
+    }
+}
@@ -1,47 +1,207 @@
-use std::collections::BTreeSet;
+//! The DIMACS format of formulas. Comment lines start with `c`, they are followed by the header
+//! `p cnf <variables> <clauses>` and a clause terminated by 0 on every line.
 
-use super::{parse_clause, parse_i32};
-use crate::common::Literal;
-use anyhow::{anyhow, Result};
+use super::{
+    is_tautology, parse_clause, parse_spanned_clause,
+    span::{self, Comment, Formula, Span},
+};
+use crate::prelude::*;
+use crate::{common::Literal, Error, Result};
 use nom::{
     bytes::complete::tag,
-    character::complete::{multispace0, multispace1},
+    character::complete::{digit1, multispace0, multispace1},
+    combinator::map_res,
     sequence::tuple,
     IResult, Parser,
 };
 
+/// The numbers of variables and clauses declared by the header of a formula.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct Header {
     pub vars: usize,
     pub clauses: usize,
 }
 
+// The header of an input which has none, which is read as the empty formula.
+const EMPTY: Header = Header {
+    vars: 0,
+    clauses: 0,
+};
+
+// The counts of the header may exceed an i32 for huge formulas, but must not be negative.
+fn parse_usize(input: &str) -> IResult<&str, usize> {
+    map_res(digit1, str::parse).parse(input)
+}
+
 fn parse_header(input: &str) -> IResult<&str, Header> {
     let (input, _) =
         tuple((multispace0, tag("p"), multispace1, tag("cnf"), multispace1)).parse(input)?;
-    let (input, (vars, _, clauses)) = tuple((parse_i32, multispace1, parse_i32)).parse(input)?;
-    Ok((
-        input,
-        Header {
-            vars: vars as usize,
-            clauses: clauses as usize,
-        },
-    ))
+    let (input, (vars, _, clauses)) =
+        tuple((parse_usize, multispace1, parse_usize)).parse(input)?;
+    Ok((input, Header { vars, clauses }))
 }
 
-pub fn parse(input: &str) -> Result<(Header, Vec<BTreeSet<Literal>>)> {
-    let mut lines = input.lines().filter(|s| !s.starts_with('c'));
+/// Parse a formula. The clauses are normalized by
+/// [`normalize_clause`](super::normalize_clause). A header which does not match the formula and
+/// clauses with duplicate literals or tautologies are only warned about, an input without a
+/// header is the empty formula.
+pub fn parse(input: &str) -> Result<(Header, Vec<Vec<Literal>>)> {
+    // lines are numbered from 1 in errors
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, s)| !s.starts_with('c'));
     let header = {
-        let (_, header) = parse_header(lines.next().ok_or(anyhow!("empty input"))?)
-            .map_err(|_| anyhow!("invalid dimacs header"))?;
+        // a formula of only comments has no clauses, which is not worth rejecting
+        let Some((line, input)) = lines.next() else {
+            tracing::warn!("the formula has no dimacs header, it is read as the empty formula");
+            return Ok((EMPTY, Vec::new()));
+        };
+        let (_, header) = parse_header(input).map_err(|_| Error::Parse {
+            line,
+            message: "invalid dimacs header".to_string(),
+        })?;
         header
     };
 
-    let clauses = lines
-        .map(|line| {
-            parse_clause(line)
-                .map(|(_, clause)| clause)
-                .map_err(|_| anyhow!("invalid clause '{}'", line))
-        })
-        .collect::<Result<Vec<_>>>()?;
+    // every clause takes at least two bytes, do not trust the header beyond that
+    let mut clauses = Vec::with_capacity(header.clauses.min(input.len() / 2));
+    let mut max_variable = 0;
+    let (mut duplicates, mut tautologies) = (0, 0);
+    for (line, input) in lines {
+        let (clause, removed) = parse_clause(input).map_err(|reason| Error::Parse {
+            line,
+            message: format!("invalid clause '{}', {}", input, reason),
+        })?;
+        duplicates += usize::from(removed > 0);
+        tautologies += usize::from(is_tautology(&clause));
+        max_variable = clause
+            .iter()
+            .map(|lit| lit.var() as usize)
+            .fold(max_variable, usize::max);
+        clauses.push(clause);
+    }
+    if max_variable > header.vars {
+        tracing::warn!(
+            "header declares {} variables but the formula contains variable {}",
+            header.vars,
+            max_variable
+        );
+    }
+    if clauses.len() != header.clauses {
+        tracing::warn!(
+            "header declares {} clauses but the formula contains {}",
+            header.clauses,
+            clauses.len()
+        );
+    }
+    if duplicates > 0 {
+        tracing::warn!(
+            "removed duplicate literals from {} clauses of the formula",
+            duplicates
+        );
+    }
+    if tautologies > 0 {
+        tracing::warn!("{} clauses of the formula are tautologies", tautologies);
+    }
     Ok((header, clauses))
 }
+
+/// Parse a formula, keeping the clauses as they are written and the position of the header, the
+/// clauses and the comments. Unlike [`parse`] it does not warn about a header which does not match
+/// the formula. The header of an input without one has an empty span at its end.
+pub fn parse_spanned(input: &str) -> Result<Formula> {
+    let mut comments = Vec::new();
+    let mut lines = span::lines(input);
+    let (header, header_span) = loop {
+        let Some((line, offset, text)) = lines.next() else {
+            return Ok(Formula {
+                header: EMPTY,
+                header_span: Span {
+                    start: input.len(),
+                    end: input.len(),
+                },
+                clauses: Vec::new(),
+                comments,
+            });
+        };
+        if text.starts_with('c') {
+            comments.push(Comment::new(line, offset, text));
+            continue;
+        }
+        let (remaining, header) = parse_header(text).map_err(|_| Error::Parse {
+            line,
+            message: "invalid dimacs header".to_string(),
+        })?;
+        let from = text.trim_start();
+        break (header, span::span_of(offset, text, from, remaining));
+    };
+
+    let mut clauses = Vec::new();
+    for (line, offset, text) in lines {
+        if text.starts_with('c') {
+            comments.push(Comment::new(line, offset, text));
+            continue;
+        }
+        let clause = parse_spanned_clause(line, offset, text).map_err(|reason| Error::Parse {
+            line,
+            message: format!("invalid clause '{}', {}", text, reason),
+        })?;
+        clauses.push(clause);
+    }
+    Ok(Formula {
+        header,
+        header_span,
+        clauses,
+        comments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_counts_beyond_i32() {
+        let (header, clauses) = parse("p cnf 3000000000 5000000000\n").unwrap();
+        assert_eq!(
+            header,
+            Header {
+                vars: 3_000_000_000,
+                clauses: 5_000_000_000
+            }
+        );
+        assert!(clauses.is_empty());
+    }
+
+    #[test]
+    fn negative_header_count() {
+        let Err(Error::Parse { line, message }) = parse("c negative\np cnf -1 0\n") else {
+            panic!("a negative count is accepted");
+        };
+        assert_eq!(line, 2);
+        assert_eq!(message, "invalid dimacs header");
+    }
+
+    #[test]
+    fn invalid_token() {
+        let Err(Error::Parse { line, message }) = parse("p cnf 2 2\n1 2 0\n1 x 0\n") else {
+            panic!("the token x is accepted");
+        };
+        assert_eq!(line, 3);
+        assert_eq!(message, "invalid clause '1 x 0', 'x' is not a literal");
+    }
+
+    #[test]
+    fn literal_out_of_range() {
+        let Err(Error::Parse { line, message }) = parse("p cnf 1 1\n-2147483648 0\n") else {
+            panic!("i32::MIN is accepted");
+        };
+        assert_eq!(line, 2);
+        assert_eq!(
+            message,
+            "invalid clause '-2147483648 0', literal '-2147483648' is out of range"
+        );
+    }
+}
@@ -1,37 +1,231 @@
-use anyhow::{anyhow, Result};
+//! The textual DRAT format of proofs. Every line which is not a comment contains a lemma, which
+//! is a clause terminated by 0, or the deletion of a clause which is prefixed with `d`. A clause
+//! prefixed with `r` restores a clause deleted before, which is only understood by [`parse`],
+//! [`lemma`] and [`stream`]. Proofs in binary DRAT are read with [`parse_binary`].
+
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
 use nom::{
-    bytes::complete::tag,
-    character::complete::{multispace0, multispace1},
-    combinator::opt,
-    sequence::{pair, tuple},
-    IResult, Parser,
+    bytes::complete::tag, character::complete::multispace1, combinator::opt, sequence::pair, Parser,
 };
 
-use super::parse_clause;
-use crate::common::RawLemma;
-
-fn parse_lemma(input: &str) -> IResult<&str, RawLemma> {
-    let (input, (del, clause)) = pair(
-        opt(tuple((multispace0, tag("d"), multispace1))),
-        parse_clause,
-    )
-    .parse(input)?;
+use super::{
+    parse_clause, parse_spanned_clause,
+    span::{self, Comment, Proof, Step},
+};
+use crate::{
+    common::{Literal, RawLemma},
+    Error, ProofStep, Result,
+};
 
-    if del.is_some() {
-        Ok((input, RawLemma::Del(clause)))
+fn parse_line(line: usize, input: &str) -> Result<RawLemma> {
+    let from = input.trim_start();
+    let prefixed = |prefix: char| {
+        from.strip_prefix(prefix)
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_whitespace()))
+    };
+    let (kind, clause): (fn(Vec<Literal>) -> RawLemma, _) = if let Some(rest) = prefixed('d') {
+        (RawLemma::Del, rest)
+    } else if let Some(rest) = prefixed('r') {
+        (RawLemma::Restore, rest)
     } else {
-        Ok((input, RawLemma::Add(clause)))
+        (RawLemma::Add, from)
+    };
+    let (clause, duplicates) = parse_clause(clause).map_err(|reason| Error::Parse {
+        line,
+        message: format!("invalid lemma '{}', {}", input, reason),
+    })?;
+    let lemma = kind(clause);
+    if duplicates > 0 {
+        tracing::warn!(
+            "removed duplicate literals from the proof step on line {}",
+            line
+        );
     }
+    Ok(lemma)
 }
 
+/// Parse a whole proof. The clauses are normalized by [`normalize_clause`](super::normalize_clause).
 pub fn parse(input: &str) -> Result<Vec<RawLemma>> {
     input
         .lines()
-        .filter(|s| !s.starts_with('c'))
-        .map(|line| {
-            parse_lemma(line)
-                .map(|(_, lemma)| lemma)
-                .map_err(|_| anyhow!("invalid lemma"))
-        })
+        .enumerate()
+        .filter_map(|(i, line)| lemma(i + 1, line))
         .collect::<Result<Vec<_>>>()
 }
+
+/// Parse a single line of a proof, returns `None` for comments. Lines are counted from 1.
+pub fn lemma(line: usize, input: &str) -> Option<Result<RawLemma>> {
+    if input.starts_with('c') {
+        None
+    } else {
+        Some(parse_line(line, input))
+    }
+}
+
+/// Parse the proof line by line while it is being read, without keeping it in memory.
+#[cfg(feature = "std")]
+pub fn stream(reader: impl BufRead) -> impl Iterator<Item = Result<RawLemma>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| match line {
+            Ok(line) => lemma(i + 1, &line),
+            Err(e) => Some(Err(e.into())),
+        })
+}
+
+/// Parse a whole proof, keeping the clauses as they are written and the position of the lemmas and
+/// the comments. Fails with [`Error::ClauseTooLong`] at the first clause with more literals than
+/// the maximum, before its literals are parsed.
+pub fn parse_spanned(input: &str, max_clause_length: Option<usize>) -> Result<Proof> {
+    let mut proof = Proof::default();
+    for (line, offset, text) in span::lines(input) {
+        if text.starts_with('c') {
+            proof.comments.push(Comment::new(line, offset, text));
+            continue;
+        }
+        let invalid = || Error::Parse {
+            line,
+            message: format!("invalid lemma '{}'", text),
+        };
+        let from = text.trim_start();
+        let (rest, del) = opt(pair(tag::<_, _, ()>("d"), multispace1))
+            .parse(from)
+            .map_err(|_| invalid())?;
+        if let Some(max) = max_clause_length {
+            let length = rest
+                .split_ascii_whitespace()
+                .take_while(|&token| token != "0")
+                .take(max.saturating_add(1))
+                .count();
+            if length > max {
+                return Err(Error::ClauseTooLong {
+                    step: proof.steps.len(),
+                    line: Some(line),
+                    length,
+                    max,
+                });
+            }
+        }
+        // the offset in the input the clause is parsed from
+        let clause_offset = offset + (text.len() - rest.len());
+        let clause =
+            parse_spanned_clause(line, clause_offset, rest).map_err(|reason| Error::Parse {
+                line,
+                message: format!("invalid lemma '{}', {}", text, reason),
+            })?;
+        let span = span::Span {
+            start: offset + (text.len() - from.len()),
+            end: clause.span.end,
+        };
+        proof.steps.push(Step {
+            deletion: del.is_some(),
+            clause,
+            span,
+        });
+    }
+    Ok(proof)
+}
+
+/// Parse a whole proof in binary DRAT format, where every step starts with the byte `a` for a
+/// lemma or `d` for a deletion and ends with a 0 byte. The literals in between are encoded as
+/// `2 * variable + 1` if negative and `2 * variable` otherwise, in groups of 7 bits starting with
+/// the lowest, where the highest bit of a byte is set if another group follows. The clauses are
+/// kept in the order they are written, errors report the position of the step counted from 1.
+/// Fails with [`Error::ClauseTooLong`] as soon as a clause has more literals than the maximum.
+pub fn parse_binary(input: &[u8], max_clause_length: Option<usize>) -> Result<Vec<ProofStep>> {
+    let mut steps = Vec::new();
+    let mut bytes = input.iter().copied().enumerate();
+    while let Some((offset, kind)) = bytes.next() {
+        let invalid = |message: String| Error::Parse {
+            line: steps.len() + 1,
+            message,
+        };
+        if kind != b'a' && kind != b'd' {
+            return Err(invalid(format!(
+                "invalid step kind {:#04x} at byte {}",
+                kind, offset
+            )));
+        }
+        let mut clause = Vec::new();
+        loop {
+            let mut code = 0u64;
+            let mut shift = 0;
+            loop {
+                let Some((offset, byte)) = bytes.next() else {
+                    return Err(invalid(
+                        "unterminated clause at the end of the proof".to_string(),
+                    ));
+                };
+                if shift > 28 {
+                    return Err(invalid(format!("literal too large at byte {}", offset)));
+                }
+                code |= u64::from(byte & 0x7f) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            if code == 0 {
+                break;
+            }
+            let var = code >> 1;
+            if var == 0 || var > i32::MAX as u64 {
+                return Err(invalid(format!("invalid literal code {}", code)));
+            }
+            clause.push(Literal::new(var as u32, code & 1 == 1).raw());
+            if let Some(max) = max_clause_length.filter(|&max| clause.len() > max) {
+                return Err(Error::ClauseTooLong {
+                    step: steps.len(),
+                    line: Some(steps.len() + 1),
+                    length: clause.len(),
+                    max,
+                });
+            }
+        }
+        steps.push(if kind == b'a' {
+            ProofStep::Add(clause)
+        } else {
+            ProofStep::Delete(clause)
+        });
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lemmas() {
+        let lemmas = parse("c comment\n2 -1 0\n  d 2 -1 0\n").unwrap();
+        let clause = vec![Literal::from(-1), Literal::from(2)];
+        assert_eq!(
+            lemmas,
+            vec![RawLemma::Add(clause.clone()), RawLemma::Del(clause)]
+        );
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        for (input, message) in [
+            (
+                "1 0\nd 1 0 2 0\n",
+                "invalid lemma 'd 1 0 2 0', '2' follows the terminating 0",
+            ),
+            ("1 0\nd1 0\n", "invalid lemma 'd1 0', 'd1' is not a literal"),
+            (
+                "1 0\nd 1\n",
+                "invalid lemma 'd 1', the terminating 0 is missing",
+            ),
+        ] {
+            let Err(Error::Parse { line, message: got }) = parse(input) else {
+                panic!("'{}' is accepted", input);
+            };
+            assert_eq!((line, got.as_str()), (2, message));
+        }
+    }
+}
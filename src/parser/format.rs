@@ -0,0 +1,173 @@
+//! Proof formats which can be selected by name. A new format is added by implementing
+//! [`ProofParser`] and registering it in a [`Registry`], the checker itself only sees the parsed
+//! proof steps.
+
+use crate::prelude::*;
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+use itertools::Itertools;
+
+use super::drat;
+use crate::{
+    common::{Origins, RawLemma},
+    CheckerOptions, Error, Result,
+};
+
+/// A proof step read by a [`ProofParser`].
+#[derive(Debug)]
+pub struct ParsedStep {
+    pub lemma: RawLemma,
+    /// The clauses justifying the lemma if the format lists them, like the clause ids of LRAT.
+    /// The forward checker finds the justification itself and ignores them.
+    pub hints: Option<Vec<u64>>,
+    /// The line of the step in the proof, counted from 1. `None` if the format has no lines,
+    /// then errors cannot point to the step in the proof.
+    pub line: Option<usize>,
+}
+
+impl ParsedStep {
+    /// A step without hints on the given line.
+    pub fn on_line(line: usize, lemma: RawLemma) -> Self {
+        ParsedStep {
+            lemma,
+            hints: None,
+            line: Some(line),
+        }
+    }
+}
+
+impl From<RawLemma> for ParsedStep {
+    fn from(lemma: RawLemma) -> Self {
+        ParsedStep {
+            lemma,
+            hints: None,
+            line: None,
+        }
+    }
+}
+
+/// Reads the proof steps of one proof format.
+pub trait ProofParser: Send + Sync {
+    /// The name the format is selected by, for example with --format.
+    fn name(&self) -> &str;
+
+    /// Parse a whole proof, skipping comments. Errors report the line they occur in.
+    fn parse<'a>(&self, input: &'a str) -> Box<dyn Iterator<Item = Result<ParsedStep>> + 'a>;
+
+    /// Parse the proof while it is being read, as needed for --stream. By default the proof is
+    /// read completely before it is parsed.
+    #[cfg(feature = "std")]
+    fn stream<'a>(
+        &self,
+        mut reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn Iterator<Item = Result<ParsedStep>> + 'a> {
+        let mut input = String::new();
+        if let Err(e) = reader.read_to_string(&mut input) {
+            return Box::new(core::iter::once(Err(e.into())));
+        }
+        Box::new(self.parse(&input).collect::<Vec<_>>().into_iter())
+    }
+}
+
+impl fmt::Debug for dyn ProofParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The textual DRAT format, see [`drat`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Drat;
+
+impl ProofParser for Drat {
+    fn name(&self) -> &str {
+        "drat"
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> Box<dyn Iterator<Item = Result<ParsedStep>> + 'a> {
+        Box::new(input.lines().enumerate().filter_map(|(i, line)| {
+            drat::lemma(i + 1, line).map(|lemma| lemma.map(|l| ParsedStep::on_line(i + 1, l)))
+        }))
+    }
+
+    #[cfg(feature = "std")]
+    fn stream<'a>(
+        &self,
+        reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn Iterator<Item = Result<ParsedStep>> + 'a> {
+        Box::new(
+            reader
+                .lines()
+                .enumerate()
+                .filter_map(|(i, line)| match line {
+                    Ok(line) => drat::lemma(i + 1, &line)
+                        .map(|lemma| lemma.map(|l| ParsedStep::on_line(i + 1, l))),
+                    Err(e) => Some(Err(e.into())),
+                }),
+        )
+    }
+}
+
+/// The proof formats known by name. The default registry contains the built-in formats, further
+/// ones are added with [`Registry::register`].
+#[derive(Debug, Clone)]
+pub struct Registry {
+    parsers: BTreeMap<String, Arc<dyn ProofParser>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry {
+            parsers: BTreeMap::new(),
+        };
+        registry.register(Drat);
+        registry
+    }
+}
+
+impl Registry {
+    /// Add a format, replacing a format of the same name.
+    pub fn register(&mut self, parser: impl ProofParser + 'static) {
+        self.parsers
+            .insert(parser.name().to_string(), Arc::new(parser));
+    }
+
+    /// The format with this name, to be passed to
+    /// [`CheckerOptions::proof_format`](crate::CheckerOptions::proof_format).
+    pub fn get(&self, name: &str) -> Result<Arc<dyn ProofParser>> {
+        self.parsers.get(name).cloned().ok_or_else(|| {
+            Error::InvalidOptions(format!(
+                "unknown proof format {}, expected one of {}",
+                name,
+                self.names().join(", ")
+            ))
+        })
+    }
+
+    /// The names of all formats in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.parsers.keys().map(String::as_str)
+    }
+}
+
+// Parse a whole proof in the format of the options and drop the hints, which the checker does not
+// use. The lines of the steps are kept in the origins. Stops at the first clause which is longer
+// than allowed.
+pub(crate) fn lemmas(options: &CheckerOptions, input: &str) -> Result<(Vec<RawLemma>, Origins)> {
+    let mut origins = Origins::default();
+    let lemmas = options
+        .proof_parser()
+        .parse(input)
+        .enumerate()
+        .map(|(i, step)| {
+            let step = step?;
+            options.check_clause_length(i, step.line, step.lemma.clause().len())?;
+            origins.record(step.line);
+            Ok(step.lemma)
+        })
+        .collect::<Result<_>>()?;
+    Ok((lemmas, origins))
+}
@@ -0,0 +1,96 @@
+//! The LRAT format of proofs, which lists the clauses justifying each lemma. Every line which is
+//! not a comment starts with a clause id. It is followed either by a lemma terminated by 0 and its
+//! hints terminated by 0, or by `d` and the ids of the deleted clauses terminated by 0. The
+//! clauses of the formula have the ids 1 to n in the order of the formula.
+
+use crate::prelude::*;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{digit1, multispace0, multispace1},
+    combinator::{all_consuming, map, map_res, opt, recognize},
+    multi::many_till,
+    sequence::{pair, preceded, terminated},
+    IResult, Parser,
+};
+
+use super::parse_i32;
+use crate::{Error, Result};
+
+/// A proof step of an LRAT proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LratStep {
+    /// A lemma in DIMACS format, with the pivot first if it has RAT. The hints are the ids of the
+    /// clauses which become unit in the order they do, a negative id starts the hints for the
+    /// resolvent with that clause.
+    Add {
+        id: u64,
+        clause: Vec<i32>,
+        hints: Vec<i64>,
+    },
+    /// The deletion of clauses by their ids. The id of the step is that of the last lemma by
+    /// convention and has no meaning otherwise.
+    Delete { id: u64, clauses: Vec<u64> },
+}
+
+fn parse_u64(input: &str) -> IResult<&str, u64> {
+    map_res(digit1, str::parse).parse(input)
+}
+
+fn parse_i64(input: &str) -> IResult<&str, i64> {
+    map_res(recognize(pair(opt(tag("-")), digit1)), str::parse).parse(input)
+}
+
+// Parses numbers separated by whitespace up to the terminating 0, which is not returned.
+fn zero_terminated<'a, T>(
+    number: impl Parser<&'a str, T, nom::error::Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>> {
+    map(
+        many_till(terminated(number, multispace1), tag("0")),
+        |(numbers, _)| numbers,
+    )
+}
+
+fn parse_step(input: &str) -> IResult<&str, LratStep> {
+    let (input, id) = preceded(multispace0, terminated(parse_u64, multispace1)).parse(input)?;
+    let delete = map(
+        preceded(pair(tag("d"), multispace1), zero_terminated(parse_u64)),
+        move |clauses| LratStep::Delete { id, clauses },
+    );
+    let add = map(
+        pair(
+            zero_terminated(map_res(parse_i32, |lit| match lit {
+                0 => Err(()),
+                lit => Ok(lit),
+            })),
+            preceded(multispace1, zero_terminated(parse_i64)),
+        ),
+        move |(clause, hints)| LratStep::Add { id, clause, hints },
+    );
+    all_consuming(terminated(alt((delete, add)), multispace0)).parse(input)
+}
+
+/// Parse a single line of a proof, returns `None` for comments. Lines are counted from 1.
+pub fn step(line: usize, input: &str) -> Option<Result<LratStep>> {
+    if input.starts_with('c') {
+        return None;
+    }
+    Some(
+        parse_step(input)
+            .map(|(_, step)| step)
+            .map_err(|_| Error::Parse {
+                line,
+                message: format!("invalid LRAT step '{}'", input),
+            }),
+    )
+}
+
+/// Parse a whole proof. The clauses are kept in the order they are written.
+pub fn parse(input: &str) -> Result<Vec<LratStep>> {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| step(i + 1, line))
+        .collect()
+}
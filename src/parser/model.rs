@@ -0,0 +1,103 @@
+//! The output of a SAT solver in the format of the SAT competition. The line `s SATISFIABLE` gives
+//! the answer and the `v` lines the model, literals which are true terminated by 0. Other lines
+//! are ignored.
+
+use crate::prelude::*;
+
+use crate::{
+    common::{FxHashMap, Literal},
+    Error, Result,
+};
+
+/// The answer and model of a solver.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Model {
+    /// The answer after `s`, such as `SATISFIABLE`, if there is one.
+    pub status: Option<String>,
+    /// The literals of the `v` lines without the terminating 0.
+    pub literals: Vec<Literal>,
+}
+
+/// Parse the output of a solver. Fails if it contains no `v` line or assigns both literals of a
+/// variable.
+pub fn parse(input: &str) -> Result<Model> {
+    let mut model = Model::default();
+    let mut seen = false;
+    let mut terminated = false;
+    // the line at which each variable has been assigned
+    let mut assigned: FxHashMap<Literal, usize> = FxHashMap::default();
+    for (i, text) in input.lines().enumerate() {
+        let line = i + 1;
+        if let Some(status) = text.strip_prefix("s ") {
+            model.status = Some(status.trim().to_string());
+            continue;
+        }
+        let Some(values) = text.strip_prefix('v') else {
+            continue;
+        };
+        seen = true;
+        for value in values.split_whitespace() {
+            let value = match value.parse::<i32>() {
+                // the negation of i32::MIN has no variable which fits into an i32
+                Ok(i32::MIN) => {
+                    return Err(Error::Parse {
+                        line,
+                        message: format!("literal '{}' is out of range", value),
+                    })
+                }
+                Ok(parsed) => parsed,
+                Err(_) => {
+                    return Err(Error::Parse {
+                        line,
+                        message: format!("invalid literal '{}'", value),
+                    })
+                }
+            };
+            if value == 0 {
+                terminated = true;
+                continue;
+            }
+            let literal = Literal::from(value);
+            if let Some(other) = assigned.get(&-literal) {
+                return Err(Error::Parse {
+                    line,
+                    message: format!("{} contradicts {} on line {}", literal, -literal, other),
+                });
+            }
+            assigned.insert(literal, line);
+            model.literals.push(literal);
+        }
+    }
+    if !seen {
+        return Err(Error::Parse {
+            line: input.lines().count().max(1),
+            message: "the solver output does not contain a model".to_string(),
+        });
+    }
+    if !terminated {
+        tracing::warn!("the model is not terminated by 0");
+    }
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_out_of_range() {
+        let Err(Error::Parse { line, message }) = parse("s SATISFIABLE\nv 1 -2147483648 0\n")
+        else {
+            panic!("i32::MIN is accepted");
+        };
+        assert_eq!(line, 2);
+        assert_eq!(message, "literal '-2147483648' is out of range");
+    }
+
+    #[test]
+    fn largest_variable() {
+        let model = parse("v -2147483647 0\n").unwrap();
+        assert_eq!(model.literals, vec![Literal::from(-i32::MAX)]);
+        assert_eq!(model.literals[0].var(), i32::MAX as u32);
+    }
+}
@@ -0,0 +1,114 @@
+//! Parsed formulas and proofs which keep the position of every clause in the input, for tools
+//! which lint or rewrite DIMACS and DRAT files. Use [`cnf::parse_spanned`](super::cnf::parse_spanned)
+//! and [`drat::parse_spanned`](super::drat::parse_spanned) to read them.
+//!
+//! Unlike the parsers used by the checker, the literals are kept in the order they are written in,
+//! including duplicates. [`normalize_clause`](super::normalize_clause) converts them to the clause
+//! the checker sees.
+
+use crate::prelude::*;
+use core::ops::Range;
+
+use super::cnf::Header;
+use crate::common::Literal;
+
+/// A range of byte offsets into the parsed input, the end is exclusive.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The text of the input covered by the span.
+    pub fn slice<'a>(&self, input: &'a str) -> &'a str {
+        &input[self.start..self.end]
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.start..span.end
+    }
+}
+
+/// A clause as it is written, from its first literal up to and including the terminating 0.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Clause {
+    /// The literals in the order of the input, without the terminating 0.
+    pub literals: Vec<Literal>,
+    pub span: Span,
+    /// The line of the clause, counted from 1.
+    pub line: usize,
+}
+
+/// A comment line. The span covers the whole line including the leading `c` but not the line
+/// break.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Comment {
+    pub span: Span,
+    pub line: usize,
+}
+
+/// A formula in DIMACS format.
+#[derive(Debug, Clone)]
+pub struct Formula {
+    pub header: Header,
+    pub header_span: Span,
+    pub clauses: Vec<Clause>,
+    pub comments: Vec<Comment>,
+}
+
+/// A lemma of a DRAT proof. The span of a deletion starts at the `d`, the span of its clause after
+/// it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Step {
+    pub deletion: bool,
+    pub clause: Clause,
+    pub span: Span,
+}
+
+/// A proof in textual DRAT format.
+#[derive(Debug, Clone, Default)]
+pub struct Proof {
+    pub steps: Vec<Step>,
+    pub comments: Vec<Comment>,
+}
+
+// The lines of the input with their number counted from 1 and the offset they start at. The line
+// breaks, including a carriage return before them, are not part of the lines.
+pub(super) fn lines(input: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut offset = 0;
+    input
+        .split_inclusive('\n')
+        .enumerate()
+        .map(move |(i, line)| {
+            let start = offset;
+            offset += line.len();
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            (i + 1, start, line)
+        })
+}
+
+// The span of the text parsed from `from` on, leaving `remaining`. Both are suffixes of `line`,
+// which starts at `offset` in the input.
+pub(super) fn span_of(offset: usize, line: &str, from: &str, remaining: &str) -> Span {
+    let start = offset + (line.len() - from.len());
+    Span {
+        start,
+        end: start + (from.len() - remaining.len()),
+    }
+}
+
+impl Comment {
+    pub(super) fn new(line: usize, offset: usize, input: &str) -> Self {
+        Comment {
+            span: Span {
+                start: offset,
+                end: offset + input.len(),
+            },
+            line,
+        }
+    }
+}
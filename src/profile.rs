@@ -0,0 +1,107 @@
+use core::{fmt::Write, time::Duration};
+
+use crate::prelude::*;
+use crate::{Instant, Timings};
+
+/// Where the time spent checking went, recorded with [`CheckerOptions::profile`]. The RUP and RAT
+/// checks include their own propagation.
+///
+/// [`CheckerOptions::profile`]: crate::CheckerOptions::profile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Collecting the unit clauses of the formula and the proof and assigning them.
+    pub units: Duration,
+    /// Propagating after the formula or a lemma has been added.
+    pub propagation: Duration,
+    pub rup: Duration,
+    pub rup_propagation: Duration,
+    pub rat: Duration,
+    pub rat_propagation: Duration,
+}
+
+impl Profile {
+    /// The profile together with the timings of a check as folded stacks, which flamegraph tools
+    /// such as inferno or speedscope read. Every line is a stack of parts and the microseconds
+    /// spent in the innermost one.
+    pub fn folded(&self, timings: &Timings) -> String {
+        let checked = self.units + self.propagation + self.rup + self.rat;
+        let stacks = [
+            ("parsing", timings.parsing),
+            ("preprocessing", timings.preprocessing),
+            ("checking", timings.checking.saturating_sub(checked)),
+            ("checking;units", self.units),
+            ("checking;propagation", self.propagation),
+            (
+                "checking;rup",
+                self.rup.saturating_sub(self.rup_propagation),
+            ),
+            ("checking;rup;propagation", self.rup_propagation),
+            (
+                "checking;rat",
+                self.rat.saturating_sub(self.rat_propagation),
+            ),
+            ("checking;rat;propagation", self.rat_propagation),
+        ];
+        let mut folded = String::new();
+        for (stack, time) in stacks {
+            let micros = time.as_micros();
+            if micros > 0 {
+                let _ = writeln!(folded, "ratify;{} {}", stack, micros);
+            }
+        }
+        folded
+    }
+}
+
+/// A part of checking which is timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Part {
+    Units,
+    Propagation,
+    Rup,
+    RupPropagation,
+    Rat,
+    RatPropagation,
+}
+
+/// Records a [`Profile`] if profiling is enabled, otherwise it does not look at the clock.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Profiler {
+    enabled: bool,
+    profile: Profile,
+}
+
+impl Profiler {
+    pub fn new(enabled: bool) -> Self {
+        Profiler {
+            enabled,
+            profile: Profile::default(),
+        }
+    }
+
+    /// The start of a part, pass it to `record` at its end.
+    pub fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    pub fn record(&mut self, started: Option<Instant>, part: Part) {
+        let Some(started) = started else {
+            return;
+        };
+        let profile = &mut self.profile;
+        let time = match part {
+            Part::Units => &mut profile.units,
+            Part::Propagation => &mut profile.propagation,
+            Part::Rup => &mut profile.rup,
+            Part::RupPropagation => &mut profile.rup_propagation,
+            Part::Rat => &mut profile.rat,
+            Part::RatPropagation => &mut profile.rat_propagation,
+        };
+        *time += started.elapsed();
+    }
+
+    /// The recorded profile, `None` if profiling is disabled.
+    pub fn profile(&self) -> Option<Profile> {
+        self.enabled.then_some(self.profile)
+    }
+}
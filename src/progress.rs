@@ -0,0 +1,127 @@
+use alloc::sync::Arc;
+use core::fmt;
+
+#[cfg(feature = "progress-bar")]
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::Phase;
+
+/// Receives the progress of a check, counted in proof steps. The terminal progress bar is one
+/// implementation, others can forward the progress to a GUI or service.
+pub trait ProgressSink: Send + Sync {
+    /// A phase of the check has started. Only checking reports its length and progress.
+    fn phase(&self, _phase: Phase) {}
+
+    /// Set the total number of proof steps, called once before checking starts.
+    fn set_length(&self, len: u64);
+
+    /// The given number of proof steps have been processed.
+    fn tick(&self, delta: u64);
+
+    /// Checking has ended, whether the proof has been verified or not.
+    fn finish(&self);
+}
+
+// Shown while parsing and preprocessing, which take an unknown time.
+#[cfg(feature = "progress-bar")]
+const SPINNER_TEMPLATE: &str = "{msg:>13} {spinner} [{elapsed_precise}]";
+#[cfg(feature = "progress-bar")]
+const BAR_TEMPLATE: &str =
+    "{msg:>13} [{elapsed_precise}] {wide_bar} {pos}/{len} steps, {per_sec}, ETA {eta}";
+
+#[cfg(feature = "progress-bar")]
+impl ProgressSink for ProgressBar {
+    fn phase(&self, phase: Phase) {
+        let template = match phase {
+            Phase::Parsing | Phase::Preprocessing => SPINNER_TEMPLATE,
+            Phase::Checking => BAR_TEMPLATE,
+        };
+        self.set_style(ProgressStyle::with_template(template).expect("valid template"));
+        self.set_message(phase.to_string());
+        if phase == Phase::Checking {
+            // the rate and ETA only count the time spent checking
+            self.disable_steady_tick();
+            self.reset();
+        } else {
+            self.enable_steady_tick(core::time::Duration::from_millis(100));
+        }
+    }
+
+    fn set_length(&self, len: u64) {
+        ProgressBar::set_length(self, len);
+    }
+
+    fn tick(&self, delta: u64) {
+        self.inc(delta);
+    }
+
+    fn finish(&self) {
+        // keep the bar at the last position if the proof was rejected early
+        self.abandon();
+    }
+}
+
+// Discards the progress.
+struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn set_length(&self, _len: u64) {}
+
+    fn tick(&self, _delta: u64) {}
+
+    fn finish(&self) {}
+}
+
+/// Where the progress of a check goes.
+#[derive(Clone, Default)]
+pub(crate) enum Progress {
+    #[default]
+    Hidden,
+    // the terminal progress bar, which shows all phases of the check
+    #[cfg(feature = "progress-bar")]
+    Bar(ProgressBar),
+    Sink(Arc<dyn ProgressSink>),
+}
+
+impl Progress {
+    /// The terminal progress bar. Without it there is nothing to show.
+    pub(crate) fn bar() -> Self {
+        #[cfg(feature = "progress-bar")]
+        return Progress::Bar(ProgressBar::new(0));
+        #[cfg(not(feature = "progress-bar"))]
+        Progress::Hidden
+    }
+
+    fn sink(&self) -> Arc<dyn ProgressSink> {
+        match self {
+            Progress::Hidden => Arc::new(NoProgress),
+            #[cfg(feature = "progress-bar")]
+            Progress::Bar(bar) => Arc::new(bar.clone()),
+            Progress::Sink(sink) => sink.clone(),
+        }
+    }
+
+    /// Report that parsing or preprocessing has started, checking is started with `start`.
+    pub(crate) fn phase(&self, phase: Phase) {
+        self.sink().phase(phase);
+    }
+
+    /// The sink for checking a proof with this many steps.
+    pub(crate) fn start(&self, len: usize) -> Arc<dyn ProgressSink> {
+        let sink = self.sink();
+        sink.phase(Phase::Checking);
+        sink.set_length(len as u64);
+        sink
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Progress::Hidden => write!(f, "Hidden"),
+            #[cfg(feature = "progress-bar")]
+            Progress::Bar(_) => write!(f, "Bar"),
+            Progress::Sink(_) => write!(f, "Sink"),
+        }
+    }
+}
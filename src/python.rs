@@ -0,0 +1,184 @@
+//! Python module of the checker, enabled with the `python` feature and built with maturin, see
+//! pyproject.toml. Rejected proofs are reported in the result, only unreadable input and invalid
+//! options raise an exception.
+
+// the wrappers generated by #[pyfunction] convert errors to themselves
+#![allow(clippy::useless_conversion)]
+
+use pyo3::{
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+};
+
+use crate::{CheckReport, CheckerOptions, DeletionPolicy, Error, Mode, Verdict};
+
+/// The result of a check.
+#[pyclass(get_all, frozen, module = "ratify")]
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// Whether the proof has been verified.
+    verified: bool,
+    /// One of "verified", "verified-probabilistic", "cancelled", "memory-limit-exceeded",
+    /// "refuted", "no-conflict" and "early-refutation".
+    verdict: &'static str,
+    /// Why the proof has been rejected.
+    error: Option<String>,
+    steps: usize,
+    rup_checks: usize,
+    rat_checks: usize,
+    parsing_seconds: f64,
+    preprocessing_seconds: f64,
+    checking_seconds: f64,
+}
+
+#[pymethods]
+impl CheckResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "CheckResult(verdict={:?}, steps={}, rup_checks={}, rat_checks={})",
+            self.verdict, self.steps, self.rup_checks, self.rat_checks
+        )
+    }
+}
+
+impl From<CheckReport> for CheckResult {
+    fn from(report: CheckReport) -> Self {
+        CheckResult {
+            verified: report.verdict.is_verified(),
+            verdict: match report.verdict {
+                Verdict::Verified => "verified",
+                Verdict::VerifiedProbabilistic => "verified-probabilistic",
+                Verdict::VerifiedPartially => "verified-partially",
+                Verdict::Cancelled { .. } => "cancelled",
+                Verdict::MemoryLimitExceeded { .. } => "memory-limit-exceeded",
+                Verdict::Refuted { .. } => "refuted",
+                Verdict::NoConflict => "no-conflict",
+                Verdict::EarlyRefutation => "early-refutation",
+            },
+            error: report.reason,
+            steps: report.steps,
+            rup_checks: report.rup_checks,
+            rat_checks: report.rat_checks,
+            parsing_seconds: report.timings.parsing.as_secs_f64(),
+            preprocessing_seconds: report.timings.preprocessing.as_secs_f64(),
+            checking_seconds: report.timings.checking.as_secs_f64(),
+        }
+    }
+}
+
+fn to_result(result: crate::Result<CheckReport>) -> PyResult<CheckResult> {
+    match result {
+        Ok(report) => Ok(report.into()),
+        Err(Error::Io(e)) => Err(PyOSError::new_err(e.to_string())),
+        Err(e) => Err(PyValueError::new_err(e.to_string())),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn options(
+    mode: &str,
+    rup_only: bool,
+    drup: bool,
+    check_all: bool,
+    implicit_empty: bool,
+    ignore_deletions: bool,
+    stream: bool,
+) -> PyResult<CheckerOptions> {
+    let mode = mode.parse::<Mode>().map_err(PyValueError::new_err)?;
+    Ok(CheckerOptions::default()
+        .mode(mode)
+        .rup_only(rup_only)
+        .drup(drup)
+        .check_all(check_all)
+        .implicit_empty(implicit_empty)
+        .deletions(if ignore_deletions {
+            DeletionPolicy::Ignore
+        } else {
+            DeletionPolicy::Apply
+        })
+        .stream(stream))
+}
+
+/// Check the DRAT proof at the path `proof` for the DIMACS formula at the path `cnf`.
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+#[pyo3(signature = (
+    cnf,
+    proof,
+    *,
+    mode = "mutating",
+    rup_only = false,
+    drup = false,
+    check_all = false,
+    implicit_empty = false,
+    ignore_deletions = false,
+    stream = false,
+))]
+fn check(
+    py: Python<'_>,
+    cnf: &str,
+    proof: &str,
+    mode: &str,
+    rup_only: bool,
+    drup: bool,
+    check_all: bool,
+    implicit_empty: bool,
+    ignore_deletions: bool,
+    stream: bool,
+) -> PyResult<CheckResult> {
+    let options = options(
+        mode,
+        rup_only,
+        drup,
+        check_all,
+        implicit_empty,
+        ignore_deletions,
+        stream,
+    )?;
+    to_result(py.allow_threads(|| crate::check_drat(cnf, proof, options)))
+}
+
+/// Like `check`, but the formula and proof are given as text instead of paths.
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+#[pyo3(signature = (
+    cnf,
+    proof,
+    *,
+    mode = "mutating",
+    rup_only = false,
+    drup = false,
+    check_all = false,
+    implicit_empty = false,
+    ignore_deletions = false,
+))]
+fn check_str(
+    py: Python<'_>,
+    cnf: &str,
+    proof: &str,
+    mode: &str,
+    rup_only: bool,
+    drup: bool,
+    check_all: bool,
+    implicit_empty: bool,
+    ignore_deletions: bool,
+) -> PyResult<CheckResult> {
+    let options = options(
+        mode,
+        rup_only,
+        drup,
+        check_all,
+        implicit_empty,
+        ignore_deletions,
+        false,
+    )?;
+    to_result(py.allow_threads(|| crate::check_str(cnf, proof, options)))
+}
+
+#[pymodule]
+fn ratify(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<CheckResult>()?;
+    m.add_function(wrap_pyfunction!(check, m)?)?;
+    m.add_function(wrap_pyfunction!(check_str, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,331 @@
+//! `ratify selftest` checks a few small formulas and proofs with known verdicts in every mode, as
+//! a quick way to see that a build of ratify works. Every case prints a line, the last line is
+//! `s PASSED` or `s FAILED`.
+//!
+//! The cases are also checked as a batch like `ratify watch` does, once with --threads 1 and once
+//! with several threads, which has to give the same result files.
+
+use std::{fs, io, path::Path, thread};
+
+use ratify::{check_str, CheckReport, CheckerOptions, Error, Mode, Verdict};
+
+use crate::{watch, Exit};
+
+const MODES: [Mode; 5] = [
+    Mode::Mutating,
+    Mode::Immutable,
+    Mode::Naive,
+    Mode::HeadTail,
+    Mode::Auto,
+];
+
+// All clauses over two variables, which is unsatisfiable.
+const ALL_CLAUSES: &str = "p cnf 2 4
+1 2 0
+-1 2 0
+1 -2 0
+-1 -2 0
+";
+
+// Satisfied by 1 and 2.
+const SATISFIABLE: &str = "p cnf 2 3
+1 2 0
+-1 2 0
+1 -2 0
+";
+
+// The example of drat-trim, whose first lemma has RAT on -1 but not RUP.
+const RAT_FORMULA: &str = "p cnf 4 8
+1 2 -3 0
+-1 -2 3 0
+2 3 -4 0
+-2 -3 4 0
+1 3 4 0
+-1 -3 -4 0
+-1 2 4 0
+1 -2 -4 0
+";
+
+const RAT_PROOF: &str = "-1 0
+d -1 -2 3 0
+d -1 -3 -4 0
+d -1 2 4 0
+2 0
+0
+";
+
+// Deletes a clause again after adding it, with a duplicate addition, a duplicate deletion and the
+// deletion of a clause which does not exist, which are all skipped.
+const DELETION_PROOF: &str = "1 2 -3 0
+d 1 2 -3 0
+1 2 -3 0
+1 2 -3 0
+d 1 2 -3 0
+d 1 2 -3 0
+d 3 4 0
+1 0
+d 1 2 0
+d 1 -2 0
+0
+";
+
+// All clauses over three variables.
+const ALL_CLAUSES_3: &str = "p cnf 3 8
+1 2 3 0
+1 2 -3 0
+1 -2 3 0
+1 -2 -3 0
+-1 2 3 0
+-1 2 -3 0
+-1 -2 3 0
+-1 -2 -3 0
+";
+
+// Adds 1 2 again after deleting it, the clause is needed for the RUP check of 1 once the
+// clauses it subsumes are deleted.
+const READD_PROOF: &str = "1 2 0
+d 1 2 0
+1 2 0
+d 1 2 3 0
+d 1 2 -3 0
+1 0
+2 0
+0
+";
+
+// Restores 1 2 after deleting it and the clauses it subsumes, at which point it has neither RUP
+// nor RAT. The proof only has RUP lemmas, so the restored clause is implied by the formula.
+const RESTORE_PROOF: &str = "1 2 0
+d 1 2 3 0
+d 1 2 -3 0
+d 1 2 0
+r 1 2 0
+1 0
+2 0
+0
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    Verdict(Verdict),
+    TooManyIgnored,
+    MissingDeletion,
+}
+
+struct Case {
+    name: &'static str,
+    cnf: &'static str,
+    proof: &'static str,
+    options: fn(CheckerOptions) -> CheckerOptions,
+    expected: Expected,
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "valid",
+        cnf: ALL_CLAUSES,
+        proof: "1 0\n0\n",
+        options: |options| options,
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "refuted",
+        cnf: SATISFIABLE,
+        proof: "-1 0\n0\n",
+        options: |options| options,
+        expected: Expected::Verdict(Verdict::Refuted { step: 0 }),
+    },
+    Case {
+        name: "no conflict",
+        cnf: SATISFIABLE,
+        proof: "2 0\n",
+        options: |options| options,
+        expected: Expected::Verdict(Verdict::NoConflict),
+    },
+    Case {
+        name: "rat",
+        cnf: RAT_FORMULA,
+        proof: RAT_PROOF,
+        options: |options| options,
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "rat with --rup-only",
+        cnf: RAT_FORMULA,
+        proof: RAT_PROOF,
+        options: |options| options.rup_only(true),
+        expected: Expected::Verdict(Verdict::Refuted { step: 0 }),
+    },
+    Case {
+        name: "deletions",
+        cnf: ALL_CLAUSES,
+        proof: DELETION_PROOF,
+        options: |options| options,
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "deletions with --deny-warnings",
+        cnf: ALL_CLAUSES,
+        proof: DELETION_PROOF,
+        options: |options| options.max_ignored(0),
+        expected: Expected::TooManyIgnored,
+    },
+    Case {
+        name: "deletions with --strict-deletions",
+        cnf: ALL_CLAUSES,
+        proof: DELETION_PROOF,
+        options: |options| options.strict_deletions(true),
+        expected: Expected::MissingDeletion,
+    },
+    Case {
+        name: "clause added again after its deletion",
+        cnf: ALL_CLAUSES_3,
+        proof: READD_PROOF,
+        options: |options| options.paranoid(true),
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "restore",
+        cnf: ALL_CLAUSES_3,
+        proof: RESTORE_PROOF,
+        options: |options| options.restores(true),
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "restore without --restores",
+        cnf: ALL_CLAUSES_3,
+        proof: RESTORE_PROOF,
+        options: |options| options,
+        expected: Expected::Verdict(Verdict::Refuted { step: 4 }),
+    },
+    Case {
+        name: "valid with --cross-check mutating",
+        cnf: ALL_CLAUSES,
+        proof: "1 0\n0\n",
+        options: |options| options.cross_check(Mode::Mutating),
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "valid with --cross-check immutable",
+        cnf: ALL_CLAUSES,
+        proof: "1 0\n0\n",
+        options: |options| options.cross_check(Mode::Immutable),
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "valid with --cross-check naive",
+        cnf: ALL_CLAUSES,
+        proof: "1 0\n0\n",
+        options: |options| options.cross_check(Mode::Naive),
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+    Case {
+        name: "valid with --cross-check head-tail",
+        cnf: ALL_CLAUSES,
+        proof: "1 0\n0\n",
+        options: |options| options.cross_check(Mode::HeadTail),
+        expected: Expected::Verdict(Verdict::Verified),
+    },
+];
+
+pub fn run() -> Exit {
+    let mut failed = 0;
+    for case in CASES {
+        for mode in MODES {
+            let options = (case.options)(CheckerOptions::default().mode(mode));
+            let result = check_str(case.cnf, case.proof, options);
+            match outcome(&result) {
+                Some(outcome) if outcome == case.expected => {
+                    println!("c {} ({:?}): pass", case.name, mode)
+                }
+                _ => {
+                    failed += 1;
+                    println!(
+                        "c {} ({:?}): FAIL, expected {:?}, got {}",
+                        case.name,
+                        mode,
+                        case.expected,
+                        match &result {
+                            Ok(report) => format!("{:?}", report.verdict),
+                            Err(e) => e.to_string(),
+                        }
+                    );
+                }
+            }
+        }
+    }
+    let threads = thread::available_parallelism().map_or(4, |n| n.get().max(2));
+    match compare_threads(threads) {
+        Ok(None) => println!("c watch with --threads 1 and {}: pass", threads),
+        Ok(Some(proof)) => {
+            failed += 1;
+            println!(
+                "c watch with --threads 1 and {}: FAIL, the results of case {} differ",
+                threads, proof
+            );
+        }
+        Err(e) => {
+            failed += 1;
+            println!("c watch with --threads 1 and {}: FAIL, {}", threads, e);
+        }
+    }
+    if failed == 0 {
+        println!("s PASSED");
+        Exit::Verified
+    } else {
+        println!(
+            "c {} of {} checks failed",
+            failed,
+            CASES.len() * MODES.len() + 1
+        );
+        println!("s FAILED");
+        Exit::InternalError
+    }
+}
+
+fn outcome(result: &Result<CheckReport, Error>) -> Option<Expected> {
+    match result {
+        Ok(report) => Some(Expected::Verdict(report.verdict)),
+        Err(Error::TooManyIgnored { .. }) => Some(Expected::TooManyIgnored),
+        Err(Error::MissingDeletion { .. }) => Some(Expected::MissingDeletion),
+        Err(e) => e.verdict().map(Expected::Verdict),
+    }
+}
+
+// Check all cases as a batch with one thread and with `threads` threads, and return the name of
+// the first case whose result files differ. The cases are checked with the default options.
+fn compare_threads(threads: usize) -> io::Result<Option<&'static str>> {
+    let dir = std::env::temp_dir().join(format!("ratify-selftest-{}", std::process::id()));
+    let result = (|| {
+        let mut results = Vec::new();
+        for threads in [1, threads] {
+            let batch = dir.join(threads.to_string());
+            fs::create_dir_all(&batch)?;
+            let proofs = CASES
+                .iter()
+                .enumerate()
+                .map(|(i, case)| {
+                    let name = batch.join(format!("{:02}", i));
+                    fs::write(name.with_extension("cnf"), case.cnf)?;
+                    fs::write(name.with_extension("drat"), case.proof)?;
+                    Ok(name.with_extension("drat"))
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            watch::check_batch(&proofs, &CheckerOptions::default(), threads);
+            results.push(read_results(&batch)?);
+        }
+        Ok(results[0]
+            .iter()
+            .zip(&results[1])
+            .position(|(one, many)| one != many)
+            .map(|i| CASES[i].name))
+    })();
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn read_results(batch: &Path) -> io::Result<Vec<String>> {
+    (0..CASES.len())
+        .map(|i| fs::read_to_string(batch.join(format!("{:02}.result", i))))
+        .collect()
+}
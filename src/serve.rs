@@ -0,0 +1,223 @@
+//! `ratify serve` checks proofs which are streamed over a socket, so a running solver can have
+//! its proof checked concurrently without writing it to a file. Every connection is handled on
+//! its own thread and may check several proofs one after another.
+//!
+//! The protocol is line based. The client sends
+//!
+//! - `load <bytes> <additions> <max-variable>` followed by `<bytes>` bytes of the formula in
+//!   DIMACS format. The proof may add at most `<additions>` lemmas. Space is reserved for
+//!   variables up to `<max-variable>`, larger ones are slower to introduce, see `ProofBounds`.
+//! - proof steps in the textual DRAT format, one per line.
+//! - `verdict` to end the proof.
+//!
+//! and the server replies with
+//!
+//! - `ok` once the formula has been loaded.
+//! - `s VERIFIED` or `s VERIFIED (probabilistic)` for `verdict` if the proof has been verified.
+//! - `s REJECTED <message>` as soon as a proof step is rejected, and again for `verdict`.
+//! - `e <message>` as soon as a command or proof step cannot be read or exceeds the bounds, and
+//!   again for `verdict`.
+//!
+//! Steps after a rejection or error are ignored until `verdict`.
+//!
+//! With --http proofs are submitted over HTTP instead, see [`http`].
+
+mod http;
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+};
+
+use anyhow::Result;
+use ratify::{parser, CheckerOptions, Error, OnlineChecker, ProofBounds, Verdict};
+
+use crate::Flags;
+
+#[derive(clap::Args, Debug, Clone)]
+#[command(group(clap::ArgGroup::new("address").required(true)))]
+pub struct Args {
+    #[command(flatten)]
+    flags: Flags,
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", group = "address")]
+    /// Listen on a Unix domain socket at this path.
+    socket: Option<String>,
+    #[arg(long, value_name = "ADDR", group = "address")]
+    /// Listen for TCP connections on this address, e.g. 127.0.0.1:4000.
+    tcp: Option<String>,
+    #[arg(long, value_name = "ADDR", group = "address")]
+    /// Accept formulas and proofs over HTTP on this address, e.g. 127.0.0.1:8080, and check them
+    /// in the background until their verdict is polled.
+    http: Option<String>,
+    #[arg(long, value_name = "N", requires = "http")]
+    /// With --http, check at most this many proofs at once. Defaults to the number of CPUs.
+    workers: Option<usize>,
+    #[arg(long, value_name = "N", default_value_t = 64, requires = "http")]
+    /// With --http, reject new proofs while this many are waiting to be checked.
+    max_queued: usize,
+    #[arg(long, value_name = "MB", default_value_t = 1024, requires = "http")]
+    /// With --http, reject requests whose body is larger than this many megabytes.
+    max_body: usize,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    if args.flags.format != "drat" {
+        return Err(Error::InvalidOptions(
+            "serve only reads proofs in the DRAT format".to_string(),
+        )
+        .into());
+    }
+    let options = args.flags.options()?;
+    if let Some(address) = &args.http {
+        let limits = http::Limits {
+            workers: args.workers.unwrap_or_else(|| {
+                thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+            }),
+            max_queued: args.max_queued,
+            max_body: args.max_body.saturating_mul(1 << 20),
+        };
+        // several proofs may be checked at once, their progress bars would overlap
+        return http::run(address, options.progress(false), limits);
+    }
+    #[cfg(unix)]
+    if let Some(path) = &args.socket {
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+        tracing::info!("listening on {}", path);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let reader = BufReader::new(stream.try_clone()?);
+            spawn(reader, stream, options.clone());
+        }
+        return Ok(());
+    }
+    if let Some(address) = &args.tcp {
+        let listener = TcpListener::bind(address)?;
+        tracing::info!("listening on {}", listener.local_addr()?);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let reader = BufReader::new(stream.try_clone()?);
+            spawn(reader, stream, options.clone());
+        }
+    }
+    Ok(())
+}
+
+fn spawn(
+    reader: impl BufRead + Send + 'static,
+    writer: impl Write + Send + 'static,
+    options: CheckerOptions,
+) {
+    thread::spawn(move || {
+        if let Err(e) = serve(reader, writer, options) {
+            tracing::warn!("connection closed: {}", e);
+        }
+    });
+}
+
+// The proof which is currently checked on a connection.
+enum Session {
+    Empty,
+    Checking(Box<OnlineChecker>),
+    // the reply which is repeated for `verdict`
+    Failed(String),
+}
+
+fn serve(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+    options: CheckerOptions,
+) -> io::Result<()> {
+    let mut session = Session::Empty;
+    let mut line = String::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        line_number += 1;
+        let command = line.trim();
+        if let Some(header) = command.strip_prefix("load ") {
+            let reply = match load(&mut reader, header, options.clone()) {
+                Ok(checker) => {
+                    session = Session::Checking(Box::new(checker));
+                    "ok".to_string()
+                }
+                Err(e) => {
+                    session = Session::Empty;
+                    format!("e {}", e)
+                }
+            };
+            line_number = 0;
+            reply_with(&mut writer, &reply)?;
+        } else if command == "verdict" {
+            let reply = match std::mem::replace(&mut session, Session::Empty) {
+                Session::Empty => "e no formula has been loaded".to_string(),
+                Session::Checking(checker) => match checker.finish() {
+                    Ok(report) => match report.verdict {
+                        Verdict::Verified => "s VERIFIED".to_string(),
+                        Verdict::VerifiedProbabilistic => "s VERIFIED (probabilistic)".to_string(),
+                        Verdict::VerifiedPartially => "s PARTIALLY VERIFIED".to_string(),
+                        Verdict::Cancelled { .. } => "e the check has been cancelled".to_string(),
+                        Verdict::MemoryLimitExceeded { .. } => {
+                            "e the memory limit has been exceeded".to_string()
+                        }
+                        Verdict::Refuted { .. }
+                        | Verdict::NoConflict
+                        | Verdict::EarlyRefutation => {
+                            format!("s REJECTED {}", report.reason.unwrap_or_default())
+                        }
+                    },
+                    Err(e) => failure(&e),
+                },
+                Session::Failed(reply) => reply,
+            };
+            reply_with(&mut writer, &reply)?;
+        } else if let Session::Checking(checker) = &mut session {
+            let Some(lemma) = parser::drat::lemma(line_number, command) else {
+                continue;
+            };
+            if let Err(e) = lemma.and_then(|lemma| checker.step(lemma)) {
+                let reply = failure(&e);
+                reply_with(&mut writer, &reply)?;
+                session = Session::Failed(reply);
+            }
+        } else if let Session::Empty = session {
+            reply_with(&mut writer, "e no formula has been loaded")?;
+        }
+    }
+}
+
+// Read the formula following a `load` command.
+fn load(reader: &mut impl BufRead, header: &str, options: CheckerOptions) -> Result<OnlineChecker> {
+    let numbers = header
+        .split_whitespace()
+        .map(str::parse::<usize>)
+        .collect::<Result<Vec<_>, _>>();
+    let Ok([bytes, additions, max_variable]) = numbers.as_deref() else {
+        anyhow::bail!("expected load <bytes> <additions> <max-variable>");
+    };
+    let mut cnf = vec![0; *bytes];
+    reader.read_exact(&mut cnf)?;
+    let cnf = String::from_utf8(cnf)?;
+    let bounds = ProofBounds {
+        additions: *additions,
+        max_variable: u32::try_from(*max_variable)?,
+    };
+    Ok(OnlineChecker::from_cnf(&cnf, options, bounds)?)
+}
+
+fn failure(error: &Error) -> String {
+    if error.is_rejection() {
+        format!("s REJECTED {}", error)
+    } else {
+        format!("e {}", error)
+    }
+}
+
+fn reply_with(writer: &mut impl Write, reply: &str) -> io::Result<()> {
+    writeln!(writer, "{}", reply)?;
+    writer.flush()
+}
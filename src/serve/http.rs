@@ -0,0 +1,347 @@
+//! `ratify serve --http` checks proofs which are submitted over HTTP, for running ratify as a
+//! service next to the solvers. Every request is answered with plain text and the connection is
+//! closed afterwards. The endpoints are
+//!
+//! - `PUT /formulas/<name>` with a formula in DIMACS format, which is kept in memory so that
+//!   proofs can refer to it by its name. Replies `ok`.
+//! - `DELETE /formulas/<name>` to drop the formula again.
+//! - `POST /checks?formula=<name>` with a proof in DRAT format for a loaded formula, or
+//!   `POST /checks?cnf=<bytes>` with `<bytes>` bytes of the formula followed by the proof.
+//!   Replies `202 Accepted` with the id of the check, or `503 Service Unavailable` if
+//!   --max-queued checks are already waiting.
+//! - `GET /checks/<id>` for the state of a check, which is `queued`, `running` or the verdict as
+//!   written by `ratify watch`, followed by `c` lines with the statistics of the check.
+//!
+//! At most --workers proofs are checked at once. The results of the last few thousand checks are
+//! kept.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use anyhow::Result;
+use ratify::{check_str, CheckReport, CheckerOptions};
+
+use crate::watch;
+
+// The number of finished checks whose results are kept, the oldest ones are dropped first.
+const KEPT_RESULTS: usize = 4096;
+
+/// The limits of the HTTP service.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub workers: usize,
+    pub max_queued: usize,
+    pub max_body: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Check {
+    Queued,
+    Running,
+    Done(String),
+}
+
+// A proof waiting to be checked.
+struct Job {
+    id: u64,
+    cnf: Arc<str>,
+    proof: String,
+}
+
+#[derive(Default)]
+struct State {
+    formulas: HashMap<String, Arc<str>>,
+    checks: BTreeMap<u64, Check>,
+    next_id: u64,
+}
+
+impl State {
+    fn finish(&mut self, id: u64, result: String) {
+        self.checks.insert(id, Check::Done(result));
+        let done = self
+            .checks
+            .values()
+            .filter(|check| matches!(check, Check::Done(_)))
+            .count();
+        if done > KEPT_RESULTS {
+            let oldest = self
+                .checks
+                .iter()
+                .find(|(_, check)| matches!(check, Check::Done(_)))
+                .map(|(&id, _)| id);
+            if let Some(oldest) = oldest {
+                self.checks.remove(&oldest);
+            }
+        }
+    }
+}
+
+pub fn run(address: &str, options: CheckerOptions, limits: Limits) -> Result<()> {
+    let state = Arc::new(Mutex::new(State::default()));
+    let (jobs, queue) = mpsc::sync_channel(limits.max_queued);
+    let queue = Arc::new(Mutex::new(queue));
+    for _ in 0..limits.workers.max(1) {
+        let (queue, state, options) = (queue.clone(), state.clone(), options.clone());
+        thread::spawn(move || work(&queue, &state, options));
+    }
+    let listener = TcpListener::bind(address)?;
+    tracing::info!("listening for HTTP on {}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let (state, jobs) = (state.clone(), jobs.clone());
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &state, &jobs, limits) {
+                tracing::warn!("connection closed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+// Check the queued proofs one after another until the server stops.
+fn work(queue: &Mutex<Receiver<Job>>, state: &Mutex<State>, options: CheckerOptions) {
+    loop {
+        // the lock is released before checking, so that other workers can take the next job
+        let job = queue.lock().unwrap().recv();
+        let Ok(job) = job else {
+            return;
+        };
+        state.lock().unwrap().checks.insert(job.id, Check::Running);
+        let result = match check_str(&job.cnf, &job.proof, options.clone()) {
+            Ok(report) => result(&report),
+            Err(e) => format!("e {}\n", e),
+        };
+        tracing::info!(
+            "check {}: {}",
+            job.id,
+            result.lines().next().unwrap_or_default()
+        );
+        state.lock().unwrap().finish(job.id, result);
+    }
+}
+
+// The verdict with the statistics of the check.
+fn result(report: &CheckReport) -> String {
+    format!(
+        "{}c {} proof steps, {} lemmas with RUP, {} lemmas with RAT\nc checking took {:.3}s\n",
+        watch::verdict(report),
+        report.steps,
+        report.rup_checks,
+        report.rat_checks,
+        report.timings.checking.as_secs_f64()
+    )
+}
+
+// A request without its headers, which are only read for the length of the body.
+struct Request {
+    method: String,
+    path: String,
+    query: Option<String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    reason: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn new(status: u16, reason: &'static str, body: impl Into<String>) -> Self {
+        let mut body = body.into();
+        if !body.ends_with('\n') {
+            body.push('\n');
+        }
+        Response {
+            status,
+            reason,
+            body,
+        }
+    }
+
+    fn ok(body: impl Into<String>) -> Self {
+        Response::new(200, "OK", body)
+    }
+
+    fn bad_request(body: impl Into<String>) -> Self {
+        Response::new(400, "Bad Request", body)
+    }
+
+    fn not_found(body: impl Into<String>) -> Self {
+        Response::new(404, "Not Found", body)
+    }
+}
+
+fn handle(
+    stream: TcpStream,
+    state: &Mutex<State>,
+    jobs: &SyncSender<Job>,
+    limits: Limits,
+) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let response = match read_request(BufReader::new(stream), limits.max_body) {
+        Ok(request) => respond(request, state, jobs),
+        Err(response) => response,
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n{}",
+        response.status,
+        response.reason,
+        response.body.len(),
+        response.body
+    )?;
+    writer.flush()
+}
+
+fn read_request(mut reader: impl BufRead, max_body: usize) -> Result<Request, Response> {
+    let invalid = |_| Response::bad_request("e cannot read the request");
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(invalid)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::bad_request("e invalid request line"));
+    };
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (target.to_string(), None),
+    };
+    let method = method.to_string();
+    let mut length = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(invalid)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            let value = value.trim().parse::<usize>();
+            length = Some(value.map_err(|_| Response::bad_request("e invalid Content-Length"))?);
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(Response::new(
+                411,
+                "Length Required",
+                "e only bodies with a Content-Length are supported",
+            ));
+        }
+    }
+    let length = length.unwrap_or(0);
+    if length > max_body {
+        return Err(Response::new(
+            413,
+            "Payload Too Large",
+            format!("e the body is larger than {} bytes", max_body),
+        ));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(invalid)?;
+    Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    })
+}
+
+fn respond(request: Request, state: &Mutex<State>, jobs: &SyncSender<Job>) -> Response {
+    let segments = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("PUT", ["formulas", name]) => match String::from_utf8(request.body) {
+            Ok(cnf) => {
+                let mut state = state.lock().unwrap();
+                state.formulas.insert(name.to_string(), cnf.into());
+                Response::ok("ok")
+            }
+            Err(_) => Response::bad_request("e the formula is not text"),
+        },
+        ("DELETE", ["formulas", name]) => match state.lock().unwrap().formulas.remove(*name) {
+            Some(_) => Response::ok("ok"),
+            None => Response::not_found(format!("e no formula named {}", name)),
+        },
+        ("POST", ["checks"]) => submit(request.query.as_deref(), request.body, state, jobs),
+        ("GET", ["checks", id]) => {
+            let check = id
+                .parse::<u64>()
+                .ok()
+                .and_then(|id| state.lock().unwrap().checks.get(&id).cloned());
+            match check {
+                Some(Check::Queued) => Response::ok("queued"),
+                Some(Check::Running) => Response::ok("running"),
+                Some(Check::Done(result)) => Response::ok(result),
+                None => Response::not_found(format!("e no check with id {}", id)),
+            }
+        }
+        (_, ["formulas", _] | ["checks"] | ["checks", _]) => {
+            Response::new(405, "Method Not Allowed", "e method not allowed")
+        }
+        _ => Response::not_found("e unknown endpoint"),
+    }
+}
+
+// Queue a proof for the formula given in the query.
+fn submit(
+    query: Option<&str>,
+    body: Vec<u8>,
+    state: &Mutex<State>,
+    jobs: &SyncSender<Job>,
+) -> Response {
+    let parameters = query
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .collect::<HashMap<_, _>>();
+    let (cnf, proof) = match (parameters.get("formula"), parameters.get("cnf")) {
+        (Some(name), None) => match state.lock().unwrap().formulas.get(*name) {
+            Some(cnf) => (cnf.clone(), body),
+            None => return Response::not_found(format!("e no formula named {}", name)),
+        },
+        (None, Some(bytes)) => match bytes.parse::<usize>() {
+            Ok(bytes) if bytes <= body.len() => {
+                let mut body = body;
+                let proof = body.split_off(bytes);
+                match String::from_utf8(body) {
+                    Ok(cnf) => (cnf.into(), proof),
+                    Err(_) => return Response::bad_request("e the formula is not text"),
+                }
+            }
+            _ => return Response::bad_request("e cnf must be the length of the formula"),
+        },
+        _ => return Response::bad_request("e expected either formula=<name> or cnf=<bytes>"),
+    };
+    let Ok(proof) = String::from_utf8(proof) else {
+        return Response::bad_request("e the proof is not text");
+    };
+    let mut state = state.lock().unwrap();
+    let id = state.next_id;
+    // the state stays locked, so that a worker cannot finish the check before it is queued
+    match jobs.try_send(Job { id, cnf, proof }) {
+        Ok(()) => {
+            state.next_id += 1;
+            state.checks.insert(id, Check::Queued);
+            Response::new(202, "Accepted", id.to_string())
+        }
+        Err(TrySendError::Full(_)) => Response::new(
+            503,
+            "Service Unavailable",
+            "e too many checks are queued, try again later",
+        ),
+        Err(TrySendError::Disconnected(_)) => {
+            Response::new(500, "Internal Server Error", "e the workers have stopped")
+        }
+    }
+}
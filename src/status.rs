@@ -0,0 +1,236 @@
+//! Shows how far a running check has got. On Unix, SIGUSR1 prints the status and the check
+//! continues, SIGINT prints it and stops the check, which then reports what it has verified so
+//! far. A second SIGINT exits right away. With --status-file the status is also written to a file
+//! every few seconds, for schedulers and dashboards watching many checks.
+
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use indicatif::ProgressBar;
+use ratify::{CancellationToken, Phase, ProgressSink};
+
+const PHASES: [Phase; 3] = [Phase::Parsing, Phase::Preprocessing, Phase::Checking];
+
+/// The progress of a check, which is also shown on the progress bar if there is one. Clones share
+/// the same state.
+#[derive(Clone)]
+pub struct Status {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    bar: Option<ProgressBar>,
+    started: Instant,
+    // the index of the phase in PHASES
+    phase: AtomicU8,
+    // when checking has started, in milliseconds since `started`
+    checking_since: AtomicU64,
+    steps: AtomicU64,
+    len: AtomicU64,
+    interrupted: AtomicBool,
+    // whether the status file has been written for the end of the check, which is held while
+    // writing it
+    written_done: Mutex<bool>,
+}
+
+impl Status {
+    pub fn new(progress: bool) -> Self {
+        Status {
+            inner: Arc::new(Inner {
+                bar: progress.then(|| ProgressBar::new(0)),
+                started: Instant::now(),
+                phase: AtomicU8::new(0),
+                checking_since: AtomicU64::new(0),
+                steps: AtomicU64::new(0),
+                len: AtomicU64::new(0),
+                interrupted: AtomicBool::new(false),
+                written_done: Mutex::new(false),
+            }),
+        }
+    }
+
+    /// Whether the check has been stopped by SIGINT.
+    pub fn interrupted(&self) -> bool {
+        self.inner.interrupted.load(Ordering::Relaxed)
+    }
+
+    /// The phase, the seconds since the start and, while checking, the proof steps processed,
+    /// their number and the steps per second.
+    pub fn snapshot(&self) -> (Phase, f64, Option<(u64, u64, f64)>) {
+        let inner = &self.inner;
+        let elapsed = inner.started.elapsed().as_secs_f64();
+        let phase = PHASES[inner.phase.load(Ordering::Relaxed) as usize];
+        if phase != Phase::Checking {
+            return (phase, elapsed, None);
+        }
+        let steps = inner.steps.load(Ordering::Relaxed);
+        let len = inner.len.load(Ordering::Relaxed).max(1);
+        let checking = elapsed - inner.checking_since.load(Ordering::Relaxed) as f64 / 1000.0;
+        (
+            phase,
+            elapsed,
+            Some((steps, len, steps as f64 / checking.max(0.001))),
+        )
+    }
+
+    /// Print the phase, the proof steps processed so far and the time taken to stderr.
+    pub fn print(&self) {
+        let inner = &self.inner;
+        let line = match self.snapshot() {
+            (phase, elapsed, Some((steps, len, rate))) => format!(
+                "c status: {} after {:.1}s, {}/{} proof steps ({:.1}%), {:.0} steps/s",
+                phase,
+                elapsed,
+                steps,
+                len,
+                steps as f64 * 100.0 / len as f64,
+                rate
+            ),
+            (phase, elapsed, None) => format!("c status: {} after {:.1}s", phase, elapsed),
+        };
+        match &inner.bar {
+            Some(bar) => bar.suspend(|| eprintln!("{}", line)),
+            None => eprintln!("{}", line),
+        }
+    }
+
+    /// Write the status to the file as `<name> <value>` lines. The file is replaced at once, so
+    /// readers never see half of it. The percentage and ETA are only written while checking, and
+    /// `done` is 1 once the check has ended.
+    pub fn write(&self, path: &Path, done: bool) -> io::Result<()> {
+        let mut written_done = self.inner.written_done.lock().unwrap();
+        if *written_done {
+            return Ok(());
+        }
+        *written_done = done;
+        let (phase, elapsed, progress) = self.snapshot();
+        let mut text = String::new();
+        let _ = writeln!(text, "phase {}", phase);
+        let _ = writeln!(text, "elapsed {:.1}", elapsed);
+        if let Some((steps, len, rate)) = progress {
+            let _ = writeln!(text, "step {}", steps);
+            let _ = writeln!(text, "steps {}", len);
+            let _ = writeln!(text, "percent {:.1}", steps as f64 * 100.0 / len as f64);
+            if !done && rate > 0.0 {
+                let _ = writeln!(text, "eta {:.0}", len.saturating_sub(steps) as f64 / rate);
+            }
+        }
+        if let Some(bytes) = ratify::common::stats::peak_rss() {
+            let _ = writeln!(text, "peak_rss_bytes {}", bytes);
+        }
+        let _ = writeln!(text, "done {}", u8::from(done));
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".tmp");
+        fs::write(&partial, text)?;
+        fs::rename(&partial, path)
+    }
+
+    /// Write the status to the file now and then after every interval, until the returned guard
+    /// is dropped at the end of the check.
+    pub fn heartbeat(&self, path: PathBuf, interval: Duration) -> io::Result<Heartbeat> {
+        self.write(&path, false)?;
+        let heartbeat = Heartbeat {
+            status: self.clone(),
+            path: path.clone(),
+        };
+        let status = self.clone();
+        std::thread::spawn(move || {
+            while !*status.inner.written_done.lock().unwrap() {
+                std::thread::sleep(interval);
+                if let Err(e) = status.write(&path, false) {
+                    tracing::warn!("cannot write {}: {}", path.display(), e);
+                }
+            }
+        });
+        Ok(heartbeat)
+    }
+
+    /// Print the status on SIGUSR1 and cancel the token on SIGINT. Does nothing if the signals
+    /// cannot be handled.
+    #[cfg(unix)]
+    pub fn handle_signals(&self, token: CancellationToken) {
+        use signal_hook::consts::{SIGINT, SIGUSR1};
+
+        let mut signals = match signal_hook::iterator::Signals::new([SIGINT, SIGUSR1]) {
+            Ok(signals) => signals,
+            Err(e) => {
+                tracing::warn!("cannot handle signals: {}", e);
+                return;
+            }
+        };
+        let status = self.clone();
+        std::thread::spawn(move || {
+            for signal in signals.forever() {
+                status.print();
+                if signal == SIGINT {
+                    if status.inner.interrupted.swap(true, Ordering::Relaxed) {
+                        std::process::exit(crate::Exit::Interrupted as i32);
+                    }
+                    token.cancel();
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    pub fn handle_signals(&self, _token: CancellationToken) {}
+}
+
+/// Writes the status file with `done 1` when dropped, whether the check has ended with a verdict
+/// or an error.
+pub struct Heartbeat {
+    status: Status,
+    path: PathBuf,
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        if let Err(e) = self.status.write(&self.path, true) {
+            tracing::warn!("cannot write {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl ProgressSink for Status {
+    fn phase(&self, phase: Phase) {
+        let inner = &self.inner;
+        let index = PHASES.iter().position(|&p| p == phase).unwrap_or(0);
+        inner.phase.store(index as u8, Ordering::Relaxed);
+        if phase == Phase::Checking {
+            let since = inner.started.elapsed().as_millis() as u64;
+            inner.checking_since.store(since, Ordering::Relaxed);
+            inner.steps.store(0, Ordering::Relaxed);
+        }
+        if let Some(bar) = &inner.bar {
+            ProgressSink::phase(bar, phase);
+        }
+    }
+
+    fn set_length(&self, len: u64) {
+        self.inner.len.store(len, Ordering::Relaxed);
+        if let Some(bar) = &self.inner.bar {
+            ProgressSink::set_length(bar, len);
+        }
+    }
+
+    fn tick(&self, delta: u64) {
+        self.inner.steps.fetch_add(delta, Ordering::Relaxed);
+        if let Some(bar) = &self.inner.bar {
+            ProgressSink::tick(bar, delta);
+        }
+    }
+
+    fn finish(&self) {
+        if let Some(bar) = &self.inner.bar {
+            ProgressSink::finish(bar);
+        }
+    }
+}
@@ -0,0 +1,763 @@
+//! Trimming a proof by checking it backwards, like drat-trim. The proof is first applied without
+//! checking anything until the active clauses propagate to a conflict. Going back from there, only
+//! the lemmas needed for the conflict or for the checks of later lemmas are checked, and the
+//! clauses their checks use are needed in turn. The needed clauses of the formula are an
+//! unsatisfiable core and the needed lemmas a proof for it.
+//!
+//! Deletions of unit clauses are ignored and every literal of a lemma is tried as the pivot of
+//! RAT, like in the forward check. The proof steps are numbered the same way as there.
+//!
+//! The clauses used by the check of each needed lemma are kept as well, so that the trimmed proof
+//! can also be given in LRAT. The hints of a lemma are the reasons of the literals in the order
+//! they have been propagated, followed by the falsified clause.
+
+use crate::prelude::*;
+use core::{mem, time::Duration};
+
+use crate::{
+    common::{storage::ClauseStorage, FxHashMap, Lemma, Literal, Origins, RawLemma},
+    parser::{self, cnf::Header, lrat::LratStep},
+    preprocess_lemmas, CheckReport, CheckerOptions, Error, Instant, Phase, Preprocessed, ProofStep,
+    Result, Timings, Verdict,
+};
+
+/// Which clauses are used to propagate while trimming.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TrimStrategy {
+    /// Propagate with the clauses which are already needed before any others, so that the core
+    /// and the proof stay small.
+    #[default]
+    Aggressive,
+    /// Propagate with the clauses in the order they are watched, which is faster but needs more
+    /// clauses.
+    Fast,
+}
+
+/// A proof which has been trimmed with [`trim_drat`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trimmed {
+    /// The verdict of the backward check, with the size of the core in `core_size`.
+    pub report: CheckReport,
+    pub header: Header,
+    /// The clauses of the formula which are needed, in the order of the formula. Duplicate
+    /// clauses are only listed once. Empty unless the proof has been verified.
+    pub core: Vec<Vec<i32>>,
+    /// The needed lemmas and the deletions of needed clauses, ending with the empty clause.
+    /// Lemmas which have RAT but not RUP start with their pivot. Empty unless the proof has been
+    /// verified.
+    pub proof: Vec<ProofStep>,
+    /// The same proof in LRAT for the whole formula, whose clauses are numbered by their position
+    /// in it. Duplicate clauses of the formula are deleted first and the deletions of clauses of
+    /// the formula which are not needed are kept. Empty unless the proof has been verified.
+    pub lrat: Vec<LratStep>,
+}
+
+/// Check a DRAT proof for the formula in DIMACS format at the given paths backwards, and keep
+/// only the clauses of the formula and the lemmas which are needed to derive the conflict. Of the
+/// options, only those selecting the proof format, RUP only, the handling of deletions and the
+/// formula propagating to a conflict apply.
+#[cfg(feature = "fs")]
+pub fn trim_drat(
+    cnf: &str,
+    proof: &str,
+    options: CheckerOptions,
+    strategy: TrimStrategy,
+) -> Result<Trimmed> {
+    let started = Instant::now();
+    let cnf = std::fs::read_to_string(cnf)?;
+    let proof = std::fs::read_to_string(proof)?;
+    trim_text(&cnf, &proof, options, strategy, started)
+}
+
+/// Like [`trim_drat`], with the formula and proof given as text instead of paths.
+pub fn trim_str(
+    cnf: &str,
+    proof: &str,
+    options: CheckerOptions,
+    strategy: TrimStrategy,
+) -> Result<Trimmed> {
+    trim_text(cnf, proof, options, strategy, Instant::now())
+}
+
+fn trim_text(
+    cnf: &str,
+    proof: &str,
+    options: CheckerOptions,
+    strategy: TrimStrategy,
+    started: Instant,
+) -> Result<Trimmed> {
+    options.validate()?;
+    let (header, formula) = parser::cnf::parse(cnf)?;
+    let (lemmas, origins) = parser::format::lemmas(&options, proof)?;
+    trim_parsed(
+        header,
+        formula,
+        lemmas,
+        origins,
+        options,
+        strategy,
+        started.elapsed(),
+    )
+}
+
+// Trim the parsed formula and proof, which took `parsing` to read.
+pub(crate) fn trim_parsed(
+    header: Header,
+    formula: Vec<Vec<Literal>>,
+    lemmas: Vec<RawLemma>,
+    origins: Origins,
+    options: CheckerOptions,
+    strategy: TrimStrategy,
+    parsing: Duration,
+) -> Result<Trimmed> {
+    if options.keep_duplicates {
+        return Err(Error::InvalidOptions(
+            "trimming stores duplicate clauses only once and cannot keep them".to_string(),
+        ));
+    }
+    let numbering = Numbering::new(&formula);
+    let (preprocessed, preprocessing, _) = preprocess_lemmas(formula, lemmas, origins, &options);
+    let phase = Instant::now();
+    let mut trimmed = Trimmer::new(&preprocessed, &options, strategy).trim(header, &numbering)?;
+    trimmed.report.timings = Timings {
+        parsing,
+        preprocessing,
+        checking: phase.elapsed(),
+    };
+    Ok(trimmed)
+}
+
+// A proof step referring to the clauses of the trimmer.
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Add(usize),
+    Del(usize),
+}
+
+// The ids of the clauses of the formula in LRAT, which are their positions in the formula.
+// Duplicate clauses are only stored once by the trimmer, which uses the first copy.
+struct Numbering {
+    // by the distinct clauses of the formula in the order they are stored
+    distinct: Vec<u64>,
+    duplicates: Vec<u64>,
+    clauses: u64,
+}
+
+impl Numbering {
+    fn new(formula: &[Vec<Literal>]) -> Self {
+        let mut first = FxHashMap::default();
+        let mut distinct = Vec::new();
+        let mut duplicates = Vec::new();
+        for (i, clause) in formula.iter().enumerate() {
+            let id = i as u64 + 1;
+            if first.insert(clause, id).is_some() {
+                duplicates.push(id);
+            } else {
+                distinct.push(id);
+            }
+        }
+        Numbering {
+            distinct,
+            duplicates,
+            clauses: formula.len() as u64,
+        }
+    }
+}
+
+// Why a lemma is redundant, as the clauses of the trimmer in the order of the LRAT hints.
+#[derive(Debug, Clone)]
+enum Hints {
+    Rup(Vec<usize>),
+    // The pivot, and every clause containing its negation together with the clauses which
+    // derive a conflict from the resolvent with it.
+    Rat(Literal, Vec<(usize, Vec<usize>)>),
+}
+
+impl Hints {
+    fn clauses(&self) -> impl Iterator<Item = usize> + '_ {
+        let (rup, rat): (&[usize], &[(usize, Vec<usize>)]) = match self {
+            Hints::Rup(deps) => (deps, &[]),
+            Hints::Rat(_, candidates) => (&[], candidates),
+        };
+        rup.iter().copied().chain(
+            rat.iter()
+                .flat_map(|(candidate, deps)| deps.iter().copied().chain([*candidate])),
+        )
+    }
+
+    fn pivot(&self) -> Option<Literal> {
+        match self {
+            Hints::Rup(_) => None,
+            Hints::Rat(pivot, _) => Some(*pivot),
+        }
+    }
+}
+
+// The clauses are numbered in the order they are added, starting with those of the formula. The
+// propagation uses two watched literals, which are the first two literals of a clause. Since every
+// check starts from the empty assignment, any two literals can be watched when it starts.
+struct Trimmer<'a> {
+    clause_db: &'a ClauseStorage,
+    origins: &'a Origins,
+    options: &'a CheckerOptions,
+    clauses: Vec<Vec<Literal>>,
+    formula: usize,
+    steps: Vec<Step>,
+    active: Vec<bool>,
+    needed: Vec<bool>,
+    // the clauses with a single literal
+    units: Vec<usize>,
+    // by the code of the literal, the clauses to look at when it becomes false
+    watches: Vec<Vec<usize>>,
+    // by the code of the literal, whether it is true
+    values: Vec<bool>,
+    // by variable, the clause which implied it
+    reasons: Vec<Option<usize>>,
+    // by variable, whether it is part of the conflict being analyzed
+    seen: Vec<bool>,
+    trail: Vec<Literal>,
+    // the positions in the trail up to which the needed and the other clauses have propagated
+    core_head: usize,
+    head: usize,
+    core_first: bool,
+}
+
+impl<'a> Trimmer<'a> {
+    fn new(
+        preprocessed: &'a Preprocessed,
+        options: &'a CheckerOptions,
+        strategy: TrimStrategy,
+    ) -> Self {
+        let clause_db = &preprocessed.clause_db;
+        let mut ids = FxHashMap::default();
+        let mut clauses = Vec::new();
+        for clause in clause_db.clauses(&preprocessed.db_view) {
+            ids.insert(clause, clauses.len());
+            clauses.push(clause_db.clause(clause).to_vec());
+        }
+        let formula = clauses.len();
+        let steps = preprocessed
+            .proof
+            .iter()
+            .map(|&lemma| match lemma {
+                // restored clauses are checked like lemmas when they are needed
+                Lemma::Add(clause) | Lemma::Restore(clause) => {
+                    // a clause which is added again after being deleted is a new clause
+                    ids.insert(clause, clauses.len());
+                    clauses.push(clause_db.clause(clause).to_vec());
+                    Step::Add(clauses.len() - 1)
+                }
+                Lemma::Del(clause) => Step::Del(ids[&clause]),
+            })
+            .collect();
+        let max_variable = clauses
+            .iter()
+            .flatten()
+            .map(|lit| lit.var() as usize)
+            .max()
+            .unwrap_or(0);
+        let units = (0..clauses.len())
+            .filter(|&id| clauses[id].len() == 1)
+            .collect();
+        Trimmer {
+            clause_db,
+            origins: &preprocessed.origins,
+            options,
+            formula,
+            steps,
+            active: vec![false; clauses.len()],
+            needed: vec![false; clauses.len()],
+            units,
+            watches: vec![Vec::new(); (max_variable + 1) * 2],
+            values: vec![false; (max_variable + 1) * 2],
+            reasons: vec![None; max_variable + 1],
+            seen: vec![false; max_variable + 1],
+            trail: Vec::new(),
+            core_head: 0,
+            head: 0,
+            core_first: strategy == TrimStrategy::Aggressive,
+            clauses,
+        }
+    }
+
+    fn trim(mut self, header: Header, numbering: &Numbering) -> Result<Trimmed> {
+        let trimmed = |report| Trimmed {
+            report,
+            header,
+            core: vec![],
+            proof: vec![],
+            lrat: vec![],
+        };
+
+        // apply the formula and the proof until they propagate to a conflict
+        let mut conflict = (0..self.formula).find_map(|id| self.add(id).err());
+        let mut last = None;
+        let mut ignored = vec![false; self.steps.len()];
+        for (i, ignore) in ignored.iter_mut().enumerate() {
+            if conflict.is_some() {
+                break;
+            }
+            last = Some(i);
+            match self.steps[i] {
+                Step::Add(id) => conflict = self.add(id).err(),
+                Step::Del(id) if self.is_unit(id) => {
+                    tracing::warn!("ignoring deletion of unit clause at proof step {}", i);
+                    *ignore = true;
+                }
+                Step::Del(id) => self.active[id] = false,
+            }
+        }
+        let Some(conflict) = conflict else {
+            let mut report = CheckReport::rejected(Error::NoConflict)?;
+            report.steps = self.steps.len();
+            return Ok(trimmed(report));
+        };
+        if last.is_none() && !self.options.implicit_empty {
+            return Ok(trimmed(CheckReport::rejected(Error::EarlyConflict)?));
+        }
+        let conflict = self.analyze(conflict);
+        self.need(conflict.iter().copied());
+        self.reset();
+
+        // check the needed lemmas from the conflict back to the start of the proof
+        let applied = last.map_or(0, |last| last + 1);
+        let mut report = CheckReport::new(Verdict::Verified);
+        report.steps = applied;
+        let mut hints = FxHashMap::default();
+        for i in (0..applied).rev() {
+            if i % 1024 == 0 && self.options.is_cancelled() {
+                report.verdict = Verdict::Cancelled {
+                    phase: Phase::Checking,
+                };
+                return Ok(trimmed(report));
+            }
+            match self.steps[i] {
+                Step::Add(id) => {
+                    self.active[id] = false;
+                    if !self.needed[id] {
+                        continue;
+                    }
+                    match self.redundancy(id, self.options.rup_only) {
+                        Some(redundant) => {
+                            self.need(redundant.clauses());
+                            match redundant {
+                                Hints::Rup(_) => report.rup_checks += 1,
+                                Hints::Rat(..) => report.rat_checks += 1,
+                            }
+                            hints.insert(id, redundant);
+                        }
+                        None => {
+                            let mut rejected = CheckReport::rejected(self.not_redundant(i, id))?;
+                            rejected.steps = applied;
+                            return Ok(trimmed(rejected));
+                        }
+                    }
+                }
+                Step::Del(id) if !ignored[i] => self.activate(id),
+                Step::Del(_) => {}
+            }
+        }
+
+        let core = (0..self.formula)
+            .filter(|&id| self.needed[id])
+            .map(|id| self.external(id, None))
+            .collect::<Vec<_>>();
+        let mut proof = Vec::new();
+        for (i, &step) in self.steps[..applied].iter().enumerate() {
+            match step {
+                Step::Add(id) if self.needed[id] => {
+                    let pivot = hints.get(&id).and_then(Hints::pivot);
+                    proof.push(ProofStep::Add(self.external(id, pivot)))
+                }
+                Step::Del(id) if self.needed[id] && !ignored[i] => {
+                    proof.push(ProofStep::Delete(self.external(id, None)))
+                }
+                _ => {}
+            }
+        }
+        if proof.last() != Some(&ProofStep::Add(vec![])) {
+            proof.push(ProofStep::Add(vec![]));
+        }
+        let lrat = self.lrat(numbering, applied, &ignored, &hints, &conflict);
+        report.core_size = Some(core.len());
+        Ok(Trimmed {
+            report,
+            header,
+            core,
+            proof,
+            lrat,
+        })
+    }
+
+    // The needed lemmas with their hints in LRAT, ending with the empty clause which is justified
+    // by the conflict if the proof does not add it.
+    fn lrat(
+        &self,
+        numbering: &Numbering,
+        applied: usize,
+        ignored: &[bool],
+        hints: &FxHashMap<usize, Hints>,
+        conflict: &[usize],
+    ) -> Vec<LratStep> {
+        let mut ids = vec![0; self.clauses.len()];
+        ids[..self.formula].copy_from_slice(&numbering.distinct);
+        let mut last = numbering.clauses;
+        let mut steps = Vec::new();
+        if !numbering.duplicates.is_empty() {
+            steps.push(LratStep::Delete {
+                id: last,
+                clauses: numbering.duplicates.clone(),
+            });
+        }
+        let lrat_hints = |ids: &[u64], clauses: &[usize]| {
+            clauses
+                .iter()
+                .map(|&clause| ids[clause] as i64)
+                .collect::<Vec<_>>()
+        };
+        let mut derived_empty = false;
+        for (i, &step) in self.steps[..applied].iter().enumerate() {
+            match step {
+                Step::Add(id) if self.needed[id] => {
+                    let redundant = &hints[&id];
+                    let hints = match redundant {
+                        Hints::Rup(deps) => lrat_hints(&ids, deps),
+                        Hints::Rat(_, candidates) => candidates
+                            .iter()
+                            .flat_map(|(candidate, deps)| {
+                                let mut hints = lrat_hints(&ids, deps);
+                                hints.insert(0, -(ids[*candidate] as i64));
+                                hints
+                            })
+                            .collect(),
+                    };
+                    last += 1;
+                    ids[id] = last;
+                    derived_empty = self.clauses[id].is_empty();
+                    steps.push(LratStep::Add {
+                        id: last,
+                        clause: self.external(id, redundant.pivot()),
+                        hints,
+                    });
+                }
+                Step::Del(clause)
+                    if (self.needed[clause] || clause < self.formula) && !ignored[i] =>
+                {
+                    // consecutive deletions are merged into one step
+                    let deleted = ids[clause];
+                    match steps.last_mut() {
+                        Some(LratStep::Delete { id, clauses }) if *id == last => {
+                            clauses.push(deleted)
+                        }
+                        _ => steps.push(LratStep::Delete {
+                            id: last,
+                            clauses: vec![deleted],
+                        }),
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !derived_empty {
+            steps.push(LratStep::Add {
+                id: last + 1,
+                clause: vec![],
+                hints: lrat_hints(&ids, conflict),
+            });
+        }
+        steps
+    }
+
+    // The literals of a clause in DIMACS format, in the order of the input but starting with the
+    // pivot if there is one.
+    fn external(&self, id: usize, pivot: Option<Literal>) -> Vec<i32> {
+        let mut literals = self.clauses[id].clone();
+        literals.sort_unstable();
+        if let Some(position) = pivot.and_then(|pivot| literals.iter().position(|&l| l == pivot)) {
+            literals[..=position].rotate_right(1);
+        }
+        literals
+            .into_iter()
+            .map(|lit| self.clause_db.external(lit))
+            .collect()
+    }
+
+    fn not_redundant(&mut self, step: usize, id: usize) -> Error {
+        let rat_pivot = if self.options.rup_only {
+            self.redundancy(id, false)
+                .and_then(|redundant| redundant.pivot())
+                .map(|pivot| self.clause_db.external(pivot))
+        } else {
+            None
+        };
+        Error::NotRedundant {
+            step,
+            line: self.origins.line(step),
+            clause: self.external(id, None),
+            rup_only: self.options.rup_only,
+            active_clauses: self.active.iter().filter(|&&active| active).count(),
+            rat_pivot,
+            needs_deleted: self.has_rup_with_deleted(step, id),
+        }
+    }
+
+    // Whether the lemma of the proof step has RUP once the clauses deleted before it are active
+    // again. The check fails after this, so the deleted clauses are not deactivated again.
+    fn has_rup_with_deleted(&mut self, step: usize, id: usize) -> bool {
+        let deleted = self.steps[..step]
+            .iter()
+            .filter_map(|&step| match step {
+                Step::Del(deleted) if !self.active[deleted] => Some(deleted),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if deleted.is_empty() {
+            return false;
+        }
+        for deleted in deleted {
+            self.activate(deleted);
+        }
+        let lemma = self.clauses[id].clone();
+        self.rup(&lemma).is_some()
+    }
+
+    fn need(&mut self, deps: impl IntoIterator<Item = usize>) {
+        for id in deps {
+            self.needed[id] = true;
+        }
+    }
+
+    fn is_true(&self, lit: Literal) -> bool {
+        self.values[lit.code()]
+    }
+
+    fn is_false(&self, lit: Literal) -> bool {
+        self.values[(-lit).code()]
+    }
+
+    // Whether all literals but one of the clause are false, like for deletions in the forward
+    // check.
+    fn is_unit(&self, id: usize) -> bool {
+        self.clauses[id]
+            .iter()
+            .filter(|&&lit| !self.is_false(lit))
+            .count()
+            == 1
+    }
+
+    fn assign(&mut self, lit: Literal, reason: Option<usize>) {
+        self.values[lit.code()] = true;
+        self.reasons[lit.var() as usize] = reason;
+        self.trail.push(lit);
+    }
+
+    fn reset(&mut self) {
+        for lit in self.trail.drain(..) {
+            self.values[lit.code()] = false;
+        }
+        self.core_head = 0;
+        self.head = 0;
+    }
+
+    fn activate(&mut self, id: usize) {
+        self.active[id] = true;
+        if let [a, b, ..] = self.clauses[id][..] {
+            self.watches[a.code()].push(id);
+            self.watches[b.code()].push(id);
+        }
+    }
+
+    // Add a clause while applying the proof, where the assignment only grows. Returns the clause
+    // which is falsified if there is a conflict.
+    fn add(&mut self, id: usize) -> Result<(), usize> {
+        // watch literals which are not false if there are any
+        let mut clause = mem::take(&mut self.clauses[id]);
+        clause.sort_by_key(|&lit| self.is_false(lit));
+        self.clauses[id] = clause;
+        self.activate(id);
+        let clause = &self.clauses[id];
+        match clause.first() {
+            None => return Err(id),
+            Some(&first) if self.is_false(first) => return Err(id),
+            Some(&first) => {
+                let unit = clause.get(1).is_none_or(|&second| self.is_false(second));
+                if unit && !self.is_true(first) {
+                    self.assign(first, Some(id));
+                }
+            }
+        }
+        self.propagate()
+    }
+
+    // Assign the literals implied by the active clauses. With core first, the needed clauses
+    // propagate as far as they can before each literal propagated by the other clauses.
+    fn propagate(&mut self) -> Result<(), usize> {
+        loop {
+            if self.core_first && self.core_head < self.trail.len() {
+                let lit = self.trail[self.core_head];
+                self.core_head += 1;
+                self.propagate_literal(-lit, Some(true))?;
+            } else if self.head < self.trail.len() {
+                let lit = self.trail[self.head];
+                self.head += 1;
+                let only = self.core_first.then_some(false);
+                self.propagate_literal(-lit, only)?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // Look at the clauses watching a literal which has become false, only those which are needed
+    // or not if `only` is given.
+    fn propagate_literal(&mut self, falsified: Literal, only: Option<bool>) -> Result<(), usize> {
+        let mut watchers = mem::take(&mut self.watches[falsified.code()]);
+        let mut result = Ok(());
+        let mut i = 0;
+        while i < watchers.len() {
+            let id = watchers[i];
+            if !self.active[id] {
+                watchers.swap_remove(i);
+                continue;
+            }
+            if only.is_some_and(|needed| needed != self.needed[id]) {
+                i += 1;
+                continue;
+            }
+            let clause = &mut self.clauses[id];
+            if clause[0] == falsified {
+                clause.swap(0, 1);
+            }
+            if clause[1] != falsified {
+                // the clause has been watched twice and the other watch has moved on
+                watchers.swap_remove(i);
+                continue;
+            }
+            let other = clause[0];
+            if self.values[other.code()] {
+                i += 1;
+                continue;
+            }
+            let values = &self.values;
+            if let Some(k) = (2..clause.len()).find(|&k| !values[(-clause[k]).code()]) {
+                clause.swap(1, k);
+                self.watches[clause[1].code()].push(id);
+                watchers.swap_remove(i);
+                continue;
+            }
+            if self.is_false(other) {
+                result = Err(id);
+                break;
+            }
+            self.assign(other, Some(id));
+            i += 1;
+        }
+        self.watches[falsified.code()] = watchers;
+        result
+    }
+
+    // The clauses which imply the conflict in the order they propagated, followed by the
+    // falsified clause.
+    fn analyze(&mut self, conflict: usize) -> Vec<usize> {
+        let mut deps = Vec::new();
+        for lit in &self.clauses[conflict] {
+            self.seen[lit.var() as usize] = true;
+        }
+        for &lit in self.trail.iter().rev() {
+            let var = lit.var() as usize;
+            if !mem::take(&mut self.seen[var]) {
+                continue;
+            }
+            if let Some(reason) = self.reasons[var] {
+                deps.push(reason);
+                for other in &self.clauses[reason] {
+                    if *other != lit {
+                        self.seen[other.var() as usize] = true;
+                    }
+                }
+            }
+        }
+        deps.reverse();
+        deps.push(conflict);
+        deps
+    }
+
+    // The clauses used to derive a conflict from the negated literals, `None` if there is none.
+    fn rup(&mut self, literals: &[Literal]) -> Option<Vec<usize>> {
+        let mut conflict = None;
+        for &lit in literals {
+            if self.is_true(lit) {
+                // the clause is a tautology
+                self.reset();
+                return Some(vec![]);
+            }
+            if !self.is_false(lit) {
+                self.assign(-lit, None);
+            }
+        }
+        // the needed units first, so that they are the reasons if others imply the same literal
+        let passes: &[Option<bool>] = if self.core_first {
+            &[Some(true), Some(false)]
+        } else {
+            &[None]
+        };
+        'units: for &only in passes {
+            for i in 0..self.units.len() {
+                let id = self.units[i];
+                if !self.active[id] || only.is_some_and(|needed| needed != self.needed[id]) {
+                    continue;
+                }
+                let lit = self.clauses[id][0];
+                if self.is_false(lit) {
+                    conflict = Some(id);
+                    break 'units;
+                }
+                if !self.is_true(lit) {
+                    self.assign(lit, Some(id));
+                }
+            }
+        }
+        let conflict = conflict.or_else(|| self.propagate().err());
+        let deps = conflict.map(|conflict| self.analyze(conflict));
+        self.reset();
+        deps
+    }
+
+    // The clauses used to show that the clause is redundant, `None` if it is not.
+    fn redundancy(&mut self, id: usize, rup_only: bool) -> Option<Hints> {
+        let mut lemma = self.clauses[id].clone();
+        if let Some(deps) = self.rup(&lemma) {
+            return Some(Hints::Rup(deps));
+        }
+        if rup_only {
+            return None;
+        }
+        lemma.sort_unstable();
+        for &pivot in &lemma {
+            let candidates = (0..self.clauses.len())
+                .filter(|&c| self.active[c] && self.clauses[c].contains(&-pivot))
+                .collect::<Vec<_>>();
+            let mut resolvents = Vec::new();
+            let rat = candidates.iter().all(|&candidate| {
+                let mut resolvent = lemma.clone();
+                resolvent.extend(
+                    self.clauses[candidate]
+                        .iter()
+                        .filter(|&&lit| lit != -pivot && !lemma.contains(&lit)),
+                );
+                match self.rup(&resolvent) {
+                    Some(used) => {
+                        resolvents.push((candidate, used));
+                        true
+                    }
+                    None => false,
+                }
+            });
+            if rat {
+                return Some(Hints::Rat(pivot, resolvents));
+            }
+        }
+        None
+    }
+}
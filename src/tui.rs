@@ -0,0 +1,197 @@
+//! The dashboard of `ratify watch --tui`. It shows a table with the progress or verdict of every
+//! check that has been started and the overall throughput, instead of one line per verdict. The
+//! dashboard takes over the terminal until q, Esc or Ctrl-C is pressed, which stops the watcher.
+
+use std::{
+    io,
+    path::Path,
+    sync::{
+        mpsc::{Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+
+use crate::{status::Status, Exit};
+
+// How often the dashboard is redrawn.
+const REFRESH: Duration = Duration::from_millis(250);
+
+struct Check {
+    name: String,
+    status: Status,
+    started: Instant,
+    verdict: Receiver<String>,
+    // the first line of the verdict and how long the check took, once it is done
+    done: Option<(String, Duration)>,
+}
+
+/// The checks shown on the dashboard. Clones share the same checks.
+#[derive(Clone)]
+pub struct Dashboard {
+    checks: Arc<Mutex<Vec<Check>>>,
+    started: Instant,
+}
+
+impl Dashboard {
+    /// Take over the terminal and draw the dashboard on its own thread.
+    pub fn start() -> io::Result<Self> {
+        let terminal = ratatui::try_init()?;
+        let dashboard = Dashboard {
+            checks: Arc::default(),
+            started: Instant::now(),
+        };
+        let drawing = dashboard.clone();
+        thread::spawn(move || drawing.run(terminal));
+        Ok(dashboard)
+    }
+
+    /// Show a check whose progress goes to the status and which sends its verdict once it is done.
+    pub fn add(&self, proof: &Path, status: Status, verdict: Receiver<String>) {
+        self.checks.lock().unwrap().push(Check {
+            name: proof.display().to_string(),
+            status,
+            started: Instant::now(),
+            verdict,
+            done: None,
+        });
+    }
+
+    fn run(self, mut terminal: DefaultTerminal) {
+        loop {
+            self.collect();
+            if let Err(e) = terminal.draw(|frame| self.draw(frame)) {
+                ratatui::restore();
+                tracing::error!("cannot draw the dashboard: {}", e);
+                std::process::exit(Exit::InternalError as i32);
+            }
+            if quit_requested() {
+                ratatui::restore();
+                std::process::exit(0);
+            }
+        }
+    }
+
+    // Take the verdicts of the checks which are done.
+    fn collect(&self) {
+        for check in self.checks.lock().unwrap().iter_mut() {
+            if check.done.is_some() {
+                continue;
+            }
+            let verdict = match check.verdict.try_recv() {
+                Ok(verdict) => verdict,
+                Err(TryRecvError::Disconnected) => "e the check failed".to_string(),
+                Err(TryRecvError::Empty) => continue,
+            };
+            check.done = Some((verdict, check.started.elapsed()));
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let checks = self.checks.lock().unwrap();
+        let [summary, table] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(frame.area());
+        let count = |prefix: &str| {
+            checks
+                .iter()
+                .filter(|check| {
+                    check
+                        .done
+                        .as_ref()
+                        .is_some_and(|(verdict, _)| verdict.starts_with(prefix))
+                })
+                .count()
+        };
+        let running = checks.iter().filter(|check| check.done.is_none()).count();
+        let steps = checks
+            .iter()
+            .filter_map(|check| check.status.snapshot().2)
+            .map(|(steps, _, _)| steps)
+            .sum::<u64>();
+        let elapsed = self.started.elapsed().as_secs_f64().max(0.001);
+        let line = format!(
+            "{} running, {} verified, {} not verified, {} failed, {:.0} proof steps/s",
+            running,
+            count("s VERIFIED"),
+            count("s NOT VERIFIED") + count("s MEMORY"),
+            count("e "),
+            steps as f64 / elapsed
+        );
+        frame.render_widget(
+            Paragraph::new(line).block(Block::bordered().title(" ratify watch, q to quit ")),
+            summary,
+        );
+
+        // the most recent checks first
+        let rows = checks.iter().rev().map(|check| {
+            let (progress, time, style) = match &check.done {
+                Some((verdict, time)) => {
+                    let color = if verdict.starts_with("s VERIFIED") {
+                        Color::Green
+                    } else {
+                        Color::Red
+                    };
+                    (verdict.clone(), *time, Style::new().fg(color))
+                }
+                None => (
+                    progress(&check.status),
+                    check.started.elapsed(),
+                    Style::new(),
+                ),
+            };
+            Row::new([
+                check.name.clone(),
+                progress,
+                format!("{:.1}s", time.as_secs_f64()),
+            ])
+            .style(style)
+        });
+        let widths = [
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+            Constraint::Length(10),
+        ];
+        let table_widget = Table::new(rows, widths)
+            .header(Row::new(["proof", "progress", "time"]).bold())
+            .block(Block::bordered().title(Line::from(" checks ")));
+        frame.render_widget(table_widget, table);
+    }
+}
+
+// The phase of a running check, with the proof steps done while checking.
+fn progress(status: &Status) -> String {
+    match status.snapshot() {
+        (phase, _, Some((steps, len, _))) => format!(
+            "{} {}/{} ({:.1}%)",
+            phase,
+            steps,
+            len,
+            steps as f64 * 100.0 / len as f64
+        ),
+        (phase, _, None) => phase.to_string(),
+    }
+}
+
+// Wait for the next redraw and tell whether a key to quit has been pressed in the meantime.
+fn quit_requested() -> bool {
+    let Ok(true) = event::poll(REFRESH) else {
+        return false;
+    };
+    match event::read() {
+        Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+            matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+        }
+        _ => false,
+    }
+}
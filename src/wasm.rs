@@ -0,0 +1,111 @@
+//! JavaScript API of the checker, enabled with the `wasm` feature. The formula and proof are
+//! passed as strings or byte arrays since there is no file system in the browser.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{check_str, CheckReport, CheckerOptions, DeletionPolicy, Mode, Verdict};
+
+/// The options of a check, see `CheckerOptions`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    inner: CheckerOptions,
+}
+
+#[wasm_bindgen]
+impl Options {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the propagator by name, one of "mutating", "immutable", "naive", "head-tail" and
+    /// "auto".
+    #[wasm_bindgen(js_name = setMode)]
+    pub fn set_mode(&mut self, mode: &str) -> Result<(), JsError> {
+        let mode = mode.parse::<Mode>().map_err(|e| JsError::new(&e))?;
+        self.inner = self.inner.clone().mode(mode);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = setRupOnly)]
+    pub fn set_rup_only(&mut self, rup_only: bool) {
+        self.inner = self.inner.clone().rup_only(rup_only);
+    }
+
+    #[wasm_bindgen(js_name = setDrup)]
+    pub fn set_drup(&mut self, drup: bool) {
+        self.inner = self.inner.clone().drup(drup);
+    }
+
+    #[wasm_bindgen(js_name = setCheckAll)]
+    pub fn set_check_all(&mut self, check_all: bool) {
+        self.inner = self.inner.clone().check_all(check_all);
+    }
+
+    #[wasm_bindgen(js_name = setImplicitEmpty)]
+    pub fn set_implicit_empty(&mut self, implicit_empty: bool) {
+        self.inner = self.inner.clone().implicit_empty(implicit_empty);
+    }
+
+    #[wasm_bindgen(js_name = setIgnoreDeletions)]
+    pub fn set_ignore_deletions(&mut self, ignore_deletions: bool) {
+        self.inner = self.inner.clone().deletions(if ignore_deletions {
+            DeletionPolicy::Ignore
+        } else {
+            DeletionPolicy::Apply
+        });
+    }
+}
+
+/// The result of a successful check, see `CheckReport`. Rejected proofs throw an error instead.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct Report {
+    /// Whether all lemmas have been checked, as opposed to a random sample of them.
+    pub complete: bool,
+    pub steps: usize,
+    #[wasm_bindgen(js_name = rupChecks)]
+    pub rup_checks: usize,
+    #[wasm_bindgen(js_name = ratChecks)]
+    pub rat_checks: usize,
+    /// The time spent checking in seconds.
+    #[wasm_bindgen(js_name = checkingSeconds)]
+    pub checking_seconds: f64,
+}
+
+impl From<CheckReport> for Report {
+    fn from(report: CheckReport) -> Self {
+        Report {
+            complete: report.verdict == Verdict::Verified,
+            steps: report.steps,
+            rup_checks: report.rup_checks,
+            rat_checks: report.rat_checks,
+            checking_seconds: report.timings.checking.as_secs_f64(),
+        }
+    }
+}
+
+/// Check a DRAT proof for a formula in DIMACS format. Throws an error if the input cannot be
+/// parsed or the proof is rejected.
+#[wasm_bindgen(js_name = checkDrat)]
+pub fn check_drat(cnf: &str, proof: &str, options: Option<Options>) -> Result<Report, JsError> {
+    let options = options.map(|o| o.inner).unwrap_or_default();
+    let report = check_str(cnf, proof, options)?;
+    if let Some(reason) = &report.reason {
+        return Err(JsError::new(reason));
+    }
+    Ok(report.into())
+}
+
+/// Like `checkDrat`, but for UTF-8 encoded bytes such as the contents of an uploaded file.
+#[wasm_bindgen(js_name = checkDratBytes)]
+pub fn check_drat_bytes(
+    cnf: &[u8],
+    proof: &[u8],
+    options: Option<Options>,
+) -> Result<Report, JsError> {
+    let cnf = std::str::from_utf8(cnf)?;
+    let proof = std::str::from_utf8(proof)?;
+    check_drat(cnf, proof, options)
+}
@@ -0,0 +1,269 @@
+//! `ratify watch` checks the proofs which solvers write into a directory as they arrive. A proof
+//! `<name>.drat` belongs to the formula `<name>.cnf` in the same directory, a proof
+//! `<name>.cnf.drat` to `<name>.cnf`. Once a proof has not changed for one polling interval it is
+//! checked on its own thread and the verdict is written to `<name>.result`:
+//!
+//! - `s VERIFIED` or `s VERIFIED (probabilistic)` if the proof has been verified.
+//! - `s NOT VERIFIED` followed by `c <reason>` if it has been rejected.
+//! - `s MEMORY LIMIT EXCEEDED` with --max-memory.
+//! - `e <message>` if the formula or proof cannot be read.
+//!
+//! Proofs are skipped if their result is newer than they are, so restarting the watcher does not
+//! check them again. A proof which is written again is checked again.
+//!
+//! The proofs found at the same time are checked in the order of their names, at most --threads
+//! of them at once, and their verdicts are printed in that order as well, however long each check
+//! takes. Every proof is checked on its own, so the verdicts and result files do not depend on
+//! the number of threads, and with --threads 1 the proofs are checked one after another. With
+//! --tui they are shown on a dashboard instead, see [`crate::tui`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use ratify::{check_drat, CheckReport, CheckerOptions, Verdict};
+
+use crate::{status::Status, Flags};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct Args {
+    #[command(flatten)]
+    flags: Flags,
+    #[arg(long, value_name = "SECONDS", default_value_t = 1)]
+    /// How often to look for new proofs. A proof is checked once it has not changed for this
+    /// long.
+    interval: u64,
+    #[arg(long, value_name = "EXT", default_value = "drat")]
+    /// The file extension of the proofs.
+    extension: String,
+    #[arg(long, value_name = "N")]
+    /// Check at most this many proofs at once. Defaults to the number of CPUs.
+    threads: Option<usize>,
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    /// Show the progress and verdicts of the checks and the overall throughput on a dashboard
+    /// instead of printing the verdicts. Press q to quit.
+    tui: bool,
+    /// The directory containing the formulas, into which the proofs are written.
+    dir: PathBuf,
+}
+
+// The size and modification time of a proof when it has been looked at.
+type Snapshot = (u64, SystemTime);
+
+pub fn run(args: Args) -> Result<()> {
+    // several proofs may be checked at once, their progress bars would overlap
+    let options = args.flags.options()?.progress(false);
+    let interval = Duration::from_secs(args.interval);
+    let slots = Arc::new(Slots::new(args.threads.unwrap_or_else(|| {
+        thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+    })));
+    let mut seen: HashMap<PathBuf, Snapshot> = HashMap::new();
+    // the modification time of the proofs when they were checked
+    let mut checked: HashMap<PathBuf, SystemTime> = HashMap::new();
+    // the checks whose verdicts have not been printed yet, in the order they were started
+    let mut running: VecDeque<(PathBuf, Receiver<String>)> = VecDeque::new();
+    #[cfg(feature = "tui")]
+    let dashboard = match args.tui {
+        true => Some(crate::tui::Dashboard::start()?),
+        false => None,
+    };
+    tracing::info!("watching {}", args.dir.display());
+    loop {
+        let mut current = HashMap::new();
+        // the order of the directory entries depends on the file system
+        let mut proofs = fs::read_dir(&args.dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        proofs.sort();
+        for proof in proofs {
+            if proof.extension() != Some(OsStr::new(&args.extension)) {
+                continue;
+            }
+            // the proof may have been removed in the meantime
+            let Ok(metadata) = fs::metadata(&proof) else {
+                continue;
+            };
+            let snapshot = (metadata.len(), metadata.modified()?);
+            let (cnf, result) = instance(&proof);
+            let unchanged = seen.get(&proof) == Some(&snapshot);
+            if unchanged
+                && checked.get(&proof) != Some(&snapshot.1)
+                && cnf.is_file()
+                && !is_newer(&result, snapshot.1)
+            {
+                checked.insert(proof.clone(), snapshot.1);
+                let status = Status::new(false);
+                let options = options.clone().progress_sink(status.clone());
+                let slot = (slots.clone(), slots.ticket());
+                let verdict = spawn(proof.clone(), cnf, result, options, slot);
+                #[cfg(feature = "tui")]
+                if let Some(dashboard) = &dashboard {
+                    dashboard.add(&proof, status, verdict);
+                    current.insert(proof, snapshot);
+                    continue;
+                }
+                running.push_back((proof.clone(), verdict));
+            }
+            current.insert(proof, snapshot);
+        }
+        seen = current;
+        thread::sleep(interval);
+        print_finished(&mut running);
+    }
+}
+
+// Print the verdicts of the checks which are done, up to the first one which is still running.
+fn print_finished(running: &mut VecDeque<(PathBuf, Receiver<String>)>) {
+    while let Some((proof, verdict)) = running.front() {
+        match verdict.try_recv() {
+            Ok(verdict) => println!("c {}: {}", proof.display(), verdict),
+            // a check which panicked is not waited for
+            Err(TryRecvError::Disconnected) => {}
+            Err(TryRecvError::Empty) => return,
+        }
+        running.pop_front();
+    }
+}
+
+// The formula and result file belonging to a proof.
+fn instance(proof: &Path) -> (PathBuf, PathBuf) {
+    let stem = proof.with_extension("");
+    let name = match stem.extension() {
+        Some(extension) if extension == "cnf" => stem.with_extension(""),
+        _ => stem,
+    };
+    (name.with_extension("cnf"), name.with_extension("result"))
+}
+
+fn is_newer(path: &Path, than: SystemTime) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified >= than)
+}
+
+// Limits how many proofs are checked at once. Checks start in the order their tickets have been
+// taken, not in the order their threads happen to wake up.
+struct Slots {
+    threads: usize,
+    // the tickets taken, the checks started and the checks which are running
+    state: Mutex<(usize, usize, usize)>,
+    changed: Condvar,
+}
+
+impl Slots {
+    fn new(threads: usize) -> Self {
+        Slots {
+            threads: threads.max(1),
+            state: Mutex::new((0, 0, 0)),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn ticket(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.0 += 1;
+        state.0 - 1
+    }
+
+    // Wait until the check with this ticket may start, it ends once the guard is dropped.
+    fn acquire(&self, ticket: usize) -> SlotGuard<'_> {
+        let mut state = self.state.lock().unwrap();
+        while state.1 != ticket || state.2 >= self.threads {
+            state = self.changed.wait(state).unwrap();
+        }
+        state.1 += 1;
+        state.2 += 1;
+        self.changed.notify_all();
+        SlotGuard(self)
+    }
+}
+
+// Frees the slot of a check even if it panics.
+struct SlotGuard<'a>(&'a Slots);
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.2 -= 1;
+        self.0.changed.notify_all();
+    }
+}
+
+/// Check the proofs like the watcher does, at most `threads` of them at once, and wait until all
+/// their result files are written. `ratify selftest` uses it to compare the result files of
+/// different numbers of threads.
+pub fn check_batch(proofs: &[PathBuf], options: &CheckerOptions, threads: usize) {
+    let slots = Arc::new(Slots::new(threads));
+    let verdicts = proofs
+        .iter()
+        .map(|proof| {
+            let (cnf, result) = instance(proof);
+            let slot = (slots.clone(), slots.ticket());
+            spawn(proof.clone(), cnf, result, options.clone(), slot)
+        })
+        .collect::<Vec<_>>();
+    for verdict in verdicts {
+        // a check which panicked has no result file, which the comparison finds
+        let _ = verdict.recv();
+    }
+}
+
+// Check the proof on its own thread once it gets a slot, the thread sends the first line of its
+// verdict once it is done.
+fn spawn(
+    proof: PathBuf,
+    cnf: PathBuf,
+    result: PathBuf,
+    options: CheckerOptions,
+    (slots, ticket): (Arc<Slots>, usize),
+) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _slot = slots.acquire(ticket);
+        let text = match check_drat(&cnf.to_string_lossy(), &proof.to_string_lossy(), options) {
+            Ok(report) => verdict(&report),
+            Err(e) => format!("e {}\n", e),
+        };
+        if let Err(e) = write_result(&result, &text) {
+            tracing::error!("cannot write {}: {}", result.display(), e);
+        }
+        let _ = sender.send(text.lines().next().unwrap_or_default().to_string());
+    });
+    receiver
+}
+
+/// The first lines of a result file for the report.
+pub fn verdict(report: &CheckReport) -> String {
+    match report.verdict {
+        Verdict::Verified => "s VERIFIED\n".to_string(),
+        Verdict::VerifiedProbabilistic => "s VERIFIED (probabilistic)\n".to_string(),
+        Verdict::VerifiedPartially => "s PARTIALLY VERIFIED\n".to_string(),
+        // the checks are not cancelled
+        Verdict::Cancelled { .. } => "e the check has been cancelled\n".to_string(),
+        Verdict::MemoryLimitExceeded { phase } => {
+            format!("s MEMORY LIMIT EXCEEDED\nc during {}\n", phase)
+        }
+        Verdict::Refuted { .. } | Verdict::NoConflict | Verdict::EarlyRefutation => format!(
+            "s NOT VERIFIED\nc {}\n",
+            report.reason.as_deref().unwrap_or_default()
+        ),
+    }
+}
+
+// Write the result next to it first, so that it never appears partially written.
+fn write_result(result: &Path, text: &str) -> std::io::Result<()> {
+    let partial = result.with_extension("result.partial");
+    fs::write(&partial, text)?;
+    fs::rename(&partial, result)
+}